@@ -1,6 +1,20 @@
 use crate::models::theme::Theme;
-use crate::services::download_service::{DownloadProgress, DownloadRequest, DownloadService};
+use crate::services::animation::{AnimationOptions, RevealMode};
+use crate::services::canvas::{CanvasPreset, MIN_FIT_FONT_SCALE};
+use crate::services::download_service::{DownloadProgress, DownloadRequest};
 use crate::services::export_service::{EnhancedExportOptions, ExportService};
+use crate::services::image_generator::{InputMode, DEFAULT_BACKDROP_MARGIN};
+use crate::services::annotations::{AnnotationKind, MAX_ANNOTATIONS, MAX_NOTE_LENGTH};
+use crate::services::line_ranges::{LineRangeMode, MAX_LINE_RANGES};
+use crate::services::redaction::{
+    RedactionOptions, RedactionStyle, BUILT_IN_RULES, HIGH_ENTROPY_RULE, MAX_CUSTOM_RULES,
+    MAX_RULE_PATTERN_LENGTH,
+};
+use crate::services::shell::{
+    ShellOptions, DEFAULT_PROMPT_PATTERN, MAX_PROMPT_PATTERN_LENGTH, MAX_SEGMENT_PART_LENGTH,
+};
+use crate::services::theme_manager::ThemeManager;
+use crate::services::wrap::WrapMode;
 use crate::AppState;
 use axum::{
     body::Body,
@@ -16,15 +30,23 @@ use serde_json::{json, Value};
 pub struct GenerateRequest {
     pub code: String,
     pub language: String,
-    // Accept a theme identifier from the frontend (e.g. "default", "light").
-    // We'll map this to a Theme on the server to keep the API simple for the client.
-    pub theme: String,
+    // Accept either a theme identifier (e.g. "dracula", "light") or a full theme
+    // object, such as the result of /api/themes/customize.
+    pub theme: ThemeSelection,
     // Accept either `export_options` or the alias `options` from the client.
-    // Be tolerant: allow the client to omit options or send an arbitrary JSON object.
+    // Omitted options use the defaults; fields left out of the object do too.
     #[serde(default, alias = "options")]
     pub export_options: Option<serde_json::Value>,
 }
 
+/// Theme selection sent by the client: a known theme ID or a complete custom theme
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ThemeSelection {
+    Id(String),
+    Custom(Box<Theme>),
+}
+
 /// Response for starting a download
 #[derive(Debug, Serialize)]
 pub struct GenerateResponse {
@@ -67,44 +89,25 @@ pub async fn generate_image(
         ));
     }
 
-    // Map theme id (string) to Theme defaults.
-    // This accepts simple theme identifiers from the frontend and maps them to
-    // one of the available Theme factory methods. Unknown identifiers fall back to dark.
-    let theme_obj = match request.theme.as_str() {
-        // Common light identifiers
-        "light" | "default-light" | "default_light" | "light-theme" | "github" => {
-            Theme::default_light()
-        }
-        // Common dark identifiers (explicit)
-        "dark" | "default" | "default-dark" | "default_dark" | "dracula" | "monokai" => {
-            Theme::default_dark()
-        }
-        // Fallback
-        _ => Theme::default_dark(),
-    };
+    let theme_obj = resolve_theme(request.theme).await?;
 
     // Use the shared DownloadService from AppState (initialized in main).
     // This ensures progress and metadata persist across requests.
     let download_service = app_state.download_service.clone();
 
-    // Create download request using the mapped Theme value
-    // Create download request
     // Map the incoming optional JSON `export_options` to the strongly-typed EnhancedExportOptions.
-    // If the client omitted options or provided invalid content, fall back to defaults.
+    // Omitted fields take their defaults; options that don't match the schema are rejected
+    // rather than silently replaced, so a mistyped field never produces a different image.
     let export_options: EnhancedExportOptions = match request.export_options {
-        Some(val) => {
-            // Try to deserialize into EnhancedExportOptions; if that fails, fallback to default
-            match serde_json::from_value::<EnhancedExportOptions>(val) {
-                Ok(opts) => opts,
-                Err(err) => {
-                    tracing::warn!(
-                        "Failed to parse export_options from request, falling back to default: {}",
-                        err
-                    );
-                    EnhancedExportOptions::default()
-                }
-            }
-        }
+        Some(val) => serde_json::from_value(val).map_err(|err| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": "Invalid export options",
+                    "message": err.to_string()
+                })),
+            )
+        })?,
         None => EnhancedExportOptions::default(),
     };
 
@@ -147,6 +150,44 @@ pub async fn generate_image(
     Ok(Json(response))
 }
 
+/// Resolves the requested theme to a Theme value
+pub(crate) async fn resolve_theme(selection: ThemeSelection) -> Result<Theme, (StatusCode, Json<Value>)> {
+    let theme_id = match selection {
+        ThemeSelection::Custom(theme) => {
+            theme.validate().map_err(|e| {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({
+                        "error": "Invalid theme",
+                        "message": e
+                    })),
+                )
+            })?;
+            return Ok(*theme);
+        }
+        ThemeSelection::Id(id) => id,
+    };
+
+    // Built-in themes are looked up by their ID first
+    let theme_manager = ThemeManager::new();
+    if let Some(theme) = theme_manager.get_theme(&theme_id).await {
+        return Ok(theme);
+    }
+
+    // Map simple identifiers from the frontend to Theme defaults.
+    // Unknown identifiers fall back to dark.
+    let theme = match theme_id.as_str() {
+        // Common light identifiers
+        "light" | "default-light" | "default_light" | "light-theme" | "github" => {
+            Theme::default_light()
+        }
+        // Fallback
+        _ => Theme::default_dark(),
+    };
+
+    Ok(theme)
+}
+
 /// Check download progress
 pub async fn check_progress(
     State(app_state): State<AppState>,
@@ -246,7 +287,74 @@ pub async fn get_export_options() -> Json<Value> {
         "quality_range": {
             "min": 1,
             "max": 100,
-            "default": 90
+            "default": 90,
+            "formats": ExportService::quality_formats()
+        },
+        "lossless_formats": ExportService::lossless_formats(),
+        "animation": {
+            "formats": ExportService::animated_formats(),
+            "reveal_modes": [RevealMode::Character, RevealMode::Line],
+            "defaults": AnimationOptions::default()
+        },
+        "line_ranges": {
+            "modes": [
+                LineRangeMode::Highlight,
+                LineRangeMode::Focus,
+                LineRangeMode::Added,
+                LineRangeMode::Removed
+            ],
+            "max": MAX_LINE_RANGES
+        },
+        "annotations": {
+            "kinds": [
+                AnnotationKind::Marker,
+                AnnotationKind::Arrow,
+                AnnotationKind::Region,
+                AnnotationKind::Note
+            ],
+            "max": MAX_ANNOTATIONS,
+            "max_note_length": MAX_NOTE_LENGTH
+        },
+        "redaction": {
+            "styles": [RedactionStyle::Solid, RedactionStyle::Blur],
+            "built_in_rules": BUILT_IN_RULES
+                .iter()
+                .map(|(name, _)| *name)
+                .chain(std::iter::once(HIGH_ENTROPY_RULE))
+                .collect::<Vec<_>>(),
+            "max_custom_rules": MAX_CUSTOM_RULES,
+            "max_rule_pattern_length": MAX_RULE_PATTERN_LENGTH,
+            "defaults": RedactionOptions::default()
+        },
+        "input_modes": {
+            "modes": [InputMode::Code, InputMode::Diff, InputMode::Shell, InputMode::Ansi],
+            "word_diff": true,
+            "shell": {
+                "default_prompt_pattern": DEFAULT_PROMPT_PATTERN,
+                "max_prompt_pattern_length": MAX_PROMPT_PATTERN_LENGTH,
+                "max_segment_part_length": MAX_SEGMENT_PART_LENGTH,
+                "defaults": ShellOptions::default()
+            }
+        },
+        "wrap_modes": [WrapMode::Wrap, WrapMode::Widen],
+        "canvas_presets": CanvasPreset::ALL
+            .iter()
+            .map(|preset| {
+                let (width, height) = preset.size();
+                json!({
+                    "preset": preset,
+                    "label": preset.label(),
+                    "width": width,
+                    "height": height
+                })
+            })
+            .collect::<Vec<_>>(),
+        "min_fit_font_scale": MIN_FIT_FONT_SCALE,
+        "spacing": {
+            "padding": ["top", "right", "bottom", "left"],
+            "margin": ["top", "right", "bottom", "left"],
+            "max": ExportService::MAX_SPACING,
+            "default_backdrop_margin": DEFAULT_BACKDROP_MARGIN
         },
         "compression_levels": {
             "png": {
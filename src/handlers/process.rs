@@ -6,7 +6,14 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use crate::AppState;
+use crate::handlers::generate::{resolve_theme, ThemeSelection};
+use crate::models::theme::Theme;
+use crate::services::download_service::DownloadRequest;
+use crate::services::export_service::EnhancedExportOptions;
+use crate::services::image_generator::InputMode;
 use crate::services::language_detector::{LanguageDetector, LanguageResult};
+use crate::services::redaction::{Finding, RedactionOptions};
+use crate::services::shell::ShellOptions;
 
 /// Request to process text input
 #[derive(Debug, Deserialize)]
@@ -15,6 +22,17 @@ pub struct ProcessRequest {
     pub code: String,
     /// Optional language hint (if not provided, will be auto-detected)
     pub language: Option<String>,
+    /// Rules for the secrets scan, the same options image generation takes
+    #[serde(default)]
+    pub redaction: RedactionOptions,
+    /// How the code will be shown, so findings are positioned in the lines as rendered
+    #[serde(default)]
+    pub input_mode: InputMode,
+    #[serde(default)]
+    pub shell: ShellOptions,
+    /// Theme the code will be rendered with; its window title and tab names are scanned too
+    #[serde(default)]
+    pub theme: Option<ThemeSelection>,
 }
 
 /// Response from text processing
@@ -26,11 +44,14 @@ pub struct ProcessResponse {
     pub formatted: bool,
     pub line_count: usize,
     pub character_count: usize,
+    /// Secrets and personal data that will be redacted when the code is rendered, by
+    /// line and column of the rendered snippet
+    pub redactions: Vec<Finding>,
 }
 
 /// Process text input with syntax highlighting and language detection
 pub async fn process_text(
-    State(_app_state): State<AppState>,
+    State(app_state): State<AppState>,
     Json(request): Json<ProcessRequest>,
 ) -> Result<Json<ProcessResponse>, (StatusCode, Json<Value>)> {
     // Validate input
@@ -68,6 +89,32 @@ pub async fn process_text(
         language_detector.detect_language(&request.code)
     };
 
+    // Scan for secrets the rendered image will hide, in the lines it will show
+    let theme = match request.theme {
+        Some(selection) => resolve_theme(selection).await?,
+        None => Theme::default_dark(),
+    };
+    let scan_request = DownloadRequest {
+        code: request.code.clone(),
+        language: language_result.language.clone(),
+        theme,
+        export_options: EnhancedExportOptions {
+            input_mode: request.input_mode,
+            shell: request.shell,
+            redaction: request.redaction,
+            ..Default::default()
+        },
+    };
+    let redactions = app_state.download_service.find_redactions(&scan_request).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Secrets scan failed",
+                "message": e.to_string()
+            }))
+        )
+    })?;
+
     // Calculate statistics
     let line_count = request.code.lines().count();
     let character_count = request.code.chars().count();
@@ -76,10 +123,11 @@ pub async fn process_text(
     let formatted_code = request.code.clone();
 
     tracing::info!(
-        "Processed text input: {} lines, {} characters, detected language: {}",
+        "Processed text input: {} lines, {} characters, detected language: {}, {} redactions",
         line_count,
         character_count,
-        language_result.language
+        language_result.language,
+        redactions.len()
     );
 
     Ok(Json(ProcessResponse {
@@ -89,6 +137,7 @@ pub async fn process_text(
         formatted: true,
         line_count,
         character_count,
+        redactions,
     }))
 }
 
@@ -7,7 +7,10 @@ use serde::Deserialize;
 use serde_json::{json, Value};
 use crate::AppState;
 use crate::services::theme_manager::{ThemeManager, ThemeCustomization};
-use crate::models::theme::Theme;
+use crate::models::theme::{
+    BackgroundImage, BorderStyle, GlassStyle, GradientStyle, OverlayKind, PatternStyle,
+    ShadowStyle, Theme, TypographyStyle, WindowStyleType, WindowTab,
+};
 
 /// Get all available themes
 pub async fn list_themes(
@@ -174,15 +177,67 @@ pub async fn get_customization_options(
     let theme_manager = ThemeManager::new();
 
     let fonts = theme_manager.get_available_fonts();
+    let font_features = theme_manager.get_available_font_features();
     let window_styles = theme_manager.get_available_window_styles();
     let background_types = theme_manager.get_available_background_types();
+    let gradient_kinds = theme_manager.get_available_gradient_kinds();
+    let pattern_kinds = theme_manager.get_available_pattern_kinds();
+    let image_fits = theme_manager.get_available_image_fits();
 
     Ok(Json(json!({
         "success": true,
         "options": {
             "fonts": fonts,
+            "font_features": font_features,
             "window_styles": window_styles,
             "background_types": background_types,
+            "gradient": {
+                "kinds": gradient_kinds,
+                "angle_range": {
+                    "min": 0.0,
+                    "max": 360.0,
+                    "default": GradientStyle::default_angle()
+                },
+                "center_range": {
+                    "min": 0.0,
+                    "max": 1.0,
+                    "default": GradientStyle::default_center()
+                },
+                "max_stops": GradientStyle::MAX_STOPS,
+                "dithered": true
+            },
+            "pattern": {
+                "kinds": pattern_kinds,
+                "scale_range": {
+                    "min": PatternStyle::MIN_SCALE,
+                    "max": PatternStyle::MAX_SCALE,
+                    "default": PatternStyle::default_scale()
+                },
+                "rotation_range": {
+                    "min": 0.0,
+                    "max": 360.0,
+                    "default": 0.0
+                },
+                "opacity_range": {
+                    "min": 0.0,
+                    "max": 1.0,
+                    "default": PatternStyle::default_opacity()
+                }
+            },
+            "image": {
+                "upload_url": "/api/upload?purpose=background",
+                "fits": image_fits,
+                "blur_range": {
+                    "min": 0.0,
+                    "max": BackgroundImage::MAX_BLUR,
+                    "default": 0.0
+                },
+                "overlays": [OverlayKind::Darken, OverlayKind::Lighten],
+                "overlay_range": {
+                    "min": 0.0,
+                    "max": 1.0
+                }
+            },
             "color_format": "Hex color codes (#RRGGBB or #RGB)",
             "opacity_range": {
                 "min": 0.0,
@@ -197,6 +252,40 @@ pub async fn get_customization_options(
                 "min": 1.0,
                 "max": 3.0,
                 "default": 1.5
+            },
+            "tab_width_range": {
+                "min": 1,
+                "max": TypographyStyle::MAX_TAB_WIDTH,
+                "default": TypographyStyle::default_tab_width()
+            },
+            "shadow": {
+                "blur_range": {
+                    "min": 0.0,
+                    "max": ShadowStyle::MAX_BLUR_RADIUS
+                },
+                "color_format": "Hex color codes with optional alpha (#RRGGBBAA)",
+                "defaults": ShadowStyle::default()
+            },
+            "glass": {
+                "blur_range": {
+                    "min": 0.0,
+                    "max": GlassStyle::MAX_BLUR,
+                    "default": GlassStyle::default_blur()
+                },
+                "noise_range": {
+                    "min": 0.0,
+                    "max": 1.0,
+                    "default": 0.0
+                },
+                "highlight_width_range": {
+                    "min": 0.0,
+                    "max": BorderStyle::MAX_WIDTH
+                }
+            },
+            "tabs": {
+                "window_styles": [WindowStyleType::VSCode, WindowStyleType::JetBrains],
+                "max_tabs": WindowTab::MAX_TABS,
+                "max_name_length": WindowTab::MAX_NAME_LENGTH
             }
         }
     })))
@@ -1,18 +1,40 @@
+use crate::services::background_images::MAX_IMAGE_SIDE;
+#[cfg(feature = "tesseract")]
 use crate::services::ocr::OCRService;
 use crate::AppState;
 use axum::{
     body::Body,
-    extract::{Request, State},
+    extract::{Query, Request, State},
     http::StatusCode,
     response::Json,
 };
 use futures_util::TryStreamExt;
 use multer::Multipart;
+use serde::Deserialize;
 use serde_json::{json, Value};
 
+/// Query parameters of an upload
+#[derive(Debug, Default, Deserialize)]
+pub struct UploadQuery {
+    #[serde(default)]
+    pub purpose: UploadPurpose,
+}
+
+/// What an uploaded image is for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UploadPurpose {
+    /// A screenshot of code, read with OCR
+    #[default]
+    Code,
+    /// A picture for themes to use as an `Image` background
+    Background,
+}
+
 /// Handle multipart file upload for images
 pub async fn upload_image(
     State(app_state): State<AppState>,
+    Query(query): Query<UploadQuery>,
     request: Request<Body>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     let boundary = request
@@ -34,7 +56,7 @@ pub async fn upload_image(
     let stream = request
         .into_body()
         .into_data_stream()
-        .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err));
+        .map_err(std::io::Error::other);
 
     let mut multipart = Multipart::new(stream, boundary);
     let mut uploaded_files = Vec::new();
@@ -103,6 +125,12 @@ pub async fn upload_image(
                 ));
             }
 
+            // Wallpapers must decode, since they're drawn rather than read for code
+            let dimensions = match query.purpose {
+                UploadPurpose::Background => Some(background_dimensions(&data)?),
+                UploadPurpose::Code => None,
+            };
+
             // Store the file
             let file_id = app_state
                 .storage
@@ -128,69 +156,74 @@ pub async fn upload_image(
                 "extension": extension
             });
 
-            // Only attempt OCR if tesseract feature is enabled
-            #[cfg(feature = "tesseract")]
-            {
-                tracing::info!("Starting OCR processing for file: {}", file_id);
-                match OCRService::new() {
-                    Ok(ocr_service) => match ocr_service.extract_and_process(&data).await {
-                        Ok(processed_result) => {
-                            tracing::info!(
-                                    "OCR completed for file {}: confidence={:.2}%, text_length={}, needs_review={}",
-                                    file_id,
-                                    processed_result.ocr_result.confidence * 100.0,
-                                    processed_result.ocr_result.text.len(),
-                                    processed_result.validation.needs_review
-                                );
+            if let Some((width, height)) = dimensions {
+                file_info["width"] = json!(width);
+                file_info["height"] = json!(height);
+            } else {
+                // Only attempt OCR if tesseract feature is enabled
+                #[cfg(feature = "tesseract")]
+                {
+                    tracing::info!("Starting OCR processing for file: {}", file_id);
+                    match OCRService::new() {
+                        Ok(ocr_service) => match ocr_service.extract_and_process(&data).await {
+                            Ok(processed_result) => {
+                                tracing::info!(
+                                        "OCR completed for file {}: confidence={:.2}%, text_length={}, needs_review={}",
+                                        file_id,
+                                        processed_result.ocr_result.confidence * 100.0,
+                                        processed_result.ocr_result.text.len(),
+                                        processed_result.validation.needs_review
+                                    );
 
-                            file_info["ocr"] = json!({
-                                "success": true,
-                                "text": processed_result.ocr_result.text,
-                                "confidence": processed_result.ocr_result.confidence,
-                                "detected_language": processed_result.ocr_result.detected_language,
-                                "needs_review": processed_result.validation.needs_review,
-                                "validation": {
-                                    "is_valid": processed_result.validation.is_valid,
-                                    "issues": processed_result.validation.issues,
-                                    "suggestions": processed_result.validation.suggestions
-                                }
-                            });
-                        }
+                                file_info["ocr"] = json!({
+                                    "success": true,
+                                    "text": processed_result.ocr_result.text,
+                                    "confidence": processed_result.ocr_result.confidence,
+                                    "detected_language": processed_result.ocr_result.detected_language,
+                                    "needs_review": processed_result.validation.needs_review,
+                                    "validation": {
+                                        "is_valid": processed_result.validation.is_valid,
+                                        "issues": processed_result.validation.issues,
+                                        "suggestions": processed_result.validation.suggestions
+                                    }
+                                });
+                            }
+                            Err(e) => {
+                                tracing::warn!("OCR processing failed for file {}: {}", file_id, e);
+                                file_info["ocr"] = json!({
+                                    "success": false,
+                                    "error": "OCR processing failed",
+                                    "message": "Text extraction encountered an error. Please manually enter your code.",
+                                    "confidence": 0.0,
+                                    "details": e.to_string()
+                                });
+                            }
+                        },
                         Err(e) => {
-                            tracing::warn!("OCR processing failed for file {}: {}", file_id, e);
+                            tracing::warn!("Failed to initialize OCR service: {}", e);
                             file_info["ocr"] = json!({
                                 "success": false,
-                                "error": "OCR processing failed",
-                                "message": "Text extraction encountered an error. Please manually enter your code.",
+                                "error": "OCR service unavailable",
+                                "message": "Text extraction is not available. Please manually enter your code.",
                                 "confidence": 0.0,
                                 "details": e.to_string()
                             });
                         }
-                    },
-                    Err(e) => {
-                        tracing::warn!("Failed to initialize OCR service: {}", e);
-                        file_info["ocr"] = json!({
-                            "success": false,
-                            "error": "OCR service unavailable",
-                            "message": "Text extraction is not available. Please manually enter your code.",
-                            "confidence": 0.0,
-                            "details": e.to_string()
-                        });
                     }
                 }
-            }
 
-            // If tesseract feature is not enabled, inform the user
-            #[cfg(not(feature = "tesseract"))]
-            {
-                tracing::info!("OCR feature not enabled for file: {}", file_id);
-                file_info["ocr"] = json!({
-                    "success": false,
-                    "error": "OCR not available",
-                    "message": "Text extraction from images is not enabled. Please manually enter your code or enable the OCR feature.",
-                    "confidence": 0.0,
-                    "help": "To enable OCR, rebuild the application with: cargo build --features tesseract"
-                });
+                // If tesseract feature is not enabled, inform the user
+                #[cfg(not(feature = "tesseract"))]
+                {
+                    tracing::info!("OCR feature not enabled for file: {}", file_id);
+                    file_info["ocr"] = json!({
+                        "success": false,
+                        "error": "OCR not available",
+                        "message": "Text extraction from images is not enabled. Please manually enter your code or enable the OCR feature.",
+                        "confidence": 0.0,
+                        "help": "To enable OCR, rebuild the application with: cargo build --features tesseract"
+                    });
+                }
             }
 
             uploaded_files.push(file_info);
@@ -221,6 +254,44 @@ pub async fn upload_image(
     })))
 }
 
+/// Decodes a background picture to check it draws, returning its size. Truncated or
+/// corrupt files, and pictures larger than `MAX_IMAGE_SIDE` on a side, are rejected.
+fn background_dimensions(data: &[u8]) -> Result<(u32, u32), (StatusCode, Json<Value>)> {
+    let mut limits = image::io::Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_SIDE);
+    limits.max_image_height = Some(MAX_IMAGE_SIDE);
+
+    let decoded = image::io::Reader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .map_err(image::ImageError::IoError)
+        .and_then(|mut reader| {
+            reader.limits(limits);
+            reader.decode()
+        });
+
+    match decoded {
+        Ok(image) if image.width() > 0 && image.height() > 0 => Ok((image.width(), image.height())),
+        Err(image::ImageError::Limits(_)) => Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "Image too large",
+                "message": format!(
+                    "Background images must be at most {} pixels on each side",
+                    MAX_IMAGE_SIDE
+                ),
+                "max_side": MAX_IMAGE_SIDE
+            })),
+        )),
+        _ => Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(json!({
+                "error": "Invalid image",
+                "message": "The background image could not be decoded"
+            })),
+        )),
+    }
+}
+
 /// Validate image format based on magic bytes and content type
 fn validate_image_format(
     data: &[u8],
@@ -235,7 +306,7 @@ fn validate_image_format(
     let is_jpeg = data.starts_with(&[0xFF, 0xD8, 0xFF]);
 
     // Also check content type as secondary validation
-    let content_type_valid = content_type.map_or(false, |ct| {
+    let content_type_valid = content_type.is_some_and(|ct| {
         ct == "image/png" || ct == "image/jpeg" || ct == "image/jpg"
     });
 
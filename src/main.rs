@@ -156,8 +156,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             "/api/upload",
             axum::routing::post(
                 |state: axum::extract::State<AppState>,
+                 query: axum::extract::Query<handlers::upload::UploadQuery>,
                  req: axum::http::Request<axum::body::Body>| async move {
-                    handlers::upload::upload_image(state, req).await
+                    handlers::upload::upload_image(state, query, req).await
                 },
             ),
         )
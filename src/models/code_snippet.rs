@@ -96,13 +96,13 @@ impl CodeSnippet {
 
         // Validate confidence scores if present
         if let Some(lang_conf) = self.metadata.detected_language_confidence {
-            if lang_conf < 0.0 || lang_conf > 1.0 {
+            if !(0.0..=1.0).contains(&lang_conf) {
                 return Err("Language confidence must be between 0.0 and 1.0".to_string());
             }
         }
 
         if let Some(ocr_conf) = self.metadata.ocr_confidence {
-            if ocr_conf < 0.0 || ocr_conf > 1.0 {
+            if !(0.0..=1.0).contains(&ocr_conf) {
                 return Err("OCR confidence must be between 0.0 and 1.0".to_string());
             }
         }
@@ -1,11 +1,20 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Theme {
     pub id: String,
     pub name: String,
     pub background: BackgroundStyle,
+    /// Canvas painted around the window; without one the area outside the window is transparent
+    #[serde(default)]
+    pub backdrop: Option<BackgroundStyle>,
     pub syntax: SyntaxColors,
+    /// Bold, italic and underline per token kind; plain unless set
+    #[serde(default)]
+    pub font_styles: SyntaxFontStyles,
+    /// The 16 colors ANSI escape codes name in terminal output
+    #[serde(default)]
+    pub ansi_colors: AnsiPalette,
     pub window: WindowStyle,
     pub typography: TypographyStyle,
 }
@@ -16,6 +25,16 @@ pub struct BackgroundStyle {
     pub primary: String,
     pub secondary: Option<String>,
     pub opacity: f32,
+    /// Shape and stops of a `Gradient` background; without one, primary fades into
+    /// secondary from top to bottom
+    #[serde(default)]
+    pub gradient: Option<GradientStyle>,
+    /// Pattern drawn over the primary color of a `Pattern` background; without one, dots
+    #[serde(default)]
+    pub pattern: Option<PatternStyle>,
+    /// Uploaded picture filling an `Image` background
+    #[serde(default)]
+    pub image: Option<BackgroundImage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -23,6 +42,120 @@ pub enum BackgroundType {
     Solid,
     Gradient,
     Pattern,
+    /// A picture uploaded through `/api/upload`, drawn over the primary color
+    Image,
+}
+
+/// A gradient with any number of color stops, following CSS gradient semantics
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GradientStyle {
+    #[serde(default)]
+    pub kind: GradientKind,
+    /// Direction of a linear gradient, or where a conic gradient starts, in degrees
+    /// clockwise from the top (180 runs top to bottom)
+    #[serde(default = "GradientStyle::default_angle")]
+    pub angle: f32,
+    /// Center of radial and conic gradients as fractions (0.0-1.0) of the width and height
+    #[serde(default = "GradientStyle::default_center")]
+    pub center_x: f32,
+    #[serde(default = "GradientStyle::default_center")]
+    pub center_y: f32,
+    pub stops: Vec<ColorStop>,
+}
+
+/// How colors spread out from a gradient's start
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum GradientKind {
+    /// Along a straight line at the gradient's angle
+    #[default]
+    Linear,
+    /// In circles from the center out to the farthest corner
+    Radial,
+    /// Around the center, clockwise from the gradient's angle
+    Conic,
+}
+
+/// A repeating pattern drawn over a background's primary color
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PatternStyle {
+    #[serde(default)]
+    pub kind: PatternKind,
+    /// Multiplies the pattern's natural size
+    #[serde(default = "PatternStyle::default_scale")]
+    pub scale: f32,
+    /// Rotation in degrees clockwise
+    #[serde(default)]
+    pub rotation: f32,
+    /// Color of the pattern; without one the background's secondary color, or a shade of primary
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default = "PatternStyle::default_opacity")]
+    pub opacity: f32,
+}
+
+/// The patterns backgrounds can be covered with
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum PatternKind {
+    #[default]
+    Dots,
+    Grid,
+    DiagonalStripes,
+    Checkerboard,
+    Waves,
+    /// Contour lines of smooth noise, like a topographic map
+    Topographic,
+    /// Fine random speckles, like film grain
+    Grain,
+}
+
+/// An uploaded picture used as a background
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BackgroundImage {
+    /// ID returned by the upload endpoint
+    pub file_id: String,
+    #[serde(default)]
+    pub fit: ImageFit,
+    /// Gaussian blur radius in layout units
+    #[serde(default)]
+    pub blur: f32,
+    /// Darkens or lightens the picture so the window stands out against it
+    #[serde(default)]
+    pub overlay: Option<ImageOverlay>,
+}
+
+/// How a background picture fills its area
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum ImageFit {
+    /// Scaled to cover the whole area, cropping what overflows
+    #[default]
+    Cover,
+    /// Scaled to fit inside the area, showing the primary color around it
+    Contain,
+    /// Repeated at its natural size from the top left corner
+    Tile,
+}
+
+/// A black or white wash over a background picture
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct ImageOverlay {
+    pub kind: OverlayKind,
+    /// Strength from 0.0 (none) to 1.0 (solid black or white)
+    pub amount: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum OverlayKind {
+    Darken,
+    Lighten,
+}
+
+/// A color at a position along a gradient
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ColorStop {
+    pub color: String,
+    /// Position from 0.0 to 1.0; stops without one are spread evenly between their neighbours
+    #[serde(default)]
+    pub offset: Option<f32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -35,6 +168,63 @@ pub struct SyntaxColors {
     pub function: String,
     pub variable: String,
     pub type_name: String,
+    /// Plain text outside any token; a neutral grey for the background when unset
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+/// Colors of the 16 standard terminal colors, for ANSI escape codes in pasted output
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AnsiPalette {
+    pub black: String,
+    pub red: String,
+    pub green: String,
+    pub yellow: String,
+    pub blue: String,
+    pub magenta: String,
+    pub cyan: String,
+    pub white: String,
+    pub bright_black: String,
+    pub bright_red: String,
+    pub bright_green: String,
+    pub bright_yellow: String,
+    pub bright_blue: String,
+    pub bright_magenta: String,
+    pub bright_cyan: String,
+    pub bright_white: String,
+}
+
+/// Font style of each token kind, mirroring `SyntaxColors`
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SyntaxFontStyles {
+    pub keyword: TokenStyle,
+    pub string: TokenStyle,
+    pub comment: TokenStyle,
+    pub number: TokenStyle,
+    pub operator: TokenStyle,
+    pub function: TokenStyle,
+    pub variable: TokenStyle,
+    pub type_name: TokenStyle,
+}
+
+/// Weight, slant and decoration of a kind of token
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct TokenStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl TokenStyle {
+    /// An italic style, the most common emphasis in code themes
+    pub fn italic() -> Self {
+        TokenStyle {
+            italic: true,
+            ..Default::default()
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -44,7 +234,86 @@ pub struct WindowStyle {
     pub title: Option<String>,
     pub show_controls: bool,
     pub border_radius: f32,
-    pub shadow: bool,
+    /// Also accepts the older `true`/`false` form, where `true` is the default shadow
+    #[serde(default, deserialize_with = "deserialize_shadow")]
+    pub shadow: Option<ShadowStyle>,
+    #[serde(default)]
+    pub border: Option<BorderStyle>,
+    /// Frosted glass material; the window's background opacity sets how much shows through
+    #[serde(default)]
+    pub glass: Option<GlassStyle>,
+    /// Open files shown by the editor styles; without any, the title is the only tab
+    #[serde(default)]
+    pub tabs: Vec<WindowTab>,
+}
+
+/// A file shown as a tab by the VS Code and JetBrains window styles
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WindowTab {
+    /// File name; its extension picks the tab's language icon
+    pub name: String,
+    /// The tab holding the snippet; the first tab is active if none is marked
+    #[serde(default)]
+    pub active: bool,
+}
+
+/// Drop shadow cast by the window, following CSS `box-shadow` semantics
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ShadowStyle {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub blur_radius: f32,
+    pub spread: f32,
+    pub color: String,
+}
+
+/// A window shadow as sent by clients: a full spec, or a bool from before shadows were configurable
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ShadowSetting {
+    Enabled(bool),
+    Style(ShadowStyle),
+}
+
+fn deserialize_shadow<'de, D>(deserializer: D) -> Result<Option<ShadowStyle>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match Option::<ShadowSetting>::deserialize(deserializer)? {
+        Some(ShadowSetting::Style(shadow)) => Some(shadow),
+        Some(ShadowSetting::Enabled(true)) => Some(ShadowStyle::default()),
+        Some(ShadowSetting::Enabled(false)) | None => None,
+    })
+}
+
+/// Reads a shadow change, where turning the shadow off (`false` or `null`) differs from
+/// leaving the field out, which keeps the current shadow
+pub(crate) fn deserialize_shadow_change<'de, D>(deserializer: D) -> Result<Option<Option<ShadowStyle>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_shadow(deserializer).map(Some)
+}
+
+/// A translucent window showing a blurred copy of the backdrop behind it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GlassStyle {
+    /// Blur radius of the backdrop seen through the window, in layout units
+    #[serde(default = "GlassStyle::default_blur")]
+    pub blur: f32,
+    /// Strength of a fine grain over the glass, from 0.0 (none) to 1.0
+    #[serde(default)]
+    pub noise: f32,
+    /// Light line just inside the window's edge, like light catching the glass
+    #[serde(default)]
+    pub highlight: Option<BorderStyle>,
+}
+
+/// Outline drawn just inside the window's edge
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BorderStyle {
+    pub width: f32,
+    pub color: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -53,6 +322,10 @@ pub enum WindowStyleType {
     Windows,
     Terminal,
     Clean,
+    /// Editor tab bar laid out like VS Code
+    VSCode,
+    /// Editor tab bar laid out like JetBrains IDEs
+    JetBrains,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -62,6 +335,18 @@ pub struct TypographyStyle {
     pub line_height: f32,
     pub letter_spacing: f32,
     pub show_line_numbers: bool,
+    /// OpenType features applied when shaping code, such as `liga`, `calt`, `ss01` or `zero`
+    #[serde(default = "TypographyStyle::default_font_features")]
+    pub font_features: Vec<String>,
+    /// Columns between tab stops
+    #[serde(default = "TypographyStyle::default_tab_width")]
+    pub tab_width: u32,
+    /// Draws faint dots for spaces and arrows for tabs
+    #[serde(default)]
+    pub render_whitespace: bool,
+    /// Draws a vertical line at each indentation level
+    #[serde(default)]
+    pub indent_guides: bool,
 }
 
 impl Theme {
@@ -85,12 +370,14 @@ impl Theme {
             &self.syntax.type_name,
         ];
 
-        for color in colors {
+        for color in colors.into_iter().chain(self.syntax.text.as_ref()) {
             if !Self::is_valid_color(color) {
                 return Err(format!("Invalid color format: {}", color));
             }
         }
 
+        self.ansi_colors.validate()?;
+
         // Validate secondary color if present
         if let Some(ref secondary) = self.background.secondary {
             if !Self::is_valid_color(secondary) {
@@ -98,6 +385,39 @@ impl Theme {
             }
         }
 
+        if let Some(ref gradient) = self.background.gradient {
+            gradient.validate()?;
+        }
+
+        if let Some(ref pattern) = self.background.pattern {
+            pattern.validate()?;
+        }
+
+        self.background.validate_image()?;
+
+        // Validate the backdrop like the window background
+        if let Some(ref backdrop) = self.backdrop {
+            if backdrop.opacity < 0.0 || backdrop.opacity > 1.0 {
+                return Err("Backdrop opacity must be between 0.0 and 1.0".to_string());
+            }
+
+            for color in std::iter::once(&backdrop.primary).chain(backdrop.secondary.as_ref()) {
+                if !Self::is_valid_color(color) {
+                    return Err(format!("Invalid backdrop color format: {}", color));
+                }
+            }
+
+            if let Some(ref gradient) = backdrop.gradient {
+                gradient.validate()?;
+            }
+
+            if let Some(ref pattern) = backdrop.pattern {
+                pattern.validate()?;
+            }
+
+            backdrop.validate_image()?;
+        }
+
         // Validate typography values
         if self.typography.font_size <= 0.0 {
             return Err("Font size must be greater than 0".to_string());
@@ -107,13 +427,71 @@ impl Theme {
             return Err("Line height must be greater than 0".to_string());
         }
 
+        if let Some(feature) = self
+            .typography
+            .font_features
+            .iter()
+            .find(|feature| !TypographyStyle::is_supported_feature(feature))
+        {
+            return Err(format!("Unsupported font feature: {}", feature));
+        }
+
+        if !(1..=TypographyStyle::MAX_TAB_WIDTH).contains(&self.typography.tab_width) {
+            return Err(format!(
+                "Tab width must be between 1 and {}",
+                TypographyStyle::MAX_TAB_WIDTH
+            ));
+        }
+
         if self.window.border_radius < 0.0 {
             return Err("Border radius cannot be negative".to_string());
         }
 
+        if let Some(ref shadow) = self.window.shadow {
+            shadow.validate()?;
+        }
+
+        if let Some(ref border) = self.window.border {
+            border.validate()?;
+        }
+
+        if let Some(ref glass) = self.window.glass {
+            glass.validate()?;
+        }
+
+        WindowTab::validate_tabs(&self.window.tabs)?;
+
         Ok(())
     }
 
+    /// Color of plain text: the theme's own, or a neutral grey that reads on its background
+    pub fn text_color(&self) -> String {
+        if let Some(ref text) = self.syntax.text {
+            return text.clone();
+        }
+
+        if Self::is_light_color(&self.background.primary) {
+            "#24292e".to_string()
+        } else {
+            "#d4d4d4".to_string()
+        }
+    }
+
+    /// True for colors bright enough to need dark text on top
+    fn is_light_color(color: &str) -> bool {
+        let hex = color.trim_start_matches('#');
+        let channel = |i: usize| match hex.len() {
+            3 => u8::from_str_radix(&hex[i..i + 1].repeat(2), 16).ok(),
+            6 | 8 => u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok(),
+            _ => None,
+        };
+
+        match (channel(0), channel(1), channel(2)) {
+            (Some(r), Some(g), Some(b)) => 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32 >= 128.0,
+            _ => false,
+        }
+    }
+
     /// Basic hex color validation
     pub fn is_valid_color(color: &str) -> bool {
         if !color.starts_with('#') {
@@ -138,7 +516,11 @@ impl Theme {
                 primary: "#1e1e1e".to_string(),
                 secondary: None,
                 opacity: 1.0,
+                gradient: None,
+                pattern: None,
+                image: None,
             },
+            backdrop: None,
             syntax: SyntaxColors {
                 keyword: "#569cd6".to_string(),
                 string: "#ce9178".to_string(),
@@ -148,14 +530,20 @@ impl Theme {
                 function: "#dcdcaa".to_string(),
                 variable: "#9cdcfe".to_string(),
                 type_name: "#4ec9b0".to_string(),
+                text: Some("#d4d4d4".to_string()),
             },
+            font_styles: SyntaxFontStyles::default(),
+            ansi_colors: AnsiPalette::default(),
             window: WindowStyle {
                 style_type: WindowStyleType::MacOS,
                 show_title_bar: true,
                 title: None,
                 show_controls: true,
                 border_radius: 8.0,
-                shadow: true,
+                shadow: Some(ShadowStyle::default()),
+                border: None,
+                glass: None,
+                tabs: Vec::new(),
             },
             typography: TypographyStyle {
                 font_family: "Fira Code".to_string(),
@@ -163,6 +551,10 @@ impl Theme {
                 line_height: 1.5,
                 letter_spacing: 0.0,
                 show_line_numbers: false,
+                font_features: TypographyStyle::default_font_features(),
+                tab_width: TypographyStyle::default_tab_width(),
+                render_whitespace: false,
+                indent_guides: false,
             },
         }
     }
@@ -177,7 +569,11 @@ impl Theme {
                 primary: "#ffffff".to_string(),
                 secondary: None,
                 opacity: 1.0,
+                gradient: None,
+                pattern: None,
+                image: None,
             },
+            backdrop: None,
             syntax: SyntaxColors {
                 keyword: "#0000ff".to_string(),
                 string: "#a31515".to_string(),
@@ -187,14 +583,20 @@ impl Theme {
                 function: "#795e26".to_string(),
                 variable: "#001080".to_string(),
                 type_name: "#267f99".to_string(),
+                text: Some("#000000".to_string()),
             },
+            font_styles: SyntaxFontStyles::default(),
+            ansi_colors: AnsiPalette::light(),
             window: WindowStyle {
                 style_type: WindowStyleType::MacOS,
                 show_title_bar: true,
                 title: None,
                 show_controls: true,
                 border_radius: 8.0,
-                shadow: true,
+                shadow: Some(ShadowStyle::default()),
+                border: None,
+                glass: None,
+                tabs: Vec::new(),
             },
             typography: TypographyStyle {
                 font_family: "Fira Code".to_string(),
@@ -202,7 +604,471 @@ impl Theme {
                 line_height: 1.5,
                 letter_spacing: 0.0,
                 show_line_numbers: false,
+                font_features: TypographyStyle::default_font_features(),
+                tab_width: TypographyStyle::default_tab_width(),
+                render_whitespace: false,
+                indent_guides: false,
             },
         }
     }
-}
\ No newline at end of file
+}
+
+impl TypographyStyle {
+    /// Widest accepted tab stop interval
+    pub const MAX_TAB_WIDTH: u32 = 16;
+
+    /// Four columns per tab, as most editors default to
+    pub fn default_tab_width() -> u32 {
+        4
+    }
+
+    /// Standard and contextual ligatures, on unless a theme lists its own features
+    pub fn default_font_features() -> Vec<String> {
+        vec!["liga".to_string(), "calt".to_string()]
+    }
+
+    /// Returns true for the OpenType features themes can turn on:
+    /// `liga`, `calt`, the stylistic sets `ss01`-`ss20` and the slashed `zero`
+    pub fn is_supported_feature(tag: &str) -> bool {
+        match tag {
+            "liga" | "calt" | "zero" => true,
+            _ => {
+                tag.len() == 4
+                    && tag
+                        .strip_prefix("ss")
+                        .and_then(|set| set.parse::<u8>().ok())
+                        .is_some_and(|set| (1..=20).contains(&set))
+            }
+        }
+    }
+}
+
+impl GradientStyle {
+    /// Most color stops a gradient may have
+    pub const MAX_STOPS: usize = 16;
+
+    /// Top to bottom, like the plain two-color gradient
+    pub fn default_angle() -> f32 {
+        180.0
+    }
+
+    /// The middle of the background
+    pub fn default_center() -> f32 {
+        0.5
+    }
+
+    /// Validates the angle, center and stops
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.angle.is_finite() {
+            return Err("Gradient angle must be a finite number".to_string());
+        }
+
+        if !(0.0..=1.0).contains(&self.center_x) || !(0.0..=1.0).contains(&self.center_y) {
+            return Err("Gradient center must be between 0.0 and 1.0".to_string());
+        }
+
+        if !(2..=Self::MAX_STOPS).contains(&self.stops.len()) {
+            return Err(format!(
+                "Gradients need between 2 and {} color stops",
+                Self::MAX_STOPS
+            ));
+        }
+
+        let mut previous = 0.0;
+        for stop in &self.stops {
+            if !Theme::is_valid_color(&stop.color) {
+                return Err(format!("Invalid gradient stop color format: {}", stop.color));
+            }
+
+            if let Some(offset) = stop.offset {
+                if !(previous..=1.0).contains(&offset) {
+                    return Err(
+                        "Gradient stop offsets must be between 0.0 and 1.0 and in increasing order"
+                            .to_string(),
+                    );
+                }
+                previous = offset;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Position of every stop, placing stops without an offset like CSS does: the first
+    /// at 0.0, the last at 1.0 and the rest evenly between the positioned stops around them
+    pub fn resolved_offsets(&self) -> Vec<f32> {
+        let last = self.stops.len().saturating_sub(1);
+        let mut offsets: Vec<Option<f32>> = self
+            .stops
+            .iter()
+            .enumerate()
+            .map(|(i, stop)| match stop.offset {
+                None if i == 0 => Some(0.0),
+                None if i == last => Some(1.0),
+                offset => offset,
+            })
+            .collect();
+
+        let mut start = 0;
+        while start < offsets.len() {
+            let Some(from) = offsets[start] else {
+                start += 1;
+                continue;
+            };
+            let Some(gap) = offsets[start + 1..].iter().position(Option::is_some) else {
+                break;
+            };
+            let end = start + 1 + gap;
+            let to = offsets[end].unwrap_or(from);
+            for (step, offset) in offsets[start + 1..end].iter_mut().enumerate() {
+                *offset = Some(from + (to - from) * (step + 1) as f32 / (gap + 1) as f32);
+            }
+            start = end;
+        }
+
+        offsets.into_iter().map(|offset| offset.unwrap_or(0.0)).collect()
+    }
+}
+
+impl PatternStyle {
+    /// Smallest accepted scale
+    pub const MIN_SCALE: f32 = 0.25;
+    /// Largest accepted scale
+    pub const MAX_SCALE: f32 = 8.0;
+
+    /// The pattern's natural size
+    pub fn default_scale() -> f32 {
+        1.0
+    }
+
+    /// Fully opaque
+    pub fn default_opacity() -> f32 {
+        1.0
+    }
+
+    /// Validates the scale, rotation, color and opacity
+    pub fn validate(&self) -> Result<(), String> {
+        if !(Self::MIN_SCALE..=Self::MAX_SCALE).contains(&self.scale) {
+            return Err(format!(
+                "Pattern scale must be between {} and {}",
+                Self::MIN_SCALE,
+                Self::MAX_SCALE
+            ));
+        }
+
+        if !self.rotation.is_finite() {
+            return Err("Pattern rotation must be a finite number".to_string());
+        }
+
+        if let Some(ref color) = self.color {
+            if !Theme::is_valid_color(color) {
+                return Err(format!("Invalid pattern color format: {}", color));
+            }
+        }
+
+        if !(0.0..=1.0).contains(&self.opacity) {
+            return Err("Pattern opacity must be between 0.0 and 1.0".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PatternStyle {
+    fn default() -> Self {
+        PatternStyle {
+            kind: PatternKind::default(),
+            scale: Self::default_scale(),
+            rotation: 0.0,
+            color: None,
+            opacity: Self::default_opacity(),
+        }
+    }
+}
+
+impl BackgroundStyle {
+    /// Checks that an `Image` background has a valid picture
+    pub fn validate_image(&self) -> Result<(), String> {
+        match self.image {
+            Some(ref image) => image.validate(),
+            None if self.bg_type == BackgroundType::Image => {
+                Err("Image backgrounds need an uploaded image".to_string())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl BackgroundImage {
+    /// Largest accepted blur radius in layout units
+    pub const MAX_BLUR: f32 = 64.0;
+
+    /// Validates the file ID, blur and overlay
+    pub fn validate(&self) -> Result<(), String> {
+        // File IDs name files on disk, so only the characters of a UUID are accepted
+        if self.file_id.is_empty()
+            || !self.file_id.chars().all(|ch| ch.is_ascii_hexdigit() || ch == '-')
+        {
+            return Err(format!("Invalid background image ID: {}", self.file_id));
+        }
+
+        if !(0.0..=Self::MAX_BLUR).contains(&self.blur) {
+            return Err(format!(
+                "Background image blur must be between 0 and {}",
+                Self::MAX_BLUR
+            ));
+        }
+
+        if let Some(overlay) = self.overlay {
+            if !(0.0..=1.0).contains(&overlay.amount) {
+                return Err("Background image overlay must be between 0.0 and 1.0".to_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ShadowStyle {
+    /// Largest accepted blur radius in layout units
+    pub const MAX_BLUR_RADIUS: f32 = 100.0;
+
+    /// Validates the shadow geometry and color
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.offset_x.is_finite() || !self.offset_y.is_finite() || !self.spread.is_finite() {
+            return Err("Shadow offset and spread must be finite numbers".to_string());
+        }
+
+        if !(0.0..=Self::MAX_BLUR_RADIUS).contains(&self.blur_radius) {
+            return Err(format!(
+                "Shadow blur radius must be between 0 and {}",
+                Self::MAX_BLUR_RADIUS
+            ));
+        }
+
+        if !Theme::is_valid_color(&self.color) {
+            return Err(format!("Invalid shadow color format: {}", self.color));
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for ShadowStyle {
+    fn default() -> Self {
+        ShadowStyle {
+            offset_x: 0.0,
+            offset_y: 8.0,
+            blur_radius: 24.0,
+            spread: 0.0,
+            color: "#00000080".to_string(),
+        }
+    }
+}
+
+impl BorderStyle {
+    /// Widest accepted border in layout units
+    pub const MAX_WIDTH: f32 = 16.0;
+
+    /// Validates the width and color
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.width > 0.0 && self.width <= Self::MAX_WIDTH) {
+            return Err(format!(
+                "Border width must be greater than 0 and at most {}",
+                Self::MAX_WIDTH
+            ));
+        }
+
+        if !Theme::is_valid_color(&self.color) {
+            return Err(format!("Invalid border color format: {}", self.color));
+        }
+
+        Ok(())
+    }
+}
+
+impl GlassStyle {
+    /// Largest accepted blur radius in layout units
+    pub const MAX_BLUR: f32 = 64.0;
+
+    /// A soft blur that keeps the backdrop's colors recognizable
+    pub fn default_blur() -> f32 {
+        24.0
+    }
+
+    /// Validates the blur, noise and highlight
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=Self::MAX_BLUR).contains(&self.blur) {
+            return Err(format!("Glass blur must be between 0 and {}", Self::MAX_BLUR));
+        }
+
+        if !(0.0..=1.0).contains(&self.noise) {
+            return Err("Glass noise must be between 0.0 and 1.0".to_string());
+        }
+
+        if let Some(ref highlight) = self.highlight {
+            highlight.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl WindowTab {
+    /// Most tabs a window can show
+    pub const MAX_TABS: usize = 12;
+    /// Longest accepted file name, in characters
+    pub const MAX_NAME_LENGTH: usize = 64;
+
+    /// Validates the file names, and that at most one tab is active
+    pub fn validate_tabs(tabs: &[WindowTab]) -> Result<(), String> {
+        if tabs.len() > Self::MAX_TABS {
+            return Err(format!("A window can show at most {} tabs", Self::MAX_TABS));
+        }
+
+        for tab in tabs {
+            if tab.name.trim().is_empty() {
+                return Err("Tab names cannot be empty".to_string());
+            }
+            if tab.name.chars().count() > Self::MAX_NAME_LENGTH {
+                return Err(format!(
+                    "Tab name exceeds {} characters: {}",
+                    Self::MAX_NAME_LENGTH,
+                    tab.name
+                ));
+            }
+        }
+
+        if tabs.iter().filter(|tab| tab.active).count() > 1 {
+            return Err("Only one tab can be active".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl AnsiPalette {
+    /// Builds a palette from colors in ANSI order: the 8 normal colors, then their bright versions
+    fn from_colors(colors: [&str; 16]) -> Self {
+        let [
+            black,
+            red,
+            green,
+            yellow,
+            blue,
+            magenta,
+            cyan,
+            white,
+            bright_black,
+            bright_red,
+            bright_green,
+            bright_yellow,
+            bright_blue,
+            bright_magenta,
+            bright_cyan,
+            bright_white,
+        ] = colors.map(str::to_string);
+
+        AnsiPalette {
+            black,
+            red,
+            green,
+            yellow,
+            blue,
+            magenta,
+            cyan,
+            white,
+            bright_black,
+            bright_red,
+            bright_green,
+            bright_yellow,
+            bright_blue,
+            bright_magenta,
+            bright_cyan,
+            bright_white,
+        }
+    }
+
+    /// Terminal colors suited to light backgrounds
+    pub fn light() -> Self {
+        Self::from_colors([
+            "#000000", "#cd3131", "#00bc00", "#949800", "#0451a5", "#bc05bc", "#0598bc", "#555555",
+            "#666666", "#cd3131", "#14ce14", "#b5ba00", "#0451a5", "#bc05bc", "#0598bc", "#a5a5a5",
+        ])
+    }
+
+    /// The Dracula terminal colors
+    pub fn dracula() -> Self {
+        Self::from_colors([
+            "#21222c", "#ff5555", "#50fa7b", "#f1fa8c", "#bd93f9", "#ff79c6", "#8be9fd", "#f8f8f2",
+            "#6272a4", "#ff6e6e", "#69ff94", "#ffffa5", "#d6acff", "#ff92df", "#a4ffff", "#ffffff",
+        ])
+    }
+
+    /// The Solarized terminal colors, shared by its dark and light variants
+    pub fn solarized() -> Self {
+        Self::from_colors([
+            "#073642", "#dc322f", "#859900", "#b58900", "#268bd2", "#d33682", "#2aa198", "#eee8d5",
+            "#002b36", "#cb4b16", "#586e75", "#657b83", "#839496", "#6c71c4", "#93a1a1", "#fdf6e3",
+        ])
+    }
+
+    /// The colors in ANSI order, so color `n` of an escape code is `colors()[n]`
+    pub fn colors(&self) -> [&str; 16] {
+        [
+            &self.black,
+            &self.red,
+            &self.green,
+            &self.yellow,
+            &self.blue,
+            &self.magenta,
+            &self.cyan,
+            &self.white,
+            &self.bright_black,
+            &self.bright_red,
+            &self.bright_green,
+            &self.bright_yellow,
+            &self.bright_blue,
+            &self.bright_magenta,
+            &self.bright_cyan,
+            &self.bright_white,
+        ]
+    }
+
+    /// Validates every color
+    pub fn validate(&self) -> Result<(), String> {
+        match self.colors().into_iter().find(|color| !Theme::is_valid_color(color)) {
+            Some(color) => Err(format!("Invalid ANSI color format: {}", color)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Terminal colors suited to dark backgrounds
+impl Default for AnsiPalette {
+    fn default() -> Self {
+        Self::from_colors([
+            "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd", "#e5e5e5",
+            "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6", "#29b8db", "#e5e5e5",
+        ])
+    }
+}
+
+impl Default for GlassStyle {
+    fn default() -> Self {
+        GlassStyle {
+            blur: Self::default_blur(),
+            noise: 0.0,
+            highlight: None,
+        }
+    }
+}
+
+impl Default for BorderStyle {
+    fn default() -> Self {
+        BorderStyle {
+            width: 1.0,
+            color: "#ffffff1a".to_string(),
+        }
+    }
+}
@@ -0,0 +1,712 @@
+use crate::models::errors::AppError;
+use crate::services::graphemes;
+use crate::services::scene::Scene;
+use crate::services::syntax_highlighter::{HighlightedLine, HighlightedSegment};
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+/// Shortest frame delay browsers honor; faster typing reveals several steps per frame
+const MIN_FRAME_DELAY_MS: f32 = 40.0;
+
+/// Upper bound on frames so long snippets stay small and quick to encode
+const MAX_TYPING_FRAMES: usize = 300;
+
+/// Longest final hold, so a finished snippet can't stretch the animation indefinitely
+const MAX_FINAL_HOLD_MS: u32 = 60_000;
+
+/// How much of the snippet each step of a typing animation reveals
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum RevealMode {
+    #[default]
+    Character,
+    Line,
+}
+
+/// Timing and looping options for animated "typing" exports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnimationOptions {
+    pub reveal: RevealMode,
+    pub speed: f32, // Characters (or lines) revealed per second
+    pub blank_line_pause_ms: u32,
+    pub loop_count: u16, // Number of plays, 0 loops forever
+    pub final_hold_ms: u32,
+    pub show_cursor: bool,
+    pub cursor_blink_ms: u32,
+}
+
+/// A text cursor position; everything before it is revealed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorPosition {
+    pub line: usize,
+    /// Grapheme clusters revealed on the line, so accents and emoji appear whole
+    pub column: usize,
+}
+
+/// One frame of a typing animation before it is drawn
+#[derive(Debug, Clone, PartialEq)]
+pub struct FramePlan {
+    pub cursor: CursorPosition,
+    pub cursor_visible: bool,
+    pub delay_ms: u32,
+}
+
+/// A laid out frame of a typing animation and how long it stays on screen
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    pub scene: Scene,
+    pub delay_ms: u32,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        AnimationOptions {
+            reveal: RevealMode::Character,
+            speed: 30.0,
+            blank_line_pause_ms: 400,
+            loop_count: 0,
+            final_hold_ms: 2000,
+            show_cursor: true,
+            cursor_blink_ms: 500,
+        }
+    }
+}
+
+impl AnimationOptions {
+    /// Checks that the timing options describe a playable animation
+    pub fn validate(&self) -> Result<(), AppError> {
+        if !(self.speed > 0.0 && self.speed <= 1000.0) {
+            return Err(AppError::image_generation_failed(
+                "Animation speed must be between 0 and 1000 steps per second",
+            ));
+        }
+
+        if self.final_hold_ms > MAX_FINAL_HOLD_MS {
+            return Err(AppError::image_generation_failed(format!(
+                "Final hold must be at most {}ms",
+                MAX_FINAL_HOLD_MS
+            )));
+        }
+
+        if self.show_cursor && (self.cursor_blink_ms as f32) < MIN_FRAME_DELAY_MS {
+            return Err(AppError::image_generation_failed(format!(
+                "Cursor blink interval must be at least {}ms",
+                MIN_FRAME_DELAY_MS
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Plans the frames that type out `lines`, ending with the final hold
+pub fn plan_typing_frames(
+    lines: &[HighlightedLine],
+    options: &AnimationOptions,
+) -> Result<Vec<FramePlan>, AppError> {
+    options.validate()?;
+
+    let lengths: Vec<usize> = lines.iter().map(line_length).collect();
+    let blank: Vec<bool> = lines.iter().map(is_blank_line).collect();
+
+    // Every cursor position the animation passes through, starting from an empty window
+    let mut steps = vec![CursorPosition { line: 0, column: 0 }];
+    for (line, &length) in lengths.iter().enumerate() {
+        match options.reveal {
+            RevealMode::Character => {
+                if line > 0 {
+                    steps.push(CursorPosition { line, column: 0 });
+                }
+                steps.extend((1..=length).map(|column| CursorPosition { line, column }));
+            }
+            RevealMode::Line => steps.push(CursorPosition {
+                line,
+                column: length,
+            }),
+        }
+    }
+
+    let step_ms = 1000.0 / options.speed;
+    let steps_per_frame = ((MIN_FRAME_DELAY_MS / step_ms).ceil() as usize)
+        .max(steps.len().div_ceil(MAX_TYPING_FRAMES))
+        .max(1);
+
+    let mut frames = Vec::new();
+    let mut paused_lines = vec![false; lines.len()];
+    for chunk in steps.chunks(steps_per_frame) {
+        let mut delay_ms = step_ms * chunk.len() as f32;
+
+        // Pause on the first frame that reaches each blank line
+        for step in chunk {
+            if blank.get(step.line) == Some(&true) && !paused_lines[step.line] {
+                paused_lines[step.line] = true;
+                delay_ms += options.blank_line_pause_ms as f32;
+            }
+        }
+
+        frames.push(FramePlan {
+            cursor: *chunk.last().unwrap_or(&steps[0]),
+            cursor_visible: options.show_cursor,
+            delay_ms: delay_ms.round() as u32,
+        });
+    }
+
+    // Hold the finished snippet, blinking the cursor if there is one
+    let Some(last) = frames.pop() else {
+        return Ok(frames);
+    };
+    let hold_ms = options.final_hold_ms.max(last.delay_ms);
+
+    if options.show_cursor {
+        // Blink slower rather than exceed the frame budget left after typing
+        let budget = (MAX_TYPING_FRAMES - frames.len()) as u32;
+        let interval = options.cursor_blink_ms.max(hold_ms.div_ceil(budget));
+        let blinks = (hold_ms / interval).max(1);
+        for blink in 0..blinks {
+            let remaining = hold_ms - blink * interval;
+            frames.push(FramePlan {
+                cursor: last.cursor,
+                cursor_visible: blink % 2 == 0,
+                delay_ms: if blink + 1 == blinks { remaining } else { interval },
+            });
+        }
+    } else {
+        frames.push(FramePlan {
+            delay_ms: hold_ms,
+            ..last
+        });
+    }
+
+    Ok(frames)
+}
+
+/// Returns the lines revealed before the cursor, keeping each segment's highlighting
+pub fn reveal_lines(lines: &[HighlightedLine], cursor: &CursorPosition) -> Vec<HighlightedLine> {
+    let mut revealed: Vec<HighlightedLine> = lines.iter().take(cursor.line).cloned().collect();
+
+    if let Some(line) = lines.get(cursor.line) {
+        let mut remaining = cursor.column;
+        let mut segments = Vec::new();
+
+        for segment in &line.segments {
+            if remaining == 0 {
+                break;
+            }
+
+            let clusters: Vec<&str> = graphemes::graphemes(&segment.text).take(remaining).collect();
+            remaining -= clusters.len();
+            let text = clusters.concat();
+
+            segments.push(HighlightedSegment {
+                text,
+                style: segment.style.clone(),
+            });
+        }
+
+        revealed.push(HighlightedLine {
+            segments,
+            line_number: line.line_number,
+            gutter: line.gutter.clone(),
+            continuation: line.continuation,
+        });
+    }
+
+    revealed
+}
+
+/// Encodes frames and their delays as an animated GIF
+///
+/// Every frame is mapped onto one palette built from `palette_source`, a frame showing
+/// all the colors of the animation, and only the region that changed since the previous
+/// frame is stored.
+pub fn encode_gif(
+    frames: impl Iterator<Item = Result<(RgbaImage, u32), AppError>>,
+    palette_source: &RgbaImage,
+    loop_count: u16,
+) -> Result<Vec<u8>, AppError> {
+    use gif::{DisposalMethod, Encoder, Frame, Repeat};
+
+    let (width, height) = palette_source.dimensions();
+    let (Ok(canvas_width), Ok(canvas_height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(AppError::image_generation_failed(format!(
+            "GIF frames are limited to {}x{} pixels",
+            u16::MAX,
+            u16::MAX
+        )));
+    };
+    let palette = GifPalette::new(palette_source);
+
+    let mut buffer = Vec::new();
+    {
+        let mut encoder =
+            Encoder::new(&mut buffer, canvas_width, canvas_height, &palette.colors).map_err(gif_error)?;
+
+        // Without a loop extension a GIF plays once; the extension counts repeats after that
+        let repeat = match loop_count {
+            0 => Some(Repeat::Infinite),
+            1 => None,
+            plays => Some(Repeat::Finite(plays - 1)),
+        };
+        if let Some(repeat) = repeat {
+            encoder.set_repeat(repeat).map_err(gif_error)?;
+        }
+
+        let mut previous: Option<RgbaImage> = None;
+        for frame in frames {
+            let (image, delay_ms) = frame?;
+            let region = match previous {
+                Some(ref previous) => changed_region(previous, &image),
+                None => FrameRegion::full(&image),
+            };
+
+            encoder
+                .write_frame(&Frame {
+                    delay: delay_ms.div_ceil(10).min(u16::MAX as u32) as u16,
+                    dispose: DisposalMethod::Keep,
+                    transparent: Some(GifPalette::TRANSPARENT),
+                    left: region.x as u16,
+                    top: region.y as u16,
+                    width: region.width as u16,
+                    height: region.height as u16,
+                    buffer: palette.indices(&image, &region).into(),
+                    ..Frame::default()
+                })
+                .map_err(gif_error)?;
+            previous = Some(image);
+        }
+    }
+
+    Ok(buffer)
+}
+
+/// Encodes frames and their delays as an animated PNG
+///
+/// After the first frame, only the region that changed since the previous frame is
+/// stored, with unchanged pixels inside it left transparent and blended over the canvas.
+pub fn encode_apng(
+    frames: impl ExactSizeIterator<Item = Result<(RgbaImage, u32), AppError>>,
+    width: u32,
+    height: u32,
+    loop_count: u16,
+) -> Result<Vec<u8>, AppError> {
+    use png::{BlendOp, DisposeOp};
+
+    let mut buffer = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut buffer, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .set_animated(frames.len() as u32, loop_count as u32)
+            .map_err(apng_error)?;
+
+        let mut writer = encoder.write_header().map_err(apng_error)?;
+        let mut previous: Option<RgbaImage> = None;
+        for frame in frames {
+            let (image, delay_ms) = frame?;
+            let (region, blend, data) = match previous {
+                Some(ref previous) => {
+                    let region = changed_region(previous, &image);
+                    let (blend, data) = apng_patch(previous, &image, &region);
+                    (region, blend, data)
+                }
+                None => (FrameRegion::full(&image), BlendOp::Source, image.as_raw().clone()),
+            };
+
+            writer.reset_frame_position().map_err(apng_error)?;
+            writer
+                .set_frame_dimension(region.width, region.height)
+                .map_err(apng_error)?;
+            writer.set_frame_position(region.x, region.y).map_err(apng_error)?;
+            writer.set_blend_op(blend).map_err(apng_error)?;
+            writer.set_dispose_op(DisposeOp::None).map_err(apng_error)?;
+            writer
+                .set_frame_delay(delay_ms.min(u16::MAX as u32) as u16, 1000)
+                .map_err(apng_error)?;
+            writer.write_image_data(&data).map_err(apng_error)?;
+            previous = Some(image);
+        }
+        writer.finish().map_err(apng_error)?;
+    }
+
+    Ok(buffer)
+}
+
+/// A rectangle of the canvas stored in one animation frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameRegion {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+impl FrameRegion {
+    fn full(image: &RgbaImage) -> Self {
+        FrameRegion { x: 0, y: 0, width: image.width(), height: image.height() }
+    }
+
+    fn pixels(self) -> impl Iterator<Item = (u32, u32)> {
+        (self.y..self.y + self.height)
+            .flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+/// The smallest rectangle containing every pixel that differs between two frames
+///
+/// Identical frames still need a frame to carry their delay, so they get a single pixel.
+fn changed_region(previous: &RgbaImage, current: &RgbaImage) -> FrameRegion {
+    let row_length = current.width() as usize * 4;
+    let rows = previous
+        .as_raw()
+        .chunks_exact(row_length)
+        .zip(current.as_raw().chunks_exact(row_length));
+
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+    for (y, (before, after)) in rows.enumerate() {
+        if before == after {
+            continue;
+        }
+        let changed = |x: &usize| before[x * 4..x * 4 + 4] != after[x * 4..x * 4 + 4];
+        let columns = row_length / 4;
+        let left = (0..columns).find(changed).unwrap_or(0) as u32;
+        let right = (0..columns).rev().find(changed).unwrap_or(0) as u32;
+        let y = y as u32;
+
+        bounds = Some(match bounds {
+            Some((x0, y0, x1, _)) => (x0.min(left), y0, x1.max(right), y),
+            None => (left, y, right, y),
+        });
+    }
+
+    match bounds {
+        Some((x0, y0, x1, y1)) => FrameRegion { x: x0, y: y0, width: x1 - x0 + 1, height: y1 - y0 + 1 },
+        None => FrameRegion { x: 0, y: 0, width: 1, height: 1 },
+    }
+}
+
+/// The pixels of an APNG frame covering `region` and how to combine them with the canvas
+///
+/// Unchanged pixels are left transparent and blended over, which compresses far better;
+/// a changed pixel that is itself translucent cannot be blended, so the region is copied.
+fn apng_patch(previous: &RgbaImage, current: &RgbaImage, region: &FrameRegion) -> (png::BlendOp, Vec<u8>) {
+    let translucent_change = region.pixels().any(|(x, y)| {
+        let pixel = current.get_pixel(x, y);
+        pixel[3] < u8::MAX && pixel != previous.get_pixel(x, y)
+    });
+
+    let data = region
+        .pixels()
+        .flat_map(|(x, y)| {
+            let pixel = current.get_pixel(x, y);
+            if !translucent_change && pixel == previous.get_pixel(x, y) {
+                [0; 4]
+            } else {
+                pixel.0
+            }
+        })
+        .collect();
+
+    let blend = if translucent_change { png::BlendOp::Source } else { png::BlendOp::Over };
+    (blend, data)
+}
+
+/// One palette shared by every frame of a GIF, with a final entry for transparent pixels
+struct GifPalette {
+    quantizer: color_quant::NeuQuant,
+    colors: Vec<u8>,
+}
+
+impl GifPalette {
+    /// Palette index of fully transparent pixels, such as the canvas around a rounded window
+    const TRANSPARENT: u8 = u8::MAX;
+
+    fn new(source: &RgbaImage) -> Self {
+        let mut opaque: Vec<u8> = source
+            .pixels()
+            .filter(|pixel| pixel[3] >= 128)
+            .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], u8::MAX])
+            .collect();
+        if opaque.is_empty() {
+            opaque.extend_from_slice(&[0, 0, 0, u8::MAX]);
+        }
+
+        // Speed 10 matches what the per-frame quantizer used before frames shared a palette
+        let quantizer = color_quant::NeuQuant::new(10, Self::TRANSPARENT as usize, &opaque);
+        let mut colors = quantizer.color_map_rgb();
+        colors.resize(256 * 3, 0);
+        GifPalette { quantizer, colors }
+    }
+
+    fn indices(&self, image: &RgbaImage, region: &FrameRegion) -> Vec<u8> {
+        region
+            .pixels()
+            .map(|(x, y)| {
+                let pixel = image.get_pixel(x, y);
+                if pixel[3] < 128 {
+                    Self::TRANSPARENT
+                } else {
+                    self.quantizer.index_of(&[pixel[0], pixel[1], pixel[2], u8::MAX]) as u8
+                }
+            })
+            .collect()
+    }
+}
+
+/// Number of grapheme clusters on a line, each revealed in one typing step
+fn line_length(line: &HighlightedLine) -> usize {
+    line.segments
+        .iter()
+        .map(|segment| graphemes::graphemes(&segment.text).count())
+        .sum()
+}
+
+fn is_blank_line(line: &HighlightedLine) -> bool {
+    line.segments
+        .iter()
+        .all(|segment| segment.text.trim().is_empty())
+}
+
+fn gif_error(e: gif::EncodingError) -> AppError {
+    AppError::image_generation_failed(format!("GIF encoding failed: {}", e))
+}
+
+fn apng_error(e: png::EncodingError) -> AppError {
+    AppError::image_generation_failed(format!("APNG encoding failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::syntax_highlighter::SegmentStyle;
+
+    fn line(number: usize, parts: &[(&str, &str)]) -> HighlightedLine {
+        HighlightedLine {
+            segments: parts
+                .iter()
+                .map(|(text, color)| HighlightedSegment {
+                    text: text.to_string(),
+                    style: SegmentStyle {
+                        color: color.to_string(),
+                        bold: false,
+                        italic: false,
+                        underline: false,
+                        background: None,
+                        redacted: false,
+                    },
+                })
+                .collect(),
+            line_number: number,
+            gutter: None,
+            continuation: false,
+        }
+    }
+
+    fn sample_lines() -> Vec<HighlightedLine> {
+        vec![
+            line(1, &[("let", "#ff0000"), (" x = 1;\n", "#ffffff")]),
+            line(2, &[("\n", "#ffffff")]),
+            line(3, &[("x", "#00ff00")]),
+        ]
+    }
+
+    #[test]
+    fn test_reveal_lines_keeps_segment_colors() {
+        let revealed = reveal_lines(&sample_lines(), &CursorPosition { line: 0, column: 5 });
+
+        assert_eq!(revealed.len(), 1);
+        assert_eq!(revealed[0].segments[0].text, "let");
+        assert_eq!(revealed[0].segments[0].style.color, "#ff0000");
+        assert_eq!(revealed[0].segments[1].text, " x");
+        assert_eq!(revealed[0].segments[1].style.color, "#ffffff");
+    }
+
+    #[test]
+    fn test_character_frames_pause_on_blank_lines() {
+        let options = AnimationOptions {
+            speed: 10.0, // 100ms per character, one step per frame
+            blank_line_pause_ms: 300,
+            final_hold_ms: 1000,
+            cursor_blink_ms: 500,
+            ..Default::default()
+        };
+        let frames = plan_typing_frames(&sample_lines(), &options).unwrap();
+
+        // Empty start, 10 characters, two line breaks, then the rest of the hold blinking
+        let typing = &frames[..frames.len() - 2];
+        assert_eq!(typing.len(), 13);
+        assert_eq!(typing[0].cursor, CursorPosition { line: 0, column: 0 });
+        assert_eq!(typing[11], FramePlan {
+            cursor: CursorPosition { line: 1, column: 0 },
+            cursor_visible: true,
+            delay_ms: 400,
+        });
+
+        let hold = &frames[frames.len() - 2..];
+        assert!(hold.iter().all(|frame| frame.cursor == CursorPosition { line: 2, column: 1 }));
+        assert_eq!(
+            hold.iter().map(|frame| frame.cursor_visible).collect::<Vec<_>>(),
+            vec![true, false]
+        );
+        assert_eq!(hold.iter().map(|frame| frame.delay_ms).sum::<u32>(), 1000);
+    }
+
+    #[test]
+    fn test_fast_typing_is_batched() {
+        let options = AnimationOptions {
+            speed: 100.0, // 10ms per character
+            show_cursor: false,
+            ..Default::default()
+        };
+        let frames = plan_typing_frames(&sample_lines(), &options).unwrap();
+
+        assert!(frames[..frames.len() - 1]
+            .iter()
+            .all(|frame| frame.delay_ms >= MIN_FRAME_DELAY_MS as u32));
+        assert_eq!(frames.last().unwrap().delay_ms, 2000);
+    }
+
+    #[test]
+    fn test_line_mode_reveals_whole_lines() {
+        let options = AnimationOptions {
+            reveal: RevealMode::Line,
+            speed: 2.0,
+            show_cursor: false,
+            ..Default::default()
+        };
+        let frames = plan_typing_frames(&sample_lines(), &options).unwrap();
+
+        let cursors: Vec<_> = frames.iter().map(|frame| frame.cursor).collect();
+        assert_eq!(
+            cursors,
+            vec![
+                CursorPosition { line: 0, column: 0 },
+                CursorPosition { line: 0, column: 10 },
+                CursorPosition { line: 1, column: 0 },
+                CursorPosition { line: 2, column: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typing_reveals_whole_graphemes() {
+        let lines = vec![line(1, &[("// cafe\u{301} 👨\u{200D}👩\u{200D}👧\n", "#ffffff")])];
+        assert_eq!(line_length(&lines[0]), 9);
+
+        let revealed = reveal_lines(&lines, &CursorPosition { line: 0, column: 7 });
+        assert_eq!(revealed[0].segments[0].text, "// cafe\u{301}");
+        let revealed = reveal_lines(&lines, &CursorPosition { line: 0, column: 9 });
+        assert_eq!(revealed[0].segments[0].text, "// cafe\u{301} 👨\u{200D}👩\u{200D}👧");
+    }
+
+    #[test]
+    fn test_hold_stays_within_frame_budget() {
+        let hostile = AnimationOptions {
+            final_hold_ms: 600_000,
+            cursor_blink_ms: 1,
+            ..Default::default()
+        };
+        assert!(plan_typing_frames(&sample_lines(), &hostile).is_err());
+        assert!(AnimationOptions { cursor_blink_ms: 1, ..Default::default() }.validate().is_err());
+        assert!(AnimationOptions { final_hold_ms: 600_000, ..Default::default() }
+            .validate()
+            .is_err());
+
+        // The longest allowed hold at the fastest blink, after typing that fills the budget
+        let lines: Vec<_> = (1..=200)
+            .map(|number| line(number, &[("let x = 1;\n", "#ffffff")]))
+            .collect();
+        let options = AnimationOptions {
+            speed: 1000.0,
+            final_hold_ms: MAX_FINAL_HOLD_MS,
+            cursor_blink_ms: MIN_FRAME_DELAY_MS as u32,
+            ..Default::default()
+        };
+        let frames = plan_typing_frames(&lines, &options).unwrap();
+
+        assert!(frames.len() <= MAX_TYPING_FRAMES);
+        let end = frames.last().unwrap().cursor;
+        let hold: Vec<_> = frames.iter().rev().take_while(|frame| frame.cursor == end).collect();
+        assert!(hold.len() > 1);
+        assert_eq!(hold.iter().map(|frame| frame.delay_ms).sum::<u32>(), MAX_FINAL_HOLD_MS);
+    }
+
+    #[test]
+    fn test_invalid_speed_is_rejected() {
+        let options = AnimationOptions {
+            speed: 0.0,
+            ..Default::default()
+        };
+        assert!(plan_typing_frames(&sample_lines(), &options).is_err());
+    }
+
+    /// Frames that differ in a few small patches, over a canvas with transparent corners
+    fn patched_frames() -> Vec<(RgbaImage, u32)> {
+        let mut image = RgbaImage::from_pixel(40, 30, image::Rgba([30, 30, 30, 255]));
+        image.put_pixel(0, 0, image::Rgba([0, 0, 0, 0]));
+        let mut frames = vec![(image.clone(), 100)];
+        for (x, y, color) in [(5, 4, [255, 0, 0, 255]), (20, 10, [0, 255, 0, 255]), (39, 29, [0, 0, 255, 128])] {
+            image.put_pixel(x, y, image::Rgba(color));
+            image.put_pixel(x.saturating_sub(1), y, image::Rgba(color));
+            frames.push((image.clone(), 100));
+        }
+        frames.push((image.clone(), 500));
+        frames
+    }
+
+    #[test]
+    fn test_changed_region_covers_only_the_difference() {
+        let frames = patched_frames();
+
+        assert_eq!(
+            changed_region(&frames[0].0, &frames[1].0),
+            FrameRegion { x: 4, y: 4, width: 2, height: 1 }
+        );
+        let mut moved = frames[1].0.clone();
+        moved.put_pixel(30, 20, image::Rgba([1, 2, 3, 255]));
+        assert_eq!(
+            changed_region(&frames[0].0, &moved),
+            FrameRegion { x: 4, y: 4, width: 27, height: 17 }
+        );
+        // An unchanged frame still carries its delay
+        assert_eq!(changed_region(&frames[3].0, &frames[4].0).width, 1);
+    }
+
+    #[test]
+    fn test_apng_delta_frames_decode_to_the_originals() {
+        use image::AnimationDecoder;
+
+        let frames = patched_frames();
+        let apng = encode_apng(frames.clone().into_iter().map(Ok), 40, 30, 0).unwrap();
+
+        let decoded = image::codecs::png::PngDecoder::new(std::io::Cursor::new(&apng))
+            .unwrap()
+            .apng()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+        assert_eq!(decoded.len(), frames.len());
+        for (decoded, (original, _)) in decoded.iter().zip(&frames) {
+            assert_eq!(decoded.buffer(), original);
+        }
+    }
+
+    #[test]
+    fn test_gif_frames_share_one_palette() {
+        let frames = patched_frames();
+        let last = frames.last().unwrap().0.clone();
+        let gif = encode_gif(frames.into_iter().map(Ok), &last, 0).unwrap();
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::Indexed);
+        let mut decoder = options.read_info(std::io::Cursor::new(&gif)).unwrap();
+        assert_eq!(decoder.global_palette().unwrap().len(), 256 * 3);
+
+        let mut regions = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert!(frame.palette.is_none());
+            regions.push((frame.left, frame.top, frame.width, frame.height));
+        }
+        assert_eq!(regions, vec![(0, 0, 40, 30), (4, 4, 2, 1), (19, 10, 2, 1), (38, 29, 2, 1), (0, 0, 1, 1)]);
+    }
+}
@@ -0,0 +1,314 @@
+use crate::models::errors::AppError;
+use crate::services::graphemes::{self, display_width, grapheme_width};
+use crate::services::syntax_highlighter::HighlightedLine;
+use serde::{Deserialize, Serialize};
+
+/// Upper bound on annotations per request so one export can't stack up thousands of callouts
+pub const MAX_ANNOTATIONS: usize = 32;
+
+/// Longest accepted margin note, in characters
+pub const MAX_NOTE_LENGTH: usize = 280;
+
+/// What an annotation draws over the code
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum AnnotationKind {
+    /// A numbered badge at a position; markers are numbered from 1 in the order given
+    #[default]
+    Marker,
+    /// An arrow from one position to another
+    Arrow,
+    /// A box around the characters from one position to another, inclusive
+    Region,
+    /// Only a margin note, next to the position's line
+    Note,
+}
+
+/// A 1-based line and display column in the code, with tabs expanded to the theme's tab width.
+/// A column past the end of the line lies that many character widths after the code.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CodePosition {
+    pub line: usize,
+    #[serde(default = "first_column")]
+    pub column: usize,
+}
+
+/// A callout drawn over the code, with an optional note in the margin to the right of the window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    #[serde(default)]
+    pub kind: AnnotationKind,
+    pub at: CodePosition,
+    /// End of arrows and regions
+    #[serde(default)]
+    pub to: Option<CodePosition>,
+    /// Margin note text; required for notes. Line breaks are kept.
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Where a position falls among laid out rows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RowPosition {
+    /// Index of the row among the laid out rows
+    pub row: usize,
+    /// Graphemes of the row before the position
+    pub graphemes: usize,
+    /// Columns past the end of the row
+    pub overflow: usize,
+}
+
+fn first_column() -> usize {
+    1
+}
+
+impl CodePosition {
+    /// Creates a position at a 1-based line and column
+    pub fn new(line: usize, column: usize) -> Self {
+        CodePosition { line, column }
+    }
+}
+
+impl Annotation {
+    /// Creates an annotation with no end or note
+    pub fn new(kind: AnnotationKind, at: CodePosition) -> Self {
+        Annotation {
+            kind,
+            at,
+            to: None,
+            note: None,
+        }
+    }
+
+    /// Checks that the annotation is well formed
+    pub fn validate(&self) -> Result<(), AppError> {
+        for position in std::iter::once(&self.at).chain(&self.to) {
+            if position.line == 0 || position.column == 0 {
+                return Err(AppError::image_generation_failed(format!(
+                    "Invalid annotation position {}:{}: lines and columns start at 1",
+                    position.line, position.column
+                )));
+            }
+        }
+
+        if matches!(self.kind, AnnotationKind::Arrow | AnnotationKind::Region) && self.to.is_none() {
+            return Err(AppError::image_generation_failed(format!(
+                "{:?} annotations need an end position",
+                self.kind
+            )));
+        }
+
+        match self.note {
+            Some(ref note) => {
+                if note.trim().is_empty() {
+                    return Err(AppError::image_generation_failed("Annotation notes cannot be empty"));
+                }
+                if note.chars().count() > MAX_NOTE_LENGTH {
+                    return Err(AppError::image_generation_failed(format!(
+                        "Annotation notes must be at most {} characters",
+                        MAX_NOTE_LENGTH
+                    )));
+                }
+                if note.chars().any(|ch| ch.is_control() && ch != '\n') {
+                    return Err(AppError::image_generation_failed(
+                        "Annotation notes cannot contain control characters other than line breaks",
+                    ));
+                }
+            }
+            None if self.kind == AnnotationKind::Note => {
+                return Err(AppError::image_generation_failed("Note annotations need a note"));
+            }
+            None => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// Validates every annotation and their count
+pub fn validate_annotations(annotations: &[Annotation]) -> Result<(), AppError> {
+    if annotations.len() > MAX_ANNOTATIONS {
+        return Err(AppError::image_generation_failed(format!(
+            "At most {} annotations are supported",
+            MAX_ANNOTATIONS
+        )));
+    }
+
+    annotations.iter().try_for_each(Annotation::validate)
+}
+
+/// Number shown on each annotation's badge; only markers have one
+pub fn marker_numbers(annotations: &[Annotation]) -> Vec<Option<usize>> {
+    let mut count = 0;
+    annotations
+        .iter()
+        .map(|annotation| {
+            (annotation.kind == AnnotationKind::Marker).then(|| {
+                count += 1;
+                count
+            })
+        })
+        .collect()
+}
+
+/// Finds the row a position falls on. Wrapped lines are followed onto their continuation
+/// rows, skipping the indentation those repeat and not counting whitespace dropped at the
+/// break; positions past the end of the line stay on its last row.
+/// Returns `None` if none of the rows belong to the position's line.
+pub fn locate(rows: &[HighlightedLine], position: &CodePosition) -> Option<RowPosition> {
+    let line_rows: Vec<(usize, String)> = rows
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| row.line_number == position.line)
+        .map(|(index, row)| (index, row.segments.iter().map(|segment| segment.text.as_str()).collect()))
+        .collect();
+    let leading_whitespace = |text: &str| text.chars().take_while(|ch| *ch == ' ').count();
+    let indent = line_rows.first().map_or(0, |(_, text)| leading_whitespace(text));
+
+    let mut remaining = position.column - 1;
+    for (i, (index, text)) in line_rows.iter().enumerate() {
+        let skip = if rows[*index].continuation {
+            indent.min(leading_whitespace(text))
+        } else {
+            0
+        };
+
+        let mut count = skip;
+        for grapheme in graphemes::graphemes(text).skip(skip) {
+            let width = grapheme_width(grapheme);
+            if remaining < width {
+                return Some(RowPosition {
+                    row: *index,
+                    graphemes: count,
+                    overflow: 0,
+                });
+            }
+            remaining -= width;
+            count += 1;
+        }
+
+        if i + 1 == line_rows.len() {
+            return Some(RowPosition {
+                row: *index,
+                graphemes: count,
+                overflow: remaining,
+            });
+        }
+    }
+
+    None
+}
+
+/// Breaks a note into lines of at most `columns` grid columns at spaces, splitting words
+/// that don't fit on a line of their own. Line breaks in the note are kept.
+pub fn wrap_note(note: &str, columns: usize) -> Vec<String> {
+    let columns = columns.max(1);
+    let mut lines = Vec::new();
+
+    for paragraph in note.trim().lines() {
+        let mut line = String::new();
+
+        for word in paragraph.split_whitespace() {
+            let needed = display_width(&line) + usize::from(!line.is_empty()) + display_width(word);
+            if needed > columns && !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+
+            for grapheme in graphemes::graphemes(word) {
+                if display_width(&line) + grapheme_width(grapheme) > columns && !line.is_empty() {
+                    lines.push(std::mem::take(&mut line));
+                }
+                line.push_str(grapheme);
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::syntax_highlighter::{HighlightedSegment, SegmentStyle};
+
+    fn row(text: &str, line_number: usize, continuation: bool) -> HighlightedLine {
+        HighlightedLine {
+            segments: vec![HighlightedSegment {
+                text: text.to_string(),
+                style: SegmentStyle {
+                    color: "#ffffff".to_string(),
+                    bold: false,
+                    italic: false,
+                    underline: false,
+                    background: None,
+                    redacted: false,
+                },
+            }],
+            line_number,
+            gutter: None,
+            continuation,
+        }
+    }
+
+    #[test]
+    fn test_positions_follow_wrapped_rows() {
+        let rows = vec![
+            row("fn main() {", 1, false),
+            row("    let values = [1, 2,", 2, false),
+            row("    3, 4];", 2, true),
+            row("}", 3, false),
+        ];
+
+        let at = |line, column| locate(&rows, &CodePosition::new(line, column));
+        assert_eq!(at(1, 4), Some(RowPosition { row: 0, graphemes: 3, overflow: 0 }));
+        assert_eq!(at(2, 23), Some(RowPosition { row: 1, graphemes: 22, overflow: 0 }));
+        // The continuation row's repeated indentation isn't part of the line, nor is the
+        // space the wrap dropped at the break
+        assert_eq!(at(2, 24), Some(RowPosition { row: 2, graphemes: 4, overflow: 0 }));
+        assert_eq!(at(3, 5), Some(RowPosition { row: 3, graphemes: 1, overflow: 3 }));
+        assert_eq!(at(4, 1), None);
+
+        // Wide characters take two columns
+        let wide = vec![row("名前 = 1", 1, false)];
+        assert_eq!(
+            locate(&wide, &CodePosition::new(1, 5)),
+            Some(RowPosition { row: 0, graphemes: 2, overflow: 0 })
+        );
+    }
+
+    #[test]
+    fn test_markers_are_numbered_in_order() {
+        let annotations = vec![
+            Annotation::new(AnnotationKind::Marker, CodePosition::new(1, 1)),
+            Annotation::new(AnnotationKind::Region, CodePosition::new(2, 1)),
+            Annotation::new(AnnotationKind::Marker, CodePosition::new(3, 1)),
+        ];
+        assert_eq!(marker_numbers(&annotations), vec![Some(1), None, Some(2)]);
+
+        // Regions need an end, notes need text and positions start at 1
+        assert!(validate_annotations(&annotations).is_err());
+        let note = Annotation::new(AnnotationKind::Note, CodePosition::new(1, 1));
+        assert!(note.validate().is_err());
+        assert!(Annotation::new(AnnotationKind::Marker, CodePosition::new(1, 0)).validate().is_err());
+        let arrow = Annotation {
+            to: Some(CodePosition::new(2, 5)),
+            note: Some("moved here".to_string()),
+            ..Annotation::new(AnnotationKind::Arrow, CodePosition::new(1, 1))
+        };
+        assert!(arrow.validate().is_ok());
+    }
+
+    #[test]
+    fn test_notes_wrap_at_spaces() {
+        assert_eq!(
+            wrap_note("allocate the buffer on the heap", 12),
+            vec!["allocate the", "buffer on", "the heap"]
+        );
+        assert_eq!(wrap_note("borrowed\nhere", 20), vec!["borrowed", "here"]);
+        assert_eq!(wrap_note("unbreakable_identifier", 10), vec!["unbreakabl", "e_identifi", "er"]);
+    }
+}
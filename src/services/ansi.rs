@@ -0,0 +1,308 @@
+use crate::models::theme::Theme;
+use crate::services::syntax_highlighter::{HighlightedLine, HighlightedSegment, SegmentStyle};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Alpha appended to faint text, about 60% opacity
+const FAINT_ALPHA: &str = "99";
+
+/// A color named by an escape code
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AnsiColor {
+    /// One of the 256 indexed colors; the first 16 come from the theme's palette
+    Indexed(u8),
+    Rgb(u8, u8, u8),
+}
+
+/// Text attributes set by SGR (Select Graphic Rendition) escape codes
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Attributes {
+    foreground: Option<AnsiColor>,
+    background: Option<AnsiColor>,
+    bold: bool,
+    faint: bool,
+    italic: bool,
+    underline: bool,
+    inverse: bool,
+}
+
+/// Turns terminal output containing ANSI escape codes into styled lines.
+/// Colors, bold, faint, italic, underline and inverse are kept; other escape sequences and
+/// control characters are dropped, and a carriage return starts its line over.
+pub fn parse_ansi(input: &str, theme: &Theme) -> Vec<HighlightedLine> {
+    let mut lines = Vec::new();
+    let mut segments: Vec<HighlightedSegment> = Vec::new();
+    let mut attributes = Attributes::default();
+    let mut style = segment_style(&attributes, theme);
+    let mut chars = input.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\u{1b}' => match chars.next() {
+                Some('[') => {
+                    if let Some(parameters) = read_sgr(&mut chars) {
+                        apply_sgr(&mut attributes, &parameters);
+                        style = segment_style(&attributes, theme);
+                    }
+                }
+                // Operating system commands, such as titles and hyperlinks, end with BEL or ST
+                Some(']') => {
+                    while let Some(ch) = chars.next() {
+                        if ch == '\u{7}' || (ch == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            '\u{9b}' => {
+                if let Some(parameters) = read_sgr(&mut chars) {
+                    apply_sgr(&mut attributes, &parameters);
+                    style = segment_style(&attributes, theme);
+                }
+            }
+            '\n' => {
+                lines.push(std::mem::take(&mut segments));
+            }
+            // A carriage return ending the line belongs to a CRLF; anywhere else progress
+            // output is about to redraw the line
+            '\r' => {
+                if chars.peek() != Some(&'\n') {
+                    segments.clear();
+                }
+            }
+            '\t' => push_char(&mut segments, ch, &style),
+            ch if ch.is_control() => {}
+            ch => push_char(&mut segments, ch, &style),
+        }
+    }
+
+    // Like `str::lines`, a trailing newline doesn't start another line
+    if !segments.is_empty() {
+        lines.push(segments);
+    }
+
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, segments)| HighlightedLine {
+            segments,
+            line_number: index + 1,
+            gutter: None,
+            continuation: false,
+        })
+        .collect()
+}
+
+/// Reads the rest of a control sequence, returning its parameters if it sets text attributes
+fn read_sgr(chars: &mut Peekable<Chars>) -> Option<String> {
+    let mut parameters = String::new();
+
+    for ch in chars.by_ref() {
+        match ch {
+            '\u{30}'..='\u{3f}' | '\u{20}'..='\u{2f}' => parameters.push(ch),
+            // Private sequences such as `ESC[?25l` only look like SGR
+            'm' if parameters.chars().all(|ch| ch.is_ascii_digit() || ch == ';' || ch == ':') => {
+                return Some(parameters)
+            }
+            // Any other final byte, or a character that cuts the sequence short
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+/// Applies SGR parameters such as `1;31` or `38;2;255;128;0` to the current attributes
+fn apply_sgr(attributes: &mut Attributes, parameters: &str) {
+    // Empty parameters count as 0, so `ESC[m` is a reset like `ESC[0m`
+    let mut groups = parameters.split(';').map(|group| {
+        group
+            .split(':')
+            .map(|value| value.parse::<u32>().unwrap_or(0))
+            .collect::<Vec<u32>>()
+    });
+
+    while let Some(group) = groups.next() {
+        match group[0] {
+            0 => *attributes = Attributes::default(),
+            1 => attributes.bold = true,
+            2 => attributes.faint = true,
+            3 => attributes.italic = true,
+            4 => attributes.underline = true,
+            7 => attributes.inverse = true,
+            21 => attributes.underline = true,
+            22 => {
+                attributes.bold = false;
+                attributes.faint = false;
+            }
+            23 => attributes.italic = false,
+            24 => attributes.underline = false,
+            27 => attributes.inverse = false,
+            code @ 30..=37 => attributes.foreground = Some(AnsiColor::Indexed((code - 30) as u8)),
+            38 => attributes.foreground = extended_color(&group, &mut groups).or(attributes.foreground),
+            39 => attributes.foreground = None,
+            code @ 40..=47 => attributes.background = Some(AnsiColor::Indexed((code - 40) as u8)),
+            48 => attributes.background = extended_color(&group, &mut groups).or(attributes.background),
+            49 => attributes.background = None,
+            code @ 90..=97 => attributes.foreground = Some(AnsiColor::Indexed((code - 90 + 8) as u8)),
+            code @ 100..=107 => attributes.background = Some(AnsiColor::Indexed((code - 100 + 8) as u8)),
+            _ => {}
+        }
+    }
+}
+
+/// Reads a 256-color (`5;n`) or truecolor (`2;r;g;b`) value after a 38 or 48, either from
+/// the same colon-separated group or from the semicolon-separated groups that follow
+fn extended_color(group: &[u32], rest: &mut impl Iterator<Item = Vec<u32>>) -> Option<AnsiColor> {
+    let values: Vec<u32> = if group.len() > 1 {
+        let values = group[1..].to_vec();
+        // `38:2:<colorspace>:r:g:b` carries an extra, ignored colorspace ID
+        match values.as_slice() {
+            [2, _, r, g, b] => vec![2, *r, *g, *b],
+            _ => values,
+        }
+    } else {
+        let kind = rest.next()?.first().copied()?;
+        let count = match kind {
+            5 => 1,
+            2 => 3,
+            _ => 0,
+        };
+        std::iter::once(kind)
+            .chain(rest.take(count).map(|group| group.first().copied().unwrap_or(0)))
+            .collect()
+    };
+
+    let channel = |value: u32| value.min(255) as u8;
+    match values.as_slice() {
+        [5, index, ..] => Some(AnsiColor::Indexed(channel(*index))),
+        [2, r, g, b, ..] => Some(AnsiColor::Rgb(channel(*r), channel(*g), channel(*b))),
+        _ => None,
+    }
+}
+
+/// Hex color of an escape code color
+fn resolve_color(color: AnsiColor, theme: &Theme) -> String {
+    let (r, g, b) = match color {
+        AnsiColor::Indexed(index) if index < 16 => {
+            return theme.ansi_colors.colors()[index as usize].to_string();
+        }
+        // A 6x6x6 color cube
+        AnsiColor::Indexed(index @ 16..=231) => {
+            let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let index = index - 16;
+            (level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        // A ramp of 24 grays, without black and white
+        AnsiColor::Indexed(index) => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+        AnsiColor::Rgb(r, g, b) => (r, g, b),
+    };
+
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Segment style for a set of attributes; unset colors fall back to the theme's text and background
+fn segment_style(attributes: &Attributes, theme: &Theme) -> SegmentStyle {
+    let foreground = attributes.foreground.map(|color| resolve_color(color, theme));
+    let background = attributes.background.map(|color| resolve_color(color, theme));
+
+    let (mut color, background) = if attributes.inverse {
+        (
+            background.unwrap_or_else(|| theme.background.primary.clone()),
+            Some(foreground.unwrap_or_else(|| theme.text_color())),
+        )
+    } else {
+        (foreground.unwrap_or_else(|| theme.text_color()), background)
+    };
+
+    // Only six-digit colors take the alpha, so one is never added twice
+    if attributes.faint && color.len() == 7 {
+        color.push_str(FAINT_ALPHA);
+    }
+
+    SegmentStyle {
+        color,
+        bold: attributes.bold,
+        italic: attributes.italic,
+        underline: attributes.underline,
+        background,
+        redacted: false,
+    }
+}
+
+/// Adds a character to the line, extending the last segment if the style hasn't changed
+fn push_char(segments: &mut Vec<HighlightedSegment>, ch: char, style: &SegmentStyle) {
+    match segments.last_mut() {
+        Some(last) if last.style == *style => last.text.push(ch),
+        _ => segments.push(HighlightedSegment {
+            text: ch.to_string(),
+            style: style.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(line: &HighlightedLine) -> Vec<&str> {
+        line.segments.iter().map(|segment| segment.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_basic_colors_and_attributes() {
+        let theme = Theme::default_dark();
+        let input = "\u{1b}[1;31merror\u{1b}[0m: \u{1b}[4mmismatched\u{1b}[24m types\r\n\u{1b}[92mok\u{1b}[m";
+        let lines = parse_ansi(input, &theme);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(texts(&lines[0]), vec!["error", ": ", "mismatched", " types"]);
+        let error = &lines[0].segments[0].style;
+        assert!(error.bold && error.color == theme.ansi_colors.red);
+        assert_eq!(lines[0].segments[1].style.color, theme.text_color());
+        assert!(lines[0].segments[2].style.underline);
+        assert!(!lines[0].segments[3].style.underline);
+        assert_eq!(lines[1].segments[0].style.color, theme.ansi_colors.bright_green);
+    }
+
+    #[test]
+    fn test_extended_colors() {
+        let theme = Theme::default_dark();
+        let input = "\u{1b}[38;5;196ma\u{1b}[38;5;244mb\u{1b}[38;2;255;128;0mc\u{1b}[48:2::1:2:3md\u{1b}[38;5;4me";
+        let colors: Vec<(String, Option<String>)> = parse_ansi(input, &theme)[0]
+            .segments
+            .iter()
+            .map(|segment| (segment.style.color.clone(), segment.style.background.clone()))
+            .collect();
+
+        assert_eq!(colors[0].0, "#ff0000");
+        assert_eq!(colors[1].0, "#808080");
+        assert_eq!(colors[2].0, "#ff8000");
+        assert_eq!(colors[3], ("#ff8000".to_string(), Some("#010203".to_string())));
+        assert_eq!(colors[4].0, theme.ansi_colors.blue);
+    }
+
+    #[test]
+    fn test_inverse_faint_and_stripped_sequences() {
+        let mut theme = Theme::default_dark();
+        theme.ansi_colors.yellow = "#123456".to_string();
+        let input = "\u{1b}]8;;https://example.com\u{7}link\u{1b}]8;;\u{1b}\\ \u{1b}[?25l\u{1b}[2K\u{1b}[7;33minv\u{1b}[27;2mdim\u{7}\n\
+                     50%\r100%\n\n";
+        let lines = parse_ansi(input, &theme);
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(texts(&lines[0]), vec!["link ", "inv", "dim"]);
+        let inverse = &lines[0].segments[1].style;
+        assert_eq!(inverse.color, theme.background.primary);
+        assert_eq!(inverse.background.as_deref(), Some("#123456"));
+        assert_eq!(lines[0].segments[2].style.color, "#12345699");
+
+        // Progress output keeps only what was drawn last, and blank lines stay
+        assert_eq!(texts(&lines[1]), vec!["100%"]);
+        assert!(lines[2].segments.is_empty());
+    }
+}
@@ -0,0 +1,281 @@
+use crate::models::errors::AppError;
+use crate::models::theme::{BackgroundImage, ImageFit, ImageOverlay, OverlayKind};
+use crate::services::blur;
+use crate::services::scene::ImageNode;
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Longest side pictures may have; uploads are rejected beyond it, and anything larger
+/// that still reaches the cache is scaled down when decoded
+pub const MAX_IMAGE_SIDE: u32 = 4096;
+/// Decoded pictures, and prepared backgrounds, each kept in memory at most
+const MAX_CACHED_IMAGES: usize = 32;
+
+/// Uploaded background pictures, decoded once and then fitted, blurred and tinted for each
+/// canvas size they're drawn at. Every renderer embeds the same prepared bitmap.
+pub struct BackgroundImageCache {
+    sources: Arc<Mutex<HashMap<String, Arc<RgbaImage>>>>,
+    prepared: Arc<Mutex<HashMap<String, ImageNode>>>,
+}
+
+impl BackgroundImageCache {
+    /// Creates an empty cache
+    pub fn new() -> Self {
+        BackgroundImageCache {
+            sources: Arc::new(Mutex::new(HashMap::new())),
+            prepared: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// True once the picture uploaded as `file_id` has been decoded
+    pub fn contains(&self, file_id: &str) -> bool {
+        self.sources.lock().unwrap().contains_key(file_id)
+    }
+
+    /// Decodes an uploaded PNG or JPEG and keeps it under its file ID
+    pub fn insert(&self, file_id: &str, data: &[u8]) -> Result<(), AppError> {
+        let mut image = image::load_from_memory(data)
+            .map_err(|e| {
+                AppError::image_generation_failed(format!(
+                    "Failed to decode background image {}: {}",
+                    file_id, e
+                ))
+            })?
+            .to_rgba8();
+
+        let (width, height) = image.dimensions();
+        if width.max(height) > MAX_IMAGE_SIDE {
+            let ratio = MAX_IMAGE_SIDE as f32 / width.max(height) as f32;
+            image = imageops::resize(
+                &image,
+                ((width as f32 * ratio).round() as u32).max(1),
+                ((height as f32 * ratio).round() as u32).max(1),
+                FilterType::Triangle,
+            );
+        }
+
+        insert_bounded(&self.sources, file_id.to_string(), Arc::new(image));
+        Ok(())
+    }
+
+    /// The picture of `style` fitted to a `width` by `height` pixel area at the origin.
+    /// `scale` converts layout units to pixels for tiles and blur.
+    pub fn node(
+        &self,
+        style: &BackgroundImage,
+        (width, height): (u32, u32),
+        scale: f32,
+        opacity: f32,
+    ) -> Result<ImageNode, AppError> {
+        let key = format!(
+            "{}:{:?}:{}:{:?}:{}x{}@{}:{}",
+            style.file_id, style.fit, style.blur, style.overlay, width, height, scale, opacity
+        );
+
+        // Check cache first
+        {
+            let prepared = self.prepared.lock().unwrap();
+            if let Some(node) = prepared.get(&key) {
+                return Ok(node.clone());
+            }
+        }
+
+        let source = self.sources.lock().unwrap().get(&style.file_id).cloned().ok_or_else(|| {
+            AppError::image_generation_failed(format!(
+                "Background image {} has not been uploaded",
+                style.file_id
+            ))
+        })?;
+
+        let (mut image, x, y) = fit_image(&source, style.fit, (width.max(1), height.max(1)), scale);
+        blur::blur_image(&mut image, style.blur * scale / 2.0);
+        tint_image(&mut image, style.overlay, opacity);
+
+        let node = ImageNode {
+            x: x as f32,
+            y: y as f32,
+            width: image.width() as f32,
+            height: image.height() as f32,
+            image: Arc::new(image),
+        };
+
+        // Cache the result
+        insert_bounded(&self.prepared, key, node.clone());
+
+        Ok(node)
+    }
+}
+
+impl Default for BackgroundImageCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Inserts into a cache, first dropping an entry if it's full
+fn insert_bounded<T>(cache: &Mutex<HashMap<String, T>>, key: String, value: T) {
+    let mut cache = cache.lock().unwrap();
+    if cache.len() >= MAX_CACHED_IMAGES && !cache.contains_key(&key) {
+        if let Some(evicted) = cache.keys().next().cloned() {
+            cache.remove(&evicted);
+        }
+    }
+    cache.insert(key, value);
+}
+
+/// Scales and crops, or repeats, a picture for an area. Returns the bitmap and its offset;
+/// contained pictures are centered and may leave the sides of the area uncovered.
+fn fit_image(source: &RgbaImage, fit: ImageFit, (width, height): (u32, u32), scale: f32) -> (RgbaImage, u32, u32) {
+    let (source_width, source_height) = (source.width() as f32, source.height() as f32);
+    let (area_width, area_height) = (width as f32, height as f32);
+
+    match fit {
+        ImageFit::Cover => {
+            // Crop the middle of the picture to the area's aspect ratio
+            let ratio = (area_width / source_width).max(area_height / source_height);
+            let crop_width = ((area_width / ratio).round() as u32).clamp(1, source.width());
+            let crop_height = ((area_height / ratio).round() as u32).clamp(1, source.height());
+            let cropped = imageops::crop_imm(
+                source,
+                (source.width() - crop_width) / 2,
+                (source.height() - crop_height) / 2,
+                crop_width,
+                crop_height,
+            )
+            .to_image();
+
+            (imageops::resize(&cropped, width, height, FilterType::Triangle), 0, 0)
+        }
+        ImageFit::Contain => {
+            let ratio = (area_width / source_width).min(area_height / source_height);
+            let fitted_width = ((source_width * ratio).round() as u32).clamp(1, width);
+            let fitted_height = ((source_height * ratio).round() as u32).clamp(1, height);
+
+            (
+                imageops::resize(source, fitted_width, fitted_height, FilterType::Triangle),
+                (width - fitted_width) / 2,
+                (height - fitted_height) / 2,
+            )
+        }
+        ImageFit::Tile => {
+            // One image pixel per layout unit
+            let tile = imageops::resize(
+                source,
+                ((source_width * scale).round() as u32).max(1),
+                ((source_height * scale).round() as u32).max(1),
+                FilterType::Triangle,
+            );
+            let tiled = RgbaImage::from_fn(width, height, |x, y| {
+                *tile.get_pixel(x % tile.width(), y % tile.height())
+            });
+
+            (tiled, 0, 0)
+        }
+    }
+}
+
+/// Washes a bitmap toward black or white and scales its alpha by the background's opacity
+fn tint_image(image: &mut RgbaImage, overlay: Option<ImageOverlay>, opacity: f32) {
+    let (target, amount) = match overlay {
+        Some(ImageOverlay {
+            kind: OverlayKind::Darken,
+            amount,
+        }) => (0.0, amount),
+        Some(ImageOverlay {
+            kind: OverlayKind::Lighten,
+            amount,
+        }) => (255.0, amount),
+        None => (0.0, 0.0),
+    };
+    let opacity = opacity.clamp(0.0, 1.0);
+
+    for pixel in image.pixels_mut() {
+        for channel in 0..3 {
+            let value = pixel[channel] as f32;
+            pixel[channel] = (value + (target - value) * amount).round() as u8;
+        }
+        pixel[3] = (pixel[3] as f32 * opacity).round() as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    /// A picture whose left half is red and right half is blue
+    fn halves(width: u32, height: u32) -> RgbaImage {
+        RgbaImage::from_fn(width, height, |x, _| {
+            if x < width / 2 {
+                Rgba([255, 0, 0, 255])
+            } else {
+                Rgba([0, 0, 255, 255])
+            }
+        })
+    }
+
+    #[test]
+    fn test_fit_modes() {
+        let source = halves(200, 100);
+
+        // Cover crops the sides of a wide picture in a square area
+        let (cover, x, y) = fit_image(&source, ImageFit::Cover, (50, 50), 1.0);
+        assert_eq!((cover.dimensions(), x, y), ((50, 50), 0, 0));
+        assert_eq!(*cover.get_pixel(2, 25), Rgba([255, 0, 0, 255]));
+        assert_eq!(*cover.get_pixel(47, 25), Rgba([0, 0, 255, 255]));
+
+        // Contain letterboxes it
+        let (contain, x, y) = fit_image(&source, ImageFit::Contain, (50, 50), 1.0);
+        assert_eq!((contain.dimensions(), x, y), ((50, 25), 0, 12));
+
+        // Tiles repeat at their scaled size
+        let (tiled, _, _) = fit_image(&source, ImageFit::Tile, (500, 100), 2.0);
+        assert_eq!(tiled.dimensions(), (500, 100));
+        assert_eq!(tiled.get_pixel(10, 10), tiled.get_pixel(410, 10));
+        assert_eq!(*tiled.get_pixel(250, 10), Rgba([0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn test_overlays() {
+        let mut darkened = halves(4, 1);
+        let darken = ImageOverlay {
+            kind: OverlayKind::Darken,
+            amount: 0.5,
+        };
+        tint_image(&mut darkened, Some(darken), 0.5);
+        assert_eq!(*darkened.get_pixel(0, 0), Rgba([128, 0, 0, 128]));
+
+        let mut lightened = halves(4, 1);
+        let lighten = ImageOverlay {
+            kind: OverlayKind::Lighten,
+            amount: 1.0,
+        };
+        tint_image(&mut lightened, Some(lighten), 1.0);
+        assert_eq!(*lightened.get_pixel(3, 0), Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn test_prepared_backgrounds_need_an_upload() {
+        let cache = BackgroundImageCache::new();
+        let style = BackgroundImage {
+            file_id: "0f8fad5b-d9cb-469f-a165-70867728950e".to_string(),
+            fit: ImageFit::Cover,
+            blur: 0.0,
+            overlay: None,
+        };
+        assert!(cache.node(&style, (10, 10), 1.0, 1.0).is_err());
+        assert!(cache.insert(&style.file_id, b"not an image").is_err());
+
+        let mut png = Vec::new();
+        halves(8, 8)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+            .unwrap();
+        cache.insert(&style.file_id, &png).unwrap();
+        assert!(cache.contains(&style.file_id));
+
+        let node = cache.node(&style, (16, 4), 2.0, 1.0).unwrap();
+        assert_eq!((node.width, node.height), (16.0, 4.0));
+    }
+}
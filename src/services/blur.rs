@@ -0,0 +1,197 @@
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+
+/// Strongest blur run at full resolution on bitmaps; stronger ones run on a scaled-down copy
+const MAX_DIRECT_SIGMA: f32 = 4.0;
+
+/// Builds a normalized 1D Gaussian kernel reaching three standard deviations to each side
+pub fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+    if sigma <= 0.0 {
+        return vec![1.0];
+    }
+
+    let radius = (sigma * 3.0).ceil() as i32;
+    let denominator = 2.0 * sigma * sigma;
+    let weights: Vec<f32> = (-radius..=radius)
+        .map(|offset| (-((offset * offset) as f32) / denominator).exp())
+        .collect();
+    let total: f32 = weights.iter().sum();
+
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+/// Blurs a row-major single-channel plane in place with a separable Gaussian.
+/// Samples beyond the edges repeat the nearest edge value.
+pub fn gaussian_blur(plane: &mut [f32], width: usize, height: usize, sigma: f32) {
+    if sigma <= 0.0 || width == 0 || height == 0 {
+        return;
+    }
+    debug_assert_eq!(plane.len(), width * height);
+
+    let kernel = gaussian_kernel(sigma);
+
+    // Horizontal pass, then the vertical pass as a horizontal pass over the transpose
+    let horizontal = blur_rows(plane, width, height, &kernel);
+    let transposed = transpose(&horizontal, width, height);
+    let vertical = blur_rows(&transposed, height, width, &kernel);
+
+    plane.copy_from_slice(&transpose(&vertical, height, width));
+}
+
+/// Gaussian blurs a bitmap with premultiplied alpha. Pixels beyond the edges repeat the
+/// nearest edge, so blurred bitmaps don't fade out at the sides.
+pub fn blur_image(image: &mut RgbaImage, sigma: f32) {
+    if sigma <= 0.0 {
+        return;
+    }
+
+    // Strong blurs leave no fine detail, so they're computed at a lower resolution
+    if sigma > MAX_DIRECT_SIGMA {
+        let factor = (sigma / MAX_DIRECT_SIGMA).ceil() as u32;
+        let (width, height) = image.dimensions();
+        let mut small = imageops::resize(
+            image,
+            (width / factor).max(1),
+            (height / factor).max(1),
+            FilterType::Triangle,
+        );
+        blur_image(&mut small, sigma / factor as f32);
+        *image = imageops::resize(&small, width, height, FilterType::Triangle);
+        return;
+    }
+
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let mut planes = vec![vec![0.0f32; width * height]; 4];
+    for (index, pixel) in image.pixels().enumerate() {
+        let alpha = pixel[3] as f32 / 255.0;
+        for channel in 0..3 {
+            planes[channel][index] = pixel[channel] as f32 * alpha;
+        }
+        planes[3][index] = alpha;
+    }
+
+    for plane in &mut planes {
+        gaussian_blur(plane, width, height, sigma);
+    }
+
+    for (index, pixel) in image.pixels_mut().enumerate() {
+        let alpha = planes[3][index];
+        for channel in 0..3 {
+            pixel[channel] = if alpha > 0.0 {
+                (planes[channel][index] / alpha).round().clamp(0.0, 255.0) as u8
+            } else {
+                0
+            };
+        }
+        pixel[3] = (alpha * 255.0).round().clamp(0.0, 255.0) as u8;
+    }
+}
+
+/// Convolves every row with the kernel; rows equal to the previous one reuse its result
+fn blur_rows(plane: &[f32], width: usize, height: usize, kernel: &[f32]) -> Vec<f32> {
+    let radius = (kernel.len() / 2) as isize;
+    let mut output = vec![0.0; plane.len()];
+
+    for y in 0..height {
+        let row = &plane[y * width..(y + 1) * width];
+
+        if y > 0 && row == &plane[(y - 1) * width..y * width] {
+            output.copy_within((y - 1) * width..y * width, y * width);
+            continue;
+        }
+
+        // Flat rows stay flat, which skips the empty margins around shapes
+        if row.iter().all(|&value| value == row[0]) {
+            output[y * width..(y + 1) * width].fill(row[0]);
+            continue;
+        }
+
+        for x in 0..width {
+            let mut sum = 0.0;
+            for (tap, weight) in kernel.iter().enumerate() {
+                let sample = (x as isize + tap as isize - radius).clamp(0, width as isize - 1);
+                sum += row[sample as usize] * weight;
+            }
+            output[y * width + x] = sum;
+        }
+    }
+
+    output
+}
+
+fn transpose(plane: &[f32], width: usize, height: usize) -> Vec<f32> {
+    let mut transposed = vec![0.0; plane.len()];
+    for y in 0..height {
+        for x in 0..width {
+            transposed[x * height + y] = plane[y * width + x];
+        }
+    }
+    transposed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernel_is_normalized_and_symmetric() {
+        let kernel = gaussian_kernel(4.0);
+
+        assert_eq!(kernel.len(), 25);
+        assert!((kernel.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert_eq!(kernel[0], kernel[24]);
+        assert!(kernel[12] > kernel[11]);
+    }
+
+    #[test]
+    fn test_blur_spreads_a_point_and_keeps_its_energy() {
+        let (width, height) = (41, 41);
+        let mut plane = vec![0.0; width * height];
+        plane[20 * width + 20] = 1.0;
+
+        gaussian_blur(&mut plane, width, height, 3.0);
+
+        let total: f32 = plane.iter().sum();
+        assert!((total - 1.0).abs() < 1e-4);
+
+        // The result is the outer product of two 1D kernels, so it is radially symmetric
+        let at = |x: usize, y: usize| plane[y * width + x];
+        assert!((at(23, 20) - at(20, 23)).abs() < 1e-6);
+        assert!((at(17, 20) - at(23, 20)).abs() < 1e-6);
+        assert!(at(20, 20) > at(21, 20) && at(21, 20) > at(22, 20));
+        assert_eq!(at(0, 0), 0.0);
+    }
+
+    #[test]
+    fn test_blur_softens_an_edge_to_half_coverage() {
+        let (width, height) = (40, 4);
+        let mut plane: Vec<f32> = (0..width * height)
+            .map(|index| if index % width < 20 { 1.0 } else { 0.0 })
+            .collect();
+
+        gaussian_blur(&mut plane, width, height, 4.0);
+
+        // The pixels either side of the edge straddle 50% coverage symmetrically
+        let row = &plane[width..2 * width];
+        assert!((row[19] + row[20] - 1.0).abs() < 1e-4);
+        assert!(row[0] > 0.999 && row[39] < 0.001);
+        assert!(row[15] > row[19] && row[19] > row[20] && row[20] > row[25]);
+    }
+
+    #[test]
+    fn test_bitmap_blur_mixes_colors_and_keeps_edges_opaque() {
+        let mut image = RgbaImage::from_fn(64, 8, |x, _| {
+            if x < 32 {
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                image::Rgba([0, 0, 255, 255])
+            }
+        });
+        blur_image(&mut image, 12.0);
+
+        let middle = image.get_pixel(32, 4);
+        assert!(middle[0] > 60 && middle[2] > 60, "{:?}", middle);
+        // Edges repeat rather than fading to transparent
+        assert_eq!(image.get_pixel(0, 0)[3], 255);
+    }
+}
@@ -10,7 +10,6 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone)]
 struct CachedItem<V> {
     value: V,
-    created_at: SystemTime,
     expires_at: Option<SystemTime>,
     access_count: usize,
     last_accessed: SystemTime,
@@ -23,7 +22,6 @@ impl<V> CachedItem<V> {
         
         Self {
             value,
-            created_at: now,
             expires_at,
             access_count: 0,
             last_accessed: now,
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+/// Smallest fraction of the theme's font size code is shrunk to when fitting a preset canvas
+pub const MIN_FIT_FONT_SCALE: f32 = 0.4;
+
+/// Named canvas sizes for sharing snippets. The window is centered on the canvas, and the
+/// font shrinks if the code wouldn't fit otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CanvasPreset {
+    /// Twitter/X in-feed image, 16:9
+    Twitter,
+    /// LinkedIn shared image, 1.91:1
+    LinkedIn,
+    /// Instagram square post, 1:1
+    InstagramSquare,
+    /// Instagram portrait post, 4:5
+    InstagramPortrait,
+    /// Open Graph link preview image
+    OpenGraph,
+    /// Presentation slide, 16:9
+    Slide,
+}
+
+impl CanvasPreset {
+    /// Every preset, in the order they're offered
+    pub const ALL: [CanvasPreset; 6] = [
+        CanvasPreset::Twitter,
+        CanvasPreset::LinkedIn,
+        CanvasPreset::InstagramSquare,
+        CanvasPreset::InstagramPortrait,
+        CanvasPreset::OpenGraph,
+        CanvasPreset::Slide,
+    ];
+
+    /// Width and height at standard resolution; High and Ultra multiply both
+    pub fn size(self) -> (u32, u32) {
+        match self {
+            CanvasPreset::Twitter => (1600, 900),
+            CanvasPreset::LinkedIn => (1200, 627),
+            CanvasPreset::InstagramSquare => (1080, 1080),
+            CanvasPreset::InstagramPortrait => (1080, 1350),
+            CanvasPreset::OpenGraph => (1200, 630),
+            CanvasPreset::Slide => (1920, 1080),
+        }
+    }
+
+    /// Name shown to users
+    pub fn label(self) -> &'static str {
+        match self {
+            CanvasPreset::Twitter => "Twitter/X (16:9)",
+            CanvasPreset::LinkedIn => "LinkedIn (1.91:1)",
+            CanvasPreset::InstagramSquare => "Instagram square (1:1)",
+            CanvasPreset::InstagramPortrait => "Instagram portrait (4:5)",
+            CanvasPreset::OpenGraph => "Open Graph image (1200×630)",
+            CanvasPreset::Slide => "Slide (1920×1080)",
+        }
+    }
+}
+
+/// How much a `width` by `height` window has to shrink to fit in `available` space,
+/// or 1.0 if it already fits
+pub fn fit_ratio((width, height): (u32, u32), available: (u32, u32)) -> f32 {
+    let horizontal = available.0 as f32 / width.max(1) as f32;
+    let vertical = available.1 as f32 / height.max(1) as f32;
+
+    horizontal.min(vertical).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_match_their_aspect_ratios() {
+        let ratio = |preset: CanvasPreset| {
+            let (width, height) = preset.size();
+            width as f32 / height as f32
+        };
+
+        assert!((ratio(CanvasPreset::Twitter) - 16.0 / 9.0).abs() < 0.01);
+        assert!((ratio(CanvasPreset::LinkedIn) - 1.91).abs() < 0.01);
+        assert_eq!(ratio(CanvasPreset::InstagramSquare), 1.0);
+        assert!((ratio(CanvasPreset::InstagramPortrait) - 0.8).abs() < 0.01);
+        assert_eq!(CanvasPreset::OpenGraph.size(), (1200, 630));
+        assert_eq!(CanvasPreset::Slide.size(), (1920, 1080));
+    }
+
+    #[test]
+    fn test_fit_ratio() {
+        assert_eq!(fit_ratio((500, 300), (1000, 1000)), 1.0);
+        assert_eq!(fit_ratio((2000, 300), (1000, 1000)), 0.5);
+        assert_eq!(fit_ratio((500, 4000), (1000, 1000)), 0.25);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::services::font_manager::LoadedFont;
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use ttf_parser::RasterImageFormat;
+
+/// A color bitmap decoded from a font's embedded strike
+#[derive(Debug)]
+pub struct ColorBitmap {
+    pub image: Arc<RgbaImage>,
+    /// Offset of the bitmap's left and bottom edges from the glyph origin, y pointing up,
+    /// in pixels of the strike
+    pub x: f32,
+    pub y: f32,
+    pub pixels_per_em: f32,
+}
+
+/// Where a color bitmap lands relative to the pen position at a font size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitmapPlacement {
+    pub left: f32,
+    /// Distance from the baseline up to the bitmap's top edge
+    pub top: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Decodes the color bitmaps of emoji fonts that store them as images (CBDT and sbix),
+/// keeping each glyph decoded once. COLR fonts have no bitmaps and draw from their
+/// outlines in the text color instead.
+pub struct ColorGlyphCache {
+    cache: Arc<Mutex<HashMap<String, Option<Arc<ColorBitmap>>>>>,
+}
+
+impl ColorGlyphCache {
+    /// Creates an empty cache
+    pub fn new() -> Self {
+        ColorGlyphCache {
+            cache: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the color bitmap a face draws for a glyph, or None for outline glyphs.
+    /// `face_key` identifies the face among everything cached.
+    pub fn bitmap(
+        &self,
+        face_key: &str,
+        font: &LoadedFont,
+        glyph_id: u16,
+        font_size: f32,
+    ) -> Option<Arc<ColorBitmap>> {
+        let key = format!("{}:{}", face_key, glyph_id);
+
+        // Check cache first
+        {
+            let cache = self.cache.lock().unwrap();
+            if let Some(bitmap) = cache.get(&key) {
+                return bitmap.clone();
+            }
+        }
+
+        let bitmap = decode_bitmap(font, glyph_id, font_size).map(Arc::new);
+
+        // Cache the result
+        {
+            let mut cache = self.cache.lock().unwrap();
+            cache.insert(key, bitmap.clone());
+        }
+
+        bitmap
+    }
+}
+
+impl Default for ColorGlyphCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ColorBitmap {
+    /// Scales the strike to a font size
+    pub fn placement(&self, font_size: f32) -> BitmapPlacement {
+        let scale = font_size / self.pixels_per_em.max(1.0);
+        let (width, height) = (
+            self.image.width() as f32 * scale,
+            self.image.height() as f32 * scale,
+        );
+
+        BitmapPlacement {
+            left: self.x * scale,
+            top: self.y * scale + height,
+            width,
+            height,
+        }
+    }
+}
+
+/// Reads a glyph's bitmap from the strike closest to the font size
+fn decode_bitmap(font: &LoadedFont, glyph_id: u16, font_size: f32) -> Option<ColorBitmap> {
+    let face = ttf_parser::Face::parse(&font.data, font.index).ok()?;
+    let tables = face.tables();
+    if tables.cbdt.is_none() && tables.sbix.is_none() {
+        return None;
+    }
+
+    let ppem = (font_size.ceil() as u16).max(1);
+    let raster = face.glyph_raster_image(ttf_parser::GlyphId(glyph_id), ppem)?;
+    let image = match raster.format {
+        RasterImageFormat::PNG => {
+            image::load_from_memory_with_format(raster.data, image::ImageFormat::Png)
+                .ok()?
+                .to_rgba8()
+        }
+        RasterImageFormat::BitmapPremulBgra32 => {
+            unpremultiply_bgra(raster.data, raster.width as u32, raster.height as u32)?
+        }
+        // Monochrome and grayscale strikes are no better than the outlines
+        _ => return None,
+    };
+
+    Some(ColorBitmap {
+        image: Arc::new(image),
+        x: raster.x as f32,
+        y: raster.y as f32,
+        pixels_per_em: raster.pixels_per_em as f32,
+    })
+}
+
+/// Converts premultiplied BGRA pixels to straight RGBA
+fn unpremultiply_bgra(data: &[u8], width: u32, height: u32) -> Option<RgbaImage> {
+    if data.len() < (width * height * 4) as usize {
+        return None;
+    }
+
+    let mut image = RgbaImage::new(width, height);
+    for (pixel, bgra) in image.pixels_mut().zip(data.chunks_exact(4)) {
+        let alpha = bgra[3];
+        let straight = |channel: u8| match alpha {
+            0 => 0,
+            _ => ((channel as u32 * 255 + alpha as u32 / 2) / alpha as u32).min(255) as u8,
+        };
+        *pixel = Rgba([straight(bgra[2]), straight(bgra[1]), straight(bgra[0]), alpha]);
+    }
+
+    Some(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::font_manager::FontManager;
+
+    #[test]
+    fn test_outline_fonts_have_no_bitmaps() {
+        let manager = FontManager::new().unwrap();
+        let font = manager.resolve_loaded("monospace");
+        let cache = ColorGlyphCache::new();
+
+        assert!(cache.bitmap("monospace", &font, 68, 20.0).is_none());
+        assert_eq!(cache.cache.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_strikes_scale_to_the_font_size() {
+        let bitmap = ColorBitmap {
+            image: Arc::new(RgbaImage::new(136, 128)),
+            x: 0.0,
+            y: -12.0,
+            pixels_per_em: 109.0,
+        };
+
+        let placement = bitmap.placement(21.8);
+        assert!((placement.width - 27.2).abs() < 0.01);
+        assert!((placement.height - 25.6).abs() < 0.01);
+        assert!((placement.top - (25.6 - 2.4)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_premultiplied_pixels_are_unpremultiplied() {
+        let image = unpremultiply_bgra(&[0, 128, 0, 128, 10, 20, 30, 255], 2, 1).unwrap();
+
+        assert_eq!(*image.get_pixel(0, 0), Rgba([0, 255, 0, 128]));
+        assert_eq!(*image.get_pixel(1, 0), Rgba([30, 20, 10, 255]));
+    }
+}
@@ -0,0 +1,376 @@
+use crate::models::errors::AppError;
+use crate::services::line_ranges::{LineRange, LineRangeMode};
+use crate::services::syntax_highlighter::split_tokens;
+use std::ops::Range;
+
+/// A parsed unified diff, flattened to the rows it renders as
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnifiedDiff {
+    /// Path of the first file in the diff, from its `+++` (or `---` for deletions) header
+    pub path: Option<String>,
+    pub lines: Vec<DiffLine>,
+}
+
+/// What a row of a diff represents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffLineKind {
+    /// `diff --git` or `---`/`+++` naming the next file, shown when a diff spans several files
+    FileHeader,
+    /// `@@ -a,b +c,d @@` starting a hunk
+    HunkHeader,
+    Context,
+    Added,
+    Removed,
+}
+
+/// One row of a diff with its prefix removed and its line numbers in the old and new file
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub text: String,
+    pub old_number: Option<usize>,
+    pub new_number: Option<usize>,
+}
+
+/// Columns of a changed line that differ from the line it replaces
+#[derive(Debug, Clone, PartialEq)]
+pub struct WordChange {
+    /// 1-based row of the line within the rendered diff
+    pub line_number: usize,
+    /// Character columns of the changed text
+    pub columns: Range<usize>,
+    pub mode: LineRangeMode,
+}
+
+/// Parses `git diff` style output; anything between hunks other than `---`/`+++` file headers is skipped
+pub fn parse_unified_diff(input: &str) -> Result<UnifiedDiff, AppError> {
+    let mut paths: Vec<String> = Vec::new();
+    let mut lines = Vec::new();
+    let mut old_path: Option<String> = None;
+    // Lines left in the current hunk, from the counts in its header
+    let (mut old_remaining, mut new_remaining) = (0, 0);
+    let (mut old_number, mut new_number) = (0, 0);
+
+    for raw in input.lines() {
+        let raw = raw.strip_suffix('\r').unwrap_or(raw);
+        let in_hunk = old_remaining > 0 || new_remaining > 0;
+
+        if !in_hunk {
+            if let Some(header) = raw.strip_prefix("@@") {
+                let hunk = parse_hunk_header(header).ok_or_else(|| {
+                    AppError::image_generation_failed(format!("Invalid hunk header: {}", raw))
+                })?;
+                (old_number, old_remaining) = hunk.0;
+                (new_number, new_remaining) = hunk.1;
+                lines.push(DiffLine {
+                    kind: DiffLineKind::HunkHeader,
+                    text: raw.to_string(),
+                    old_number: None,
+                    new_number: None,
+                });
+            } else if let Some(path) = raw.strip_prefix("--- ") {
+                old_path = Some(strip_diff_path(path));
+            } else if let Some(path) = raw.strip_prefix("+++ ") {
+                let path = strip_diff_path(path);
+                let path = match (path.as_str(), old_path.take()) {
+                    ("/dev/null", Some(old)) => old,
+                    _ => path,
+                };
+                lines.push(DiffLine {
+                    kind: DiffLineKind::FileHeader,
+                    text: path.clone(),
+                    old_number: None,
+                    new_number: None,
+                });
+                paths.push(path);
+            }
+            continue;
+        }
+
+        let (kind, text) = match raw.chars().next() {
+            Some('+') => (DiffLineKind::Added, &raw[1..]),
+            Some('-') => (DiffLineKind::Removed, &raw[1..]),
+            Some(' ') => (DiffLineKind::Context, &raw[1..]),
+            // Some tools drop the space on empty context lines
+            None => (DiffLineKind::Context, ""),
+            // "\ No newline at end of file" and anything else isn't part of either file
+            _ => continue,
+        };
+
+        let (old, new) = match kind {
+            DiffLineKind::Added => (None, Some(new_number)),
+            DiffLineKind::Removed => (Some(old_number), None),
+            _ => (Some(old_number), Some(new_number)),
+        };
+        if old.is_some() {
+            old_number += 1;
+            old_remaining = old_remaining.saturating_sub(1);
+        }
+        if new.is_some() {
+            new_number += 1;
+            new_remaining = new_remaining.saturating_sub(1);
+        }
+
+        lines.push(DiffLine {
+            kind,
+            text: text.to_string(),
+            old_number: old,
+            new_number: new,
+        });
+    }
+
+    if !lines.iter().any(|line| line.kind == DiffLineKind::HunkHeader) {
+        return Err(AppError::image_generation_failed(
+            "Input is not a unified diff: no @@ hunk headers found",
+        ));
+    }
+
+    // A single file's name adds nothing to the picture
+    if paths.len() <= 1 {
+        lines.retain(|line| line.kind != DiffLineKind::FileHeader);
+    }
+
+    Ok(UnifiedDiff {
+        path: paths.into_iter().next(),
+        lines,
+    })
+}
+
+impl UnifiedDiff {
+    /// Extension of the first file, used to pick the syntax for the code lines
+    pub fn file_extension(&self) -> Option<&str> {
+        let path = self.path.as_deref()?;
+        let name = path.rsplit('/').next()?;
+        name.rsplit_once('.').map(|(_, extension)| extension)
+    }
+
+    /// Bands for added and removed lines and faint ones for headers
+    pub fn line_ranges(&self) -> Vec<LineRange> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, line)| {
+                let mode = match line.kind {
+                    DiffLineKind::Added => LineRangeMode::Added,
+                    DiffLineKind::Removed => LineRangeMode::Removed,
+                    DiffLineKind::HunkHeader | DiffLineKind::FileHeader => LineRangeMode::Highlight,
+                    DiffLineKind::Context => return None,
+                };
+                Some(LineRange::new(index + 1, index + 1, mode))
+            })
+            .collect()
+    }
+
+    /// Finds the words that changed between each run of removed lines and the added lines after it.
+    /// Lines are paired in order; unpaired lines are wholly new or deleted and get no emphasis.
+    pub fn word_changes(&self) -> Vec<WordChange> {
+        let mut changes = Vec::new();
+        let mut index = 0;
+
+        while index < self.lines.len() {
+            let removed_start = index;
+            while index < self.lines.len() && self.lines[index].kind == DiffLineKind::Removed {
+                index += 1;
+            }
+            let added_start = index;
+            while index < self.lines.len() && self.lines[index].kind == DiffLineKind::Added {
+                index += 1;
+            }
+
+            if removed_start == added_start || added_start == index {
+                index = index.max(removed_start + 1);
+                continue;
+            }
+
+            for (removed, added) in (removed_start..added_start).zip(added_start..index) {
+                let (old_columns, new_columns) =
+                    changed_columns(&self.lines[removed].text, &self.lines[added].text);
+
+                changes.extend(old_columns.into_iter().map(|columns| WordChange {
+                    line_number: removed + 1,
+                    columns,
+                    mode: LineRangeMode::Removed,
+                }));
+                changes.extend(new_columns.into_iter().map(|columns| WordChange {
+                    line_number: added + 1,
+                    columns,
+                    mode: LineRangeMode::Added,
+                }));
+            }
+        }
+
+        changes
+    }
+}
+
+/// Reads the old and new `(start, count)` pairs from the text after a hunk's leading `@@`
+fn parse_hunk_header(header: &str) -> Option<((usize, usize), (usize, usize))> {
+    let ranges = header.trim_start().split("@@").next()?;
+    let mut parts = ranges.split_whitespace();
+    let old = parts.next()?.strip_prefix('-')?;
+    let new = parts.next()?.strip_prefix('+')?;
+
+    // A missing count means a single line
+    let range = |range: &str| -> Option<(usize, usize)> {
+        match range.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    Some((range(old)?, range(new)?))
+}
+
+/// Removes the `a/` or `b/` prefix git adds and any trailing timestamp
+fn strip_diff_path(path: &str) -> String {
+    let path = path.split('\t').next().unwrap_or(path).trim();
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+        .to_string()
+}
+
+/// Character columns of the tokens that differ between two lines, from a token-level LCS
+fn changed_columns(old: &str, new: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let (old_tokens, new_tokens) = (split_tokens(old), split_tokens(new));
+    let (n, m) = (old_tokens.len(), new_tokens.len());
+
+    // lengths[i][j] is the LCS length of old_tokens[i..] and new_tokens[j..]
+    let mut lengths = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if old_tokens[i] == new_tokens[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut old_kept = vec![false; n];
+    let mut new_kept = vec![false; m];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old_tokens[i] == new_tokens[j] {
+            old_kept[i] = true;
+            new_kept[j] = true;
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    (
+        column_ranges(&old_tokens, &old_kept),
+        column_ranges(&new_tokens, &new_kept),
+    )
+}
+
+/// Merges adjacent changed tokens into character column ranges
+fn column_ranges(tokens: &[&str], kept: &[bool]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut column = 0;
+
+    for (token, &kept) in tokens.iter().zip(kept) {
+        let width = token.chars().count();
+        if !kept {
+            match ranges.last_mut() {
+                Some(last) if last.end == column => last.end += width,
+                _ => ranges.push(column..column + width),
+            }
+        }
+        column += width;
+    }
+
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "diff --git a/src/lib.rs b/src/lib.rs
+index 3b18e51..a9c2f0e 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -10,4 +10,4 @@ fn main() {
+ let a = 1;
+-let total = a + 1;
++let total = a + 2;
+ println!(\"{}\", total);
+\\ No newline at end of file
+";
+
+    #[test]
+    fn test_parse_hunks_and_line_numbers() {
+        let diff = parse_unified_diff(SAMPLE).unwrap();
+
+        assert_eq!(diff.path.as_deref(), Some("src/lib.rs"));
+        assert_eq!(diff.file_extension(), Some("rs"));
+
+        let kinds: Vec<DiffLineKind> = diff.lines.iter().map(|line| line.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DiffLineKind::HunkHeader,
+                DiffLineKind::Context,
+                DiffLineKind::Removed,
+                DiffLineKind::Added,
+                DiffLineKind::Context,
+            ]
+        );
+
+        assert_eq!(diff.lines[2].text, "let total = a + 1;");
+        assert_eq!((diff.lines[2].old_number, diff.lines[2].new_number), (Some(11), None));
+        assert_eq!((diff.lines[3].old_number, diff.lines[3].new_number), (None, Some(11)));
+        assert_eq!((diff.lines[4].old_number, diff.lines[4].new_number), (Some(12), Some(12)));
+    }
+
+    #[test]
+    fn test_multiple_files_keep_their_headers() {
+        let input = "--- a/one.py\n+++ b/one.py\n@@ -1 +1 @@\n-a\n+b\n--- a/two.py\n+++ /dev/null\n@@ -1 +0,0 @@\n-c\n";
+        let diff = parse_unified_diff(input).unwrap();
+
+        let headers: Vec<&str> = diff
+            .lines
+            .iter()
+            .filter(|line| line.kind == DiffLineKind::FileHeader)
+            .map(|line| line.text.as_str())
+            .collect();
+        assert_eq!(headers, vec!["one.py", "two.py"]);
+        assert_eq!(diff.path.as_deref(), Some("one.py"));
+    }
+
+    #[test]
+    fn test_plain_code_is_rejected() {
+        assert!(parse_unified_diff("fn main() {}\n").is_err());
+    }
+
+    #[test]
+    fn test_word_changes_pair_removed_and_added_lines() {
+        let diff = parse_unified_diff(SAMPLE).unwrap();
+        let changes = diff.word_changes();
+
+        assert_eq!(
+            changes,
+            vec![
+                WordChange {
+                    line_number: 3,
+                    columns: 16..17,
+                    mode: LineRangeMode::Removed,
+                },
+                WordChange {
+                    line_number: 4,
+                    columns: 16..17,
+                    mode: LineRangeMode::Added,
+                },
+            ]
+        );
+
+        let ranges = diff.line_ranges();
+        assert_eq!(ranges.len(), 3);
+        assert_eq!(ranges[1], LineRange::new(3, 3, LineRangeMode::Removed));
+    }
+}
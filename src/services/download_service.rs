@@ -1,7 +1,8 @@
 use crate::models::errors::AppError;
 use crate::services::export_service::{ExportService, ExportResult, EnhancedExportOptions};
 use crate::services::file_storage::FileStorageService;
-use crate::models::theme::Theme;
+use crate::services::redaction::Finding;
+use crate::models::theme::{BackgroundType, Theme};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
@@ -85,6 +86,9 @@ impl DownloadService {
         // Validate export options
         ExportService::validate_options(&request.export_options)?;
 
+        // Fail early if an uploaded background has expired
+        self.load_background_images(&request.theme).await?;
+
         // Check concurrent download limit BEFORE adding to tracker
         let active_downloads = self.count_active_downloads().await;
         if active_downloads >= self.max_concurrent_downloads {
@@ -214,12 +218,25 @@ impl DownloadService {
                 tracing::warn!("Failed to update retry progress: {}", e);
             }
 
-            match self.export_service.export_code_snippet(
-                &request.code,
-                &request.language,
-                &request.theme,
-                &request.export_options,
-            ).await {
+            // Pictures may have been dropped from memory since the download started
+            self.load_background_images(&request.theme).await?;
+
+            // Rendering and encoding are CPU-bound, so they run on the blocking pool
+            // instead of stalling the async workers that serve other requests
+            let export_service = Arc::clone(&self.export_service);
+            let job = request.clone();
+            let export = tokio::task::spawn_blocking(move || {
+                tokio::runtime::Handle::current().block_on(export_service.export_code_snippet(
+                    &job.code,
+                    &job.language,
+                    &job.theme,
+                    &job.export_options,
+                ))
+            })
+            .await
+            .unwrap_or_else(|e| Err(AppError::image_generation_failed(format!("Export task failed: {}", e))));
+
+            match export {
                 Ok(result) => return Ok(result),
                 Err(e) => {
                     last_error = Some(e);
@@ -235,6 +252,48 @@ impl DownloadService {
         Err(last_error.unwrap_or_else(|| AppError::image_generation_failed("Unknown error during export")))
     }
 
+    /// The secrets a download of `request` would redact, without rendering it
+    pub fn find_redactions(&self, request: &DownloadRequest) -> Result<Vec<Finding>, AppError> {
+        self.export_service.find_redactions(
+            &request.code,
+            &request.language,
+            &request.theme,
+            &request.export_options,
+        )
+    }
+
+    /// Reads the uploaded pictures a theme's backgrounds use and hands them to the exporter
+    async fn load_background_images(&self, theme: &Theme) -> Result<(), AppError> {
+        let backgrounds = std::iter::once(&theme.background).chain(theme.backdrop.as_ref());
+
+        for background in backgrounds {
+            let Some(ref image) = background.image else {
+                continue;
+            };
+            if background.bg_type != BackgroundType::Image
+                || self.export_service.has_background_image(&image.file_id)
+            {
+                continue;
+            }
+
+            // Uploads are stored under the extension their format was detected as
+            let extension = ["png", "jpg"]
+                .into_iter()
+                .find(|extension| self.file_storage.temp_file_exists(&image.file_id, extension))
+                .ok_or_else(|| {
+                    AppError::storage_failed(format!(
+                        "Background image {} was not found or has expired",
+                        image.file_id
+                    ))
+                })?;
+
+            let data = self.file_storage.read_temp_file(&image.file_id, extension).await?;
+            self.export_service.add_background_image(&image.file_id, &data)?;
+        }
+
+        Ok(())
+    }
+
     /// Store the generated file
     async fn store_generated_file(
         &self,
@@ -247,6 +306,11 @@ impl DownloadService {
             crate::services::image_generator::ImageFormat::PNG => "png",
             crate::services::image_generator::ImageFormat::JPEG => "jpg",
             crate::services::image_generator::ImageFormat::SVG => "svg",
+            crate::services::image_generator::ImageFormat::PDF => "pdf",
+            crate::services::image_generator::ImageFormat::WEBP => "webp",
+            crate::services::image_generator::ImageFormat::AVIF => "avif",
+            crate::services::image_generator::ImageFormat::GIF => "gif",
+            crate::services::image_generator::ImageFormat::APNG => "png",
         };
 
         // Store the file
@@ -257,6 +321,11 @@ impl DownloadService {
             crate::services::image_generator::ImageFormat::PNG => "image/png",
             crate::services::image_generator::ImageFormat::JPEG => "image/jpeg",
             crate::services::image_generator::ImageFormat::SVG => "image/svg+xml",
+            crate::services::image_generator::ImageFormat::PDF => "application/pdf",
+            crate::services::image_generator::ImageFormat::WEBP => "image/webp",
+            crate::services::image_generator::ImageFormat::AVIF => "image/avif",
+            crate::services::image_generator::ImageFormat::GIF => "image/gif",
+            crate::services::image_generator::ImageFormat::APNG => "image/apng",
         };
 
         let original_filename = format!("code-snippet.{}", extension);
@@ -307,6 +376,11 @@ impl DownloadService {
             "image/png" => "png",
             "image/jpeg" => "jpg",
             "image/svg+xml" => "svg",
+            "application/pdf" => "pdf",
+            "image/webp" => "webp",
+            "image/avif" => "avif",
+            "image/gif" => "gif",
+            "image/apng" => "png",
             _ => return Err(AppError::storage_failed("Unknown file type")),
         };
 
@@ -407,6 +481,11 @@ impl DownloadService {
                 "image/png" => "png",
                 "image/jpeg" => "jpg",
                 "image/svg+xml" => "svg",
+                "application/pdf" => "pdf",
+                "image/webp" => "webp",
+                "image/avif" => "avif",
+                "image/gif" => "gif",
+                "image/apng" => "png",
                 _ => "tmp",
             };
 
@@ -585,7 +664,7 @@ mod tests {
         
         let cleaned = service.cleanup_expired_downloads().await.unwrap();
         // Note: The actual cleanup depends on the download completing first
-        assert!(cleaned >= 0);
+        assert!(cleaned <= 1);
     }
 
     #[tokio::test]
@@ -1,7 +1,16 @@
 use crate::models::errors::AppError;
 use crate::models::theme::Theme;
-use crate::services::image_generator::{ImageGenerator, ExportOptions, ImageFormat, Resolution};
-use crate::services::syntax_highlighter::{SyntaxHighlighter, HighlightResult};
+use crate::services::animation::AnimationOptions;
+use crate::services::canvas::CanvasPreset;
+use crate::services::annotations::{self, Annotation};
+use crate::services::line_ranges::{self, LineRange};
+use crate::services::redaction::{Finding, RedactionOptions};
+use crate::services::shell::ShellOptions;
+use crate::services::wrap::WrapMode;
+use crate::services::image_generator::{
+    ExportOptions, ImageFormat, ImageGenerator, InputMode, Margin, Padding, Resolution,
+    SvgTextMode,
+};
 use serde::{Serialize, Deserialize};
 use std::sync::Arc;
 use uuid::Uuid;
@@ -9,7 +18,6 @@ use uuid::Uuid;
 /// Enhanced export service with multiple format support
 pub struct ExportService {
     image_generator: Arc<ImageGenerator>,
-    syntax_highlighter: Arc<SyntaxHighlighter>,
 }
 
 /// Export result containing the generated image data and metadata
@@ -25,31 +33,68 @@ pub struct ExportResult {
 
 /// Enhanced export options with additional configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EnhancedExportOptions {
     pub format: ImageFormat,
     pub resolution: Resolution,
-    pub quality: u8, // 1-100 for JPEG
+    pub quality: u8, // 1-100 for JPEG, lossy WebP and AVIF
+    pub lossless: bool, // Lossless WebP; quality is ignored
     pub width: Option<u32>,
     pub height: Option<u32>,
     pub dpi: Option<u32>, // For high-quality exports
     pub compression_level: Option<u8>, // For PNG compression
     pub progressive: bool, // For progressive JPEG
     pub include_metadata: bool, // Include EXIF/metadata
+    pub svg_text: SvgTextMode, // Outlined glyphs or embedded font for SVG
+    pub lines_per_page: Option<usize>, // Splits long snippets across PDF pages
+    pub animation: AnimationOptions, // Typing animation for GIF and APNG
+    pub padding: Option<Padding>, // Space around the code inside the window
+    pub margin: Option<Margin>, // Space around the window, filled by the theme's backdrop
+    pub line_ranges: Vec<LineRange>, // Highlight, focus, added and removed lines
+    pub input_mode: InputMode, // Plain code, a unified diff, a shell session or ANSI colored output
+    pub word_diff: bool, // Emphasizes changed words in diff mode
+    pub wrap: WrapMode, // Wraps long lines or widens the canvas to fit them
+    pub canvas: Option<CanvasPreset>, // Social media or slide size; the font shrinks to fit
+    pub shell: ShellOptions, // Prompt pattern, prompt segment and cursor for shell sessions
+    pub annotations: Vec<Annotation>, // Numbered markers, arrows, boxes and margin notes
+    pub redaction: RedactionOptions, // Secrets and personal data hidden behind bars; on by default
 }
 
 impl ExportService {
+    /// Largest padding or margin, per side, in layout units
+    pub const MAX_SPACING: u32 = 512;
+
     /// Creates a new ExportService
     pub fn new() -> Result<Self, AppError> {
         let image_generator = Arc::new(ImageGenerator::new()?);
-        let syntax_highlighter = Arc::new(SyntaxHighlighter::new()
-            .map_err(|e| AppError::image_generation_failed(format!("Failed to initialize syntax highlighter: {}", e)))?);
 
         Ok(ExportService {
             image_generator,
-            syntax_highlighter,
         })
     }
 
+    /// Makes an uploaded picture available to themes with an `Image` background
+    pub fn add_background_image(&self, file_id: &str, data: &[u8]) -> Result<(), AppError> {
+        self.image_generator.add_background_image(file_id, data)
+    }
+
+    /// The secrets that would be redacted when exporting the snippet with these options
+    pub fn find_redactions(
+        &self,
+        code: &str,
+        language: &str,
+        theme: &Theme,
+        options: &EnhancedExportOptions,
+    ) -> Result<Vec<Finding>, AppError> {
+        self.image_generator
+            .find_redactions(code, language, theme, &Self::basic_options(options))
+    }
+
+    /// True if the picture uploaded as `file_id` is ready to be drawn
+    pub fn has_background_image(&self, file_id: &str) -> bool {
+        self.image_generator.has_background_image(file_id)
+    }
+
     /// Export code snippet to image with enhanced options
     pub async fn export_code_snippet(
         &self,
@@ -64,19 +109,20 @@ impl ExportService {
         }
 
         // Convert enhanced options to basic export options
-        let basic_options = ExportOptions {
-            format: options.format.clone(),
-            resolution: options.resolution.clone(),
-            quality: options.quality,
-            width: options.width,
-            height: options.height,
-        };
+        let basic_options = Self::basic_options(options);
 
         // Generate the image based on format
         let (data, width, height) = match options.format {
             ImageFormat::PNG => self.export_png(code, language, theme, &basic_options, options).await?,
             ImageFormat::JPEG => self.export_jpeg(code, language, theme, &basic_options, options).await?,
-            ImageFormat::SVG => self.export_svg(code, language, theme, options).await?,
+            ImageFormat::SVG => self.export_svg(code, language, theme, &basic_options).await?,
+            ImageFormat::PDF => self.export_pdf(code, language, theme, &basic_options).await?,
+            ImageFormat::WEBP | ImageFormat::AVIF => {
+                self.export_web_image(code, language, theme, &basic_options).await?
+            }
+            ImageFormat::GIF | ImageFormat::APNG => {
+                self.export_animation(code, language, theme, &basic_options).await?
+            }
         };
 
         let export_id = Uuid::new_v4().to_string();
@@ -92,6 +138,31 @@ impl ExportService {
         })
     }
 
+    /// The options the image generator takes for a set of enhanced export options
+    fn basic_options(options: &EnhancedExportOptions) -> ExportOptions {
+        ExportOptions {
+            format: options.format.clone(),
+            resolution: options.resolution.clone(),
+            quality: options.quality,
+            lossless: options.lossless,
+            width: options.width,
+            height: options.height,
+            svg_text: options.svg_text.clone(),
+            lines_per_page: options.lines_per_page,
+            animation: options.animation.clone(),
+            padding: options.padding.clone(),
+            margin: options.margin.clone(),
+            line_ranges: options.line_ranges.clone(),
+            input_mode: options.input_mode.clone(),
+            word_diff: options.word_diff,
+            wrap: options.wrap,
+            canvas: options.canvas,
+            shell: options.shell.clone(),
+            annotations: options.annotations.clone(),
+            redaction: options.redaction.clone(),
+        }
+    }
+
     /// Export as PNG with enhanced options
     async fn export_png(
         &self,
@@ -189,172 +260,101 @@ impl ExportService {
         Ok((buffer, width, height))
     }
 
-    /// Export as SVG with vector graphics
+    /// Export as SVG with vector graphics drawn from the same scene as raster exports
     async fn export_svg(
         &self,
         code: &str,
         language: &str,
         theme: &Theme,
-        options: &EnhancedExportOptions,
+        options: &ExportOptions,
     ) -> Result<(Vec<u8>, u32, u32), AppError> {
-        // Perform syntax highlighting
-        let highlight_result = self.syntax_highlighter
-            .highlight_code(code, language, theme)
-            .map_err(|e| AppError::image_generation_failed(format!("Syntax highlighting failed: {}", e)))?;
-
-        // Calculate dimensions
-        let scale_factor = match options.resolution {
-            Resolution::Standard => 1.0,
-            Resolution::High => 2.0,
-            Resolution::Ultra => 3.0,
-        };
+        let scene = self.image_generator.create_scene(code, language, theme, options)?;
+        let svg_content = self.image_generator.render_svg(&scene, &options.svg_text)?;
 
-        let font_size = theme.typography.font_size * scale_factor;
-        let line_height = font_size * theme.typography.line_height;
-        let char_width = font_size * 0.6; // Monospace approximation
-
-        // Calculate content dimensions
-        let max_line_length = highlight_result.highlighted_lines
-            .iter()
-            .map(|line| line.segments.iter().map(|seg| seg.text.len()).sum::<usize>())
-            .max()
-            .unwrap_or(0);
-
-        let content_width = (max_line_length as f32 * char_width) as u32;
-        let content_height = (highlight_result.total_lines as f32 * line_height) as u32;
-
-        // Add padding
-        let padding = 40.0 * scale_factor;
-        let width = content_width + (padding * 2.0) as u32;
-        let height = content_height + (padding * 2.0) as u32;
-
-        // Generate SVG content
-        let svg_content = self.generate_svg_content(
-            &highlight_result,
-            theme,
-            width,
-            height,
-            padding,
-            font_size,
-            line_height,
-            char_width,
-        )?;
-
-        Ok((svg_content.into_bytes(), width, height))
+        Ok((svg_content.into_bytes(), scene.width, scene.height))
     }
 
-    /// Generate SVG content for the code snippet
-    fn generate_svg_content(
+    /// Export as PDF with selectable text, one page per `lines_per_page` lines
+    async fn export_pdf(
         &self,
-        highlight_result: &HighlightResult,
+        code: &str,
+        language: &str,
         theme: &Theme,
-        width: u32,
-        height: u32,
-        padding: f32,
-        font_size: f32,
-        line_height: f32,
-        char_width: f32,
-    ) -> Result<String, AppError> {
-        let mut svg = String::new();
-        
-        // SVG header
-        svg.push_str(&format!(
-            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">\n<defs>\n<style type=\"text/css\">\n<![CDATA[\n.code-text {{\n    font-family: 'SF Mono', 'Monaco', 'Inconsolata', 'Fira Code', monospace;\n    font-size: {}px;\n    line-height: {};\n}}\n]]>\n</style>\n</defs>\n",
-            width, height, font_size, line_height / font_size
-        ));
-
-        // Background
-        svg.push_str(&format!(
-            "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
-            theme.background.primary
-        ));
-
-        // Add gradient background if specified
-        if let crate::models::theme::BackgroundType::Gradient = theme.background.bg_type {
-            if let Some(ref secondary) = theme.background.secondary {
-                svg.push_str(&format!(
-                    "<defs>\n<linearGradient id=\"bg-gradient\" x1=\"0%\" y1=\"0%\" x2=\"0%\" y2=\"100%\">\n<stop offset=\"0%\" style=\"stop-color:{};stop-opacity:1\" />\n<stop offset=\"100%\" style=\"stop-color:{};stop-opacity:1\" />\n</linearGradient>\n</defs>\n<rect width=\"100%\" height=\"100%\" fill=\"url(#bg-gradient)\"/>",
-                    theme.background.primary, secondary
-                ));
-            }
-        }
-
-        // Window frame if enabled
-        if theme.window.show_title_bar {
-            let title_bar_height = 30.0;
-            let stroke_color = "#cccccc";
-            svg.push_str(&format!(
-                "<rect x=\"0\" y=\"0\" width=\"{}\" height=\"{}\" fill=\"#f0f0f0\" stroke=\"{}\" stroke-width=\"1\"/>",
-                width, title_bar_height, stroke_color
-            ));
+        options: &ExportOptions,
+    ) -> Result<(Vec<u8>, u32, u32), AppError> {
+        let pages = self.image_generator.create_pages(code, language, theme, options)?;
+        let pdf_content = self.image_generator.render_pdf(&pages, options)?;
 
-            // Window controls
-            if theme.window.show_controls {
-                svg.push_str("<circle cx=\"15\" cy=\"15\" r=\"6\" fill=\"#ff5f57\"/>");
-                svg.push_str("<circle cx=\"35\" cy=\"15\" r=\"6\" fill=\"#ffbd2e\"/>");
-                svg.push_str("<circle cx=\"55\" cy=\"15\" r=\"6\" fill=\"#28ca42\"/>");
-            }
+        // Every page has the same size
+        let (width, height) = pages
+            .first()
+            .map(|page| (page.width, page.height))
+            .unwrap_or_default();
 
-            // Title text
-            if let Some(ref title) = theme.window.title {
-                let text_color = "#333333";
-                svg.push_str(&format!(
-                    "<text x=\"{}\" y=\"20\" class=\"code-text\" fill=\"{}\" text-anchor=\"middle\">{}</text>",
-                    width / 2, text_color, title
-                ));
-            }
-        }
-
-        // Code content
-        let mut y = padding + font_size;
-        if theme.window.show_title_bar {
-            y += 30.0;
-        }
+        Ok((pdf_content, width, height))
+    }
 
-        for line in &highlight_result.highlighted_lines {
-            let mut x = padding;
-
-            // Line number if enabled
-            if theme.typography.show_line_numbers {
-                let line_color = "#666666";
-                svg.push_str(&format!(
-                    "<text x=\"{}\" y=\"{}\" class=\"code-text\" fill=\"{}\">{:3}</text>",
-                    x, y, line_color, line.line_number
-                ));
-                x += 50.0; // Line number width
-            }
+    /// Export as WebP or AVIF, encoded straight from the rendered scene
+    async fn export_web_image(
+        &self,
+        code: &str,
+        language: &str,
+        theme: &Theme,
+        options: &ExportOptions,
+    ) -> Result<(Vec<u8>, u32, u32), AppError> {
+        let scene = self.image_generator.create_scene(code, language, theme, options)?;
+        let image_data = self.image_generator.encode_image(&scene, options).await?;
 
-            // Code segments
-            for segment in &line.segments {
-                if !segment.text.is_empty() {
-                    let escaped_text = segment.text
-                        .replace('&', "&amp;")
-                        .replace('<', "&lt;")
-                        .replace('>', "&gt;")
-                        .replace('"', "&quot;")
-                        .replace('\'', "&#39;");
-
-                    svg.push_str(&format!(
-                        "<text x=\"{}\" y=\"{}\" class=\"code-text\" fill=\"{}\">{}</text>",
-                        x, y, segment.style.color, escaped_text
-                    ));
-
-                    x += segment.text.len() as f32 * char_width;
-                }
-            }
+        Ok((image_data, scene.width, scene.height))
+    }
 
-            y += line_height;
-        }
+    /// Export as an animated GIF or APNG of the snippet being typed out
+    async fn export_animation(
+        &self,
+        code: &str,
+        language: &str,
+        theme: &Theme,
+        options: &ExportOptions,
+    ) -> Result<(Vec<u8>, u32, u32), AppError> {
+        let frames = self.image_generator.create_typing_frames(code, language, theme, options)?;
+        let animation_data = self.image_generator.encode_animation(&frames, options)?;
 
-        // SVG footer
-        svg.push_str("</svg>");
+        // Every frame has the same size
+        let (width, height) = frames
+            .first()
+            .map(|frame| (frame.scene.width, frame.scene.height))
+            .unwrap_or_default();
 
-        Ok(svg)
+        Ok((animation_data, width, height))
     }
 
     /// Get supported export formats
     pub fn supported_formats() -> Vec<ImageFormat> {
-        vec![ImageFormat::PNG, ImageFormat::JPEG, ImageFormat::SVG]
+        vec![
+            ImageFormat::PNG,
+            ImageFormat::JPEG,
+            ImageFormat::SVG,
+            ImageFormat::PDF,
+            ImageFormat::WEBP,
+            ImageFormat::AVIF,
+            ImageFormat::GIF,
+            ImageFormat::APNG,
+        ]
+    }
+
+    /// Get the formats that animate the snippet being typed
+    pub fn animated_formats() -> Vec<ImageFormat> {
+        vec![ImageFormat::GIF, ImageFormat::APNG]
+    }
+
+    /// Get the formats whose size is controlled by the `quality` option
+    pub fn quality_formats() -> Vec<ImageFormat> {
+        vec![ImageFormat::JPEG, ImageFormat::WEBP, ImageFormat::AVIF]
+    }
+
+    /// Get the formats that can be encoded without any loss
+    pub fn lossless_formats() -> Vec<ImageFormat> {
+        vec![ImageFormat::PNG, ImageFormat::WEBP]
     }
 
     /// Get supported resolutions
@@ -364,9 +364,20 @@ impl ExportService {
 
     /// Validate export options
     pub fn validate_options(options: &EnhancedExportOptions) -> Result<(), AppError> {
-        // Validate quality for JPEG
-        if options.format == ImageFormat::JPEG && (options.quality < 1 || options.quality > 100) {
-            return Err(AppError::image_generation_failed("JPEG quality must be between 1 and 100"));
+        // Validate quality for lossy formats
+        let uses_quality = Self::quality_formats().contains(&options.format) && !options.lossless;
+        if uses_quality && (options.quality < 1 || options.quality > 100) {
+            return Err(AppError::image_generation_failed(format!(
+                "{:?} quality must be between 1 and 100",
+                options.format
+            )));
+        }
+
+        // Validate lossless encoding
+        if options.lossless && options.format == ImageFormat::AVIF {
+            return Err(AppError::image_generation_failed(
+                "AVIF export does not support lossless encoding; use a quality of 100 instead",
+            ));
         }
 
         // Validate compression level for PNG
@@ -376,15 +387,55 @@ impl ExportService {
             }
         }
 
+        // Validate animation timing
+        if Self::animated_formats().contains(&options.format) {
+            options.animation.validate()?;
+        }
+
+        // Validate pagination
+        if options.lines_per_page == Some(0) {
+            return Err(AppError::image_generation_failed("Lines per page must be at least 1"));
+        }
+
+        // Validate line ranges
+        line_ranges::validate_line_ranges(&options.line_ranges)?;
+
+        // Validate annotations
+        annotations::validate_annotations(&options.annotations)?;
+
+        // Validate custom redaction rules
+        options.redaction.validate()?;
+
+        // Validate shell session prompts
+        if options.input_mode == InputMode::Shell {
+            options.shell.validate()?;
+        }
+
+        // Validate spacing around the code and the window
+        let padding_sides = options.padding.iter().flat_map(|p| [p.top, p.right, p.bottom, p.left]);
+        let margin_sides = options.margin.iter().flat_map(|m| [m.top, m.right, m.bottom, m.left]);
+        if padding_sides.chain(margin_sides).any(|side| side > Self::MAX_SPACING) {
+            return Err(AppError::image_generation_failed(format!(
+                "Padding and margin must be at most {} on each side",
+                Self::MAX_SPACING
+            )));
+        }
+
         // Validate dimensions
+        if options.canvas.is_some() && (options.width.is_some() || options.height.is_some()) {
+            return Err(AppError::image_generation_failed(
+                "A canvas preset sets the size, so width and height can't be set with it",
+            ));
+        }
+
         if let Some(width) = options.width {
-            if width < 100 || width > 8000 {
+            if !(100..=8000).contains(&width) {
                 return Err(AppError::image_generation_failed("Width must be between 100 and 8000 pixels"));
             }
         }
 
         if let Some(height) = options.height {
-            if height < 100 || height > 8000 {
+            if !(100..=8000).contains(&height) {
                 return Err(AppError::image_generation_failed("Height must be between 100 and 8000 pixels"));
             }
         }
@@ -399,12 +450,26 @@ impl Default for EnhancedExportOptions {
             format: ImageFormat::PNG,
             resolution: Resolution::Standard,
             quality: 90,
+            lossless: false,
             width: None,
             height: None,
             dpi: None,
             compression_level: None,
             progressive: false,
             include_metadata: false,
+            svg_text: SvgTextMode::default(),
+            lines_per_page: None,
+            animation: AnimationOptions::default(),
+            padding: None,
+            margin: None,
+            line_ranges: Vec::new(),
+            input_mode: InputMode::default(),
+            word_diff: false,
+            wrap: WrapMode::default(),
+            canvas: None,
+            shell: ShellOptions::default(),
+            annotations: Vec::new(),
+            redaction: RedactionOptions::default(),
         }
     }
 }
@@ -487,6 +552,49 @@ mod tests {
         assert!(svg_content.contains("</svg>"));
     }
 
+    #[tokio::test]
+    async fn test_export_webp() {
+        let service = ExportService::new().unwrap();
+        let theme = Theme::default_dark();
+        let options = EnhancedExportOptions {
+            format: ImageFormat::WEBP,
+            lossless: true,
+            ..Default::default()
+        };
+
+        let export_result = service
+            .export_code_snippet("SELECT 1;", "SQL", &theme, &options)
+            .await
+            .unwrap();
+        assert_eq!(export_result.format, ImageFormat::WEBP);
+
+        let image = image::load_from_memory(&export_result.data).unwrap();
+        assert_eq!((image.width(), image.height()), (export_result.width, export_result.height));
+    }
+
+    #[tokio::test]
+    async fn test_export_pdf_pages() {
+        let service = ExportService::new().unwrap();
+        let theme = Theme::default_dark();
+        let code = "fn one() {}\nfn two() {}\nfn three() {}\nfn four() {}\nfn five() {}";
+
+        let options = EnhancedExportOptions {
+            format: ImageFormat::PDF,
+            lines_per_page: Some(2),
+            ..Default::default()
+        };
+
+        let export_result = service.export_code_snippet(code, "Rust", &theme, &options).await.unwrap();
+        assert_eq!(export_result.format, ImageFormat::PDF);
+        assert!(export_result.data.starts_with(b"%PDF-"));
+
+        // Five lines at two per page
+        let pages = pdf_extract::extract_text_from_mem_by_pages(&export_result.data).unwrap();
+        assert_eq!(pages.len(), 3);
+        assert!(pages[1].contains("fn three"));
+        assert!(pages[2].contains("fn five"));
+    }
+
     #[tokio::test]
     async fn test_different_resolutions() {
         let service = ExportService::new().unwrap();
@@ -531,6 +639,58 @@ mod tests {
             ..Default::default()
         };
         assert!(ExportService::validate_options(&invalid_width).is_err());
+
+        // Invalid WebP quality
+        let invalid_webp = EnhancedExportOptions {
+            format: ImageFormat::WEBP,
+            quality: 0,
+            ..Default::default()
+        };
+        assert!(ExportService::validate_options(&invalid_webp).is_err());
+
+        // Quality is unused for lossless WebP
+        let lossless_webp = EnhancedExportOptions {
+            lossless: true,
+            ..invalid_webp
+        };
+        assert!(ExportService::validate_options(&lossless_webp).is_ok());
+
+        // Lossless AVIF
+        let lossless_avif = EnhancedExportOptions {
+            format: ImageFormat::AVIF,
+            lossless: true,
+            ..Default::default()
+        };
+        assert!(ExportService::validate_options(&lossless_avif).is_err());
+
+        // Stalled animation
+        let invalid_animation = EnhancedExportOptions {
+            format: ImageFormat::GIF,
+            animation: AnimationOptions {
+                speed: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(ExportService::validate_options(&invalid_animation).is_err());
+
+        // Empty PDF pages
+        let invalid_pages = EnhancedExportOptions {
+            format: ImageFormat::PDF,
+            lines_per_page: Some(0),
+            ..Default::default()
+        };
+        assert!(ExportService::validate_options(&invalid_pages).is_err());
+
+        // Spacing is limited per side
+        let invalid_margin = EnhancedExportOptions {
+            margin: Some(Margin {
+                left: ExportService::MAX_SPACING + 1,
+                ..Margin::uniform(0)
+            }),
+            ..Default::default()
+        };
+        assert!(ExportService::validate_options(&invalid_margin).is_err());
     }
 
     #[test]
@@ -539,6 +699,11 @@ mod tests {
         assert!(formats.contains(&ImageFormat::PNG));
         assert!(formats.contains(&ImageFormat::JPEG));
         assert!(formats.contains(&ImageFormat::SVG));
+        assert!(formats.contains(&ImageFormat::PDF));
+        assert!(formats.contains(&ImageFormat::WEBP));
+        assert!(formats.contains(&ImageFormat::AVIF));
+        assert!(formats.contains(&ImageFormat::GIF));
+        assert!(formats.contains(&ImageFormat::APNG));
     }
 
     #[test]
@@ -0,0 +1,78 @@
+/// A small colored badge drawn before a file name in editor tabs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileIcon {
+    /// One to three characters shown on the badge
+    pub label: &'static str,
+    /// Badge color, as a hex string
+    pub color: &'static str,
+}
+
+/// Badge for files whose language has no icon of its own
+pub const GENERIC_ICON: FileIcon = FileIcon {
+    label: "",
+    color: "#8c8c8c",
+};
+
+/// The icon for a language, matched by name or common alias in any case
+pub fn icon_for_language(language: &str) -> Option<FileIcon> {
+    let icon = |label, color| Some(FileIcon { label, color });
+
+    match language.trim().to_lowercase().as_str() {
+        "rust" | "rs" => icon("RS", "#dea584"),
+        "javascript" | "js" | "jsx" => icon("JS", "#f1e05a"),
+        "typescript" | "ts" | "tsx" => icon("TS", "#3178c6"),
+        "python" | "py" => icon("PY", "#3572a5"),
+        "go" | "golang" => icon("GO", "#00add8"),
+        "java" => icon("J", "#b07219"),
+        "kotlin" | "kt" => icon("KT", "#a97bff"),
+        "swift" => icon("SW", "#f05138"),
+        "c" => icon("C", "#555555"),
+        "c++" | "cpp" => icon("C++", "#f34b7d"),
+        "c#" | "csharp" | "cs" => icon("C#", "#178600"),
+        "objective-c" => icon("OC", "#438eff"),
+        "ruby" | "rb" => icon("RB", "#cc342d"),
+        "php" => icon("PHP", "#4f5d95"),
+        "html" => icon("<>", "#e34c26"),
+        "css" | "scss" | "sass" => icon("#", "#563d7c"),
+        "json" => icon("{}", "#cbcb41"),
+        "yaml" | "yml" => icon("YML", "#cb171e"),
+        "toml" => icon("TML", "#9c4221"),
+        "xml" => icon("<>", "#0060ac"),
+        "markdown" | "md" => icon("MD", "#519aba"),
+        "sql" => icon("SQL", "#e38c00"),
+        "shell" | "sh" | "bash" | "zsh" | "bourne again shell (bash)" => icon("$", "#89e051"),
+        "lua" => icon("LUA", "#000080"),
+        "haskell" | "hs" => icon("HS", "#5e5086"),
+        "scala" => icon("SC", "#c22d40"),
+        "perl" | "pl" => icon("PL", "#0298c3"),
+        "r" => icon("R", "#198ce7"),
+        "dockerfile" | "docker" => icon("DK", "#384d54"),
+        _ => None,
+    }
+}
+
+/// The extension of a file name, if it has one
+pub fn extension(file_name: &str) -> Option<&str> {
+    file_name
+        .rsplit_once('.')
+        .map(|(_, extension)| extension)
+        .filter(|extension| !extension.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icons_match_names_and_aliases() {
+        assert_eq!(icon_for_language("Rust"), icon_for_language("rs"));
+        assert_eq!(icon_for_language("C++").unwrap().label, "C++");
+        assert_eq!(icon_for_language(" TypeScript ").unwrap().color, "#3178c6");
+        assert_eq!(icon_for_language("Plain Text"), None);
+
+        assert_eq!(extension("main.rs"), Some("rs"));
+        assert_eq!(extension("archive.tar.gz"), Some("gz"));
+        assert_eq!(extension("Makefile"), None);
+        assert_eq!(extension("trailing."), None);
+    }
+}
@@ -0,0 +1,412 @@
+use crate::models::errors::AppError;
+use crate::services::graphemes;
+use ab_glyph::{Font, FontArc, FontVec};
+use fontdb::{Database, Family, Query, Style, Weight};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Monospace font bundled into the binary so rendering works without system fonts
+const BUNDLED_MONO_REGULAR: &[u8] = include_bytes!("../../assets/fonts/DejaVuSansMono.ttf");
+
+/// Family name of the bundled fallback font
+pub const FALLBACK_FONT_FAMILY: &str = "DejaVu Sans Mono";
+
+/// Programming font with ligatures, bundled because the built-in themes ask for it
+const BUNDLED_LIGATURE_MONO: &[u8] = include_bytes!("../../assets/fonts/FiraCode-Medium.ttf");
+
+/// Family name of the bundled ligature font
+pub const LIGATURE_FONT_FAMILY: &str = "Fira Code";
+
+/// Monochrome emoji font bundled so emoji render where no emoji font is installed
+const BUNDLED_EMOJI: &[u8] = include_bytes!("../../assets/fonts/NotoEmoji-Regular.ttf");
+
+/// Families tried, in order, for CJK and other characters code fonts lack
+const CJK_FALLBACK_FAMILIES: &[&str] = &[
+    "Noto Sans Mono CJK SC",
+    "Noto Sans CJK SC",
+    "Source Han Sans SC",
+    "Sarasa Mono SC",
+    "WenQuanYi Zen Hei Mono",
+    "WenQuanYi Micro Hei",
+    "PingFang SC",
+    "Hiragino Sans",
+    "Microsoft YaHei",
+    "MS Gothic",
+    "Malgun Gothic",
+];
+
+/// Families tried, in order, for emoji, color fonts first
+const EMOJI_FALLBACK_FAMILIES: &[&str] = &[
+    "Noto Color Emoji",
+    "Apple Color Emoji",
+    "Segoe UI Emoji",
+    "Twemoji Mozilla",
+    "EmojiOne Color",
+    "Noto Emoji",
+];
+
+/// Horizontal shear of synthesized italics, about 12 degrees
+pub const SYNTHETIC_ITALIC_SKEW: f32 = 0.21;
+
+/// How much synthesized bold widens strokes, as a fraction of the font size
+pub const SYNTHETIC_BOLD_EMBOLDEN: f32 = 0.04;
+
+/// A parsed font face together with the raw file data it came from
+#[derive(Clone)]
+pub struct LoadedFont {
+    pub font: FontArc,
+    pub data: Arc<Vec<u8>>,
+    pub index: u32,
+}
+
+impl LoadedFont {
+    /// Returns true if both refer to the same face of the same font file
+    pub fn same_face(&self, other: &LoadedFont) -> bool {
+        self.index == other.index
+            && (Arc::ptr_eq(&self.data, &other.data) || self.data == other.data)
+    }
+
+    /// Returns true if the face has a glyph for every visible character of the text
+    pub fn covers(&self, text: &str) -> bool {
+        text.chars().all(|ch| {
+            ch.is_whitespace()
+                || ch.is_control()
+                || graphemes::is_invisible(ch)
+                || self.font.glyph_id(ch).0 != 0
+        })
+    }
+}
+
+/// A face resolved for a weight and slant. When the family has no matching face,
+/// the closest one is returned and renderers synthesize what it lacks.
+#[derive(Clone)]
+pub struct StyledFont {
+    pub loaded: LoadedFont,
+    pub synthetic_bold: bool,
+    pub synthetic_italic: bool,
+}
+
+/// Service for resolving font families to loaded font faces
+pub struct FontManager {
+    database: Database,
+    cache: Arc<Mutex<HashMap<String, StyledFont>>>,
+    fallback_families: Arc<Mutex<HashMap<String, Option<String>>>>,
+    fallback: LoadedFont,
+}
+
+impl FontManager {
+    /// Creates a new FontManager with system fonts and the bundled fallback font
+    pub fn new() -> Result<Self, AppError> {
+        let mut database = Database::new();
+        database.load_system_fonts();
+        database.load_font_data(BUNDLED_MONO_REGULAR.to_vec());
+        database.load_font_data(BUNDLED_LIGATURE_MONO.to_vec());
+        database.load_font_data(BUNDLED_EMOJI.to_vec());
+
+        let font = FontArc::try_from_slice(BUNDLED_MONO_REGULAR).map_err(|e| {
+            AppError::image_generation_failed(format!("Failed to load bundled font: {}", e))
+        })?;
+        let fallback = LoadedFont {
+            font,
+            data: Arc::new(BUNDLED_MONO_REGULAR.to_vec()),
+            index: 0,
+        };
+
+        tracing::debug!("Font database loaded with {} faces", database.len());
+
+        Ok(FontManager {
+            database,
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            fallback_families: Arc::new(Mutex::new(HashMap::new())),
+            fallback,
+        })
+    }
+
+    /// Resolves a font family name to a font face, falling back to the bundled font
+    pub fn resolve(&self, family: &str) -> FontArc {
+        self.resolve_loaded(family).font
+    }
+
+    /// Resolves a font family name to a face and its raw data, falling back to the bundled font
+    pub fn resolve_loaded(&self, family: &str) -> LoadedFont {
+        self.resolve_styled(family, false, false).loaded
+    }
+
+    /// Resolves the bold and/or italic face of a family, falling back to the bundled font
+    pub fn resolve_styled(&self, family: &str, bold: bool, italic: bool) -> StyledFont {
+        let mut key = family.trim().to_lowercase();
+        if bold || italic {
+            key.push_str(match (bold, italic) {
+                (true, true) => ":bold-italic",
+                (true, false) => ":bold",
+                _ => ":italic",
+            });
+        }
+
+        // Check cache first
+        {
+            let cache = self.cache.lock().unwrap();
+            if let Some(font) = cache.get(&key) {
+                return font.clone();
+            }
+        }
+
+        let font = self.load_family(family.trim(), bold, italic).unwrap_or_else(|| {
+            tracing::debug!(
+                "Font family '{}' not found, using {}",
+                family,
+                FALLBACK_FONT_FAMILY
+            );
+            StyledFont {
+                loaded: self.fallback.clone(),
+                synthetic_bold: bold,
+                synthetic_italic: italic,
+            }
+        });
+
+        // Cache the result
+        {
+            let mut cache = self.cache.lock().unwrap();
+            cache.insert(key, font.clone());
+        }
+
+        font
+    }
+
+    /// Returns true if the family is available on this system or bundled
+    pub fn has_family(&self, family: &str) -> bool {
+        self.query_family(family, false, false).is_some()
+    }
+
+    /// Returns the bundled fallback font
+    pub fn fallback(&self) -> FontArc {
+        self.fallback.font.clone()
+    }
+
+    /// Finds a family to draw a grapheme the code font lacks: the bundled font, then CJK
+    /// fonts, then emoji fonts (first, for emoji), then any installed face that has it.
+    /// Returns None if no font covers the grapheme.
+    pub fn fallback_family(&self, grapheme: &str) -> Option<String> {
+        {
+            let cache = self.fallback_families.lock().unwrap();
+            if let Some(family) = cache.get(grapheme) {
+                return family.clone();
+            }
+        }
+
+        let chain: Vec<&str> = if graphemes::is_emoji(grapheme) {
+            EMOJI_FALLBACK_FAMILIES
+                .iter()
+                .chain([FALLBACK_FONT_FAMILY].iter())
+                .chain(CJK_FALLBACK_FAMILIES)
+                .copied()
+                .collect()
+        } else {
+            [FALLBACK_FONT_FAMILY]
+                .iter()
+                .chain(CJK_FALLBACK_FAMILIES)
+                .chain(EMOJI_FALLBACK_FAMILIES)
+                .copied()
+                .collect()
+        };
+
+        let family = chain
+            .into_iter()
+            .find(|family| {
+                self.query_family(family, false, false)
+                    .is_some_and(|id| self.face_covers(id, grapheme))
+            })
+            .map(str::to_string)
+            .or_else(|| {
+                self.database
+                    .faces()
+                    .filter(|face| face.style == Style::Normal)
+                    .find(|face| self.face_covers(face.id, grapheme))
+                    .and_then(|face| face.families.first().map(|(name, _)| name.clone()))
+            });
+
+        if family.is_none() {
+            tracing::debug!("No font covers {:?}", grapheme);
+        }
+
+        {
+            let mut cache = self.fallback_families.lock().unwrap();
+            cache.insert(grapheme.to_string(), family.clone());
+        }
+
+        family
+    }
+
+    /// Loads the closest face of a font family from the database
+    fn load_family(&self, family: &str, bold: bool, italic: bool) -> Option<StyledFont> {
+        let id = self.query_family(family, bold, italic)?;
+        let info = self.database.face(id)?;
+        let synthetic_bold = bold && info.weight < Weight::SEMIBOLD;
+        let synthetic_italic = italic && info.style == Style::Normal;
+
+        self.database
+            .with_face_data(id, |data, index| {
+                let font = FontVec::try_from_vec_and_index(data.to_vec(), index).ok()?;
+                Some(StyledFont {
+                    loaded: LoadedFont {
+                        font: FontArc::new(font),
+                        data: Arc::new(data.to_vec()),
+                        index,
+                    },
+                    synthetic_bold,
+                    synthetic_italic,
+                })
+            })
+            .flatten()
+    }
+
+    /// Returns true if a face in the database has glyphs for the whole grapheme
+    fn face_covers(&self, id: fontdb::ID, grapheme: &str) -> bool {
+        self.database
+            .with_face_data(id, |data, index| {
+                let face = ttf_parser::Face::parse(data, index).ok()?;
+                Some(grapheme.chars().all(|ch| {
+                    ch.is_whitespace()
+                        || ch.is_control()
+                        || graphemes::is_invisible(ch)
+                        || face.glyph_index(ch).is_some()
+                }))
+            })
+            .flatten()
+            .unwrap_or(false)
+    }
+
+    /// Finds the database ID of the face of a family closest to the weight and slant
+    fn query_family(&self, family: &str, bold: bool, italic: bool) -> Option<fontdb::ID> {
+        if family.is_empty() {
+            return None;
+        }
+
+        let families = match family.to_lowercase().as_str() {
+            "monospace" => [Family::Name(FALLBACK_FONT_FAMILY)],
+            _ => [Family::Name(family)],
+        };
+
+        self.database.query(&Query {
+            families: &families,
+            weight: if bold { Weight::BOLD } else { Weight::NORMAL },
+            style: if italic { Style::Italic } else { Style::Normal },
+            ..Query::default()
+        })
+    }
+}
+
+impl Default for FontManager {
+    fn default() -> Self {
+        Self::new().expect("Failed to create default FontManager")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ab_glyph::Font;
+
+    #[test]
+    fn test_font_manager_creation() {
+        let manager = FontManager::new();
+        assert!(manager.is_ok());
+    }
+
+    #[test]
+    fn test_bundled_font_available() {
+        let manager = FontManager::new().unwrap();
+        assert!(manager.has_family(FALLBACK_FONT_FAMILY));
+        assert!(manager.has_family("monospace"));
+    }
+
+    #[test]
+    fn test_unknown_family_falls_back() {
+        let manager = FontManager::new().unwrap();
+        let font = manager.resolve("Definitely Not A Real Font");
+
+        // The fallback font must be able to render basic ASCII
+        assert_ne!(font.glyph_id('a').0, 0);
+        assert_eq!(
+            font.units_per_em(),
+            manager.fallback().units_per_em()
+        );
+    }
+
+    #[test]
+    fn test_missing_styles_are_synthesized() {
+        let manager = FontManager::new().unwrap();
+
+        let regular = manager.resolve_styled("Definitely Not A Real Font", false, false);
+        assert!(!regular.synthetic_bold && !regular.synthetic_italic);
+
+        // The bundled font only has a regular face
+        let bold_italic = manager.resolve_styled("Definitely Not A Real Font", true, true);
+        assert!(bold_italic.synthetic_bold && bold_italic.synthetic_italic);
+        assert_eq!(bold_italic.loaded.data.len(), BUNDLED_MONO_REGULAR.len());
+    }
+
+    #[test]
+    fn test_coverage_ignores_joiners_and_selectors() {
+        let manager = FontManager::new().unwrap();
+        let font = manager.resolve_loaded("monospace");
+
+        assert!(font.covers("cafe\u{301}"));
+        assert!(font.covers("\t"));
+        assert!(font.covers("\u{263A}\u{FE0F}"));
+        assert!(!font.covers("漢"));
+    }
+
+    #[test]
+    fn test_fallback_family_covers_the_grapheme() {
+        let manager = FontManager::new().unwrap();
+
+        // The bundled font is tried before anything installed
+        assert_eq!(manager.fallback_family("é").as_deref(), Some(FALLBACK_FONT_FAMILY));
+
+        // Whatever is found for characters the bundled font lacks must draw them
+        if let Some(family) = manager.fallback_family("漢") {
+            assert!(manager.resolve_loaded(&family).covers("漢"));
+        }
+        let cache = manager.fallback_families.lock().unwrap();
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_ligature_font_is_bundled() {
+        let manager = FontManager::new().unwrap();
+        assert!(manager.has_family(LIGATURE_FONT_FAMILY));
+
+        let font = manager.resolve_loaded(LIGATURE_FONT_FAMILY);
+        assert!(!font.same_face(&manager.resolve_loaded("monospace")));
+    }
+
+    #[test]
+    fn test_emoji_resolve_to_a_bundled_glyph() {
+        let manager = FontManager::new().unwrap();
+
+        let family = manager.fallback_family("😀").unwrap();
+        let font = manager.resolve_loaded(&family);
+        assert_ne!(font.font.glyph_id('😀').0, 0);
+    }
+
+    #[test]
+    #[ignore = "needs a CJK font such as fonts-noto-cjk, which the Docker image installs"]
+    fn test_cjk_resolves_to_an_installed_glyph() {
+        let manager = FontManager::new().unwrap();
+
+        let family = manager.fallback_family("漢").unwrap();
+        let font = manager.resolve_loaded(&family);
+        assert_ne!(font.font.glyph_id('漢').0, 0);
+    }
+
+    #[test]
+    fn test_resolve_is_cached() {
+        let manager = FontManager::new().unwrap();
+        manager.resolve("Fira Code");
+        manager.resolve("fira code");
+
+        let cache = manager.cache.lock().unwrap();
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -0,0 +1,456 @@
+use crate::models::errors::AppError;
+use std::collections::BTreeSet;
+use ttf_parser::{RawFace, Tag};
+
+const WOFF2_SIGNATURE: u32 = 0x774F_4632; // "wOF2"
+const WOFF2_HEADER_SIZE: usize = 48;
+
+/// Table directory flag for an explicit tag
+const ARBITRARY_TAG: u8 = 0x3F;
+/// glyf/loca transform version meaning "stored as is"
+const GLYF_LOCA_NULL_TRANSFORM: u8 = 3 << 6;
+
+// Composite glyph flags from the TrueType glyf table
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+/// Creates a WOFF2 font containing only the glyphs needed to draw `chars`.
+///
+/// Glyph IDs are preserved so every other table (cmap, hmtx, GSUB, ...) stays valid;
+/// unused glyphs simply become empty. Fonts without TrueType outlines are embedded whole.
+pub fn subset_to_woff2(
+    data: &[u8],
+    index: u32,
+    chars: &BTreeSet<char>,
+) -> Result<Vec<u8>, AppError> {
+    let face = ttf_parser::Face::parse(data, index)
+        .map_err(|e| AppError::image_generation_failed(format!("Failed to parse font: {}", e)))?;
+    let glyph_ids = chars
+        .iter()
+        .filter_map(|&ch| face.glyph_index(ch))
+        .map(|id| id.0)
+        .collect();
+
+    let tables = subset_tables(data, index, &glyph_ids)?;
+    encode_woff2(sfnt_flavor(data, index)?, tables)
+}
+
+/// Creates a plain sfnt (TTF/OTF) font containing only the given glyphs, which may
+/// include ligatures and alternates picked by text shaping.
+///
+/// Glyph IDs are preserved in the same way as [`subset_to_woff2`].
+pub fn subset_to_sfnt(
+    data: &[u8],
+    index: u32,
+    glyph_ids: &BTreeSet<u16>,
+) -> Result<Vec<u8>, AppError> {
+    let tables = subset_tables(data, index, glyph_ids)?;
+    Ok(encode_sfnt(sfnt_flavor(data, index)?, tables))
+}
+
+/// Copies the tables of a face, emptying glyphs that are not in `glyph_ids`
+fn subset_tables(
+    data: &[u8],
+    index: u32,
+    glyph_ids: &BTreeSet<u16>,
+) -> Result<Vec<(Tag, Vec<u8>)>, AppError> {
+    let face = ttf_parser::Face::parse(data, index)
+        .map_err(|e| AppError::image_generation_failed(format!("Failed to parse font: {}", e)))?;
+    let raw = RawFace::parse(data, index)
+        .map_err(|e| AppError::image_generation_failed(format!("Failed to parse font: {}", e)))?;
+
+    let mut tables: Vec<(Tag, Vec<u8>)> = raw
+        .table_records
+        .into_iter()
+        // The signature no longer matches once glyphs are removed
+        .filter(|record| record.tag != Tag::from_bytes(b"DSIG"))
+        .filter_map(|record| {
+            let start = record.offset as usize;
+            let end = start.checked_add(record.length as usize)?;
+            data.get(start..end).map(|bytes| (record.tag, bytes.to_vec()))
+        })
+        .collect();
+
+    let glyf = raw.table(Tag::from_bytes(b"glyf"));
+    let loca = raw.table(Tag::from_bytes(b"loca"));
+    let head = raw.table(Tag::from_bytes(b"head"));
+
+    if let (Some(glyf), Some(loca), Some(head)) = (glyf, loca, head) {
+        let long_offsets = read_u16(head, 50).ok_or_else(invalid_font)? == 1;
+        let offsets = parse_loca(loca, long_offsets, face.number_of_glyphs())?;
+
+        let mut keep = BTreeSet::from([0u16]); // .notdef is always required
+        keep.extend(glyph_ids);
+        close_over_components(glyf, &offsets, &mut keep);
+
+        let (new_glyf, new_loca) = rebuild_glyf(glyf, &offsets, &keep, long_offsets);
+        for (tag, bytes) in tables.iter_mut() {
+            if *tag == Tag::from_bytes(b"glyf") {
+                *bytes = new_glyf.clone();
+            } else if *tag == Tag::from_bytes(b"loca") {
+                *bytes = new_loca.clone();
+            }
+        }
+    }
+
+    Ok(tables)
+}
+
+/// Reads the glyph offsets from the loca table
+fn parse_loca(loca: &[u8], long_offsets: bool, num_glyphs: u16) -> Result<Vec<usize>, AppError> {
+    (0..=num_glyphs as usize)
+        .map(|i| {
+            if long_offsets {
+                read_u32(loca, i * 4).map(|offset| offset as usize)
+            } else {
+                read_u16(loca, i * 2).map(|offset| offset as usize * 2)
+            }
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(invalid_font)
+}
+
+/// Adds the components of every kept composite glyph to the kept set
+fn close_over_components(glyf: &[u8], offsets: &[usize], keep: &mut BTreeSet<u16>) {
+    let mut pending: Vec<u16> = keep.iter().copied().collect();
+
+    while let Some(glyph_id) = pending.pop() {
+        let Some(glyph) = glyph_data(glyf, offsets, glyph_id) else {
+            continue;
+        };
+
+        for component in composite_components(glyph) {
+            if keep.insert(component) {
+                pending.push(component);
+            }
+        }
+    }
+}
+
+/// Returns the raw glyf bytes of a glyph
+fn glyph_data<'a>(glyf: &'a [u8], offsets: &[usize], glyph_id: u16) -> Option<&'a [u8]> {
+    let start = *offsets.get(glyph_id as usize)?;
+    let end = *offsets.get(glyph_id as usize + 1)?;
+    glyf.get(start..end.max(start))
+}
+
+/// Lists the glyph IDs referenced by a composite glyph
+fn composite_components(glyph: &[u8]) -> Vec<u16> {
+    let mut components = Vec::new();
+
+    let is_composite = glyph.len() >= 10 && (read_u16(glyph, 0).unwrap_or(0) as i16) < 0;
+    if !is_composite {
+        return components;
+    }
+
+    let mut offset = 10;
+    while let (Some(flags), Some(glyph_index)) =
+        (read_u16(glyph, offset), read_u16(glyph, offset + 2))
+    {
+        components.push(glyph_index);
+        offset += 4;
+        offset += if flags & ARG_1_AND_2_ARE_WORDS != 0 { 4 } else { 2 };
+
+        if flags & WE_HAVE_A_SCALE != 0 {
+            offset += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            offset += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            offset += 8;
+        }
+
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+
+    components
+}
+
+/// Builds new glyf and loca tables where glyphs outside `keep` are empty
+fn rebuild_glyf(
+    glyf: &[u8],
+    offsets: &[usize],
+    keep: &BTreeSet<u16>,
+    long_offsets: bool,
+) -> (Vec<u8>, Vec<u8>) {
+    let mut new_glyf = Vec::new();
+    let mut new_loca = Vec::new();
+    let alignment = if long_offsets { 4 } else { 2 };
+
+    let push_offset = |loca: &mut Vec<u8>, offset: usize| {
+        if long_offsets {
+            loca.extend_from_slice(&(offset as u32).to_be_bytes());
+        } else {
+            loca.extend_from_slice(&((offset / 2) as u16).to_be_bytes());
+        }
+    };
+
+    for glyph_id in 0..offsets.len().saturating_sub(1) {
+        push_offset(&mut new_loca, new_glyf.len());
+
+        if keep.contains(&(glyph_id as u16)) {
+            if let Some(glyph) = glyph_data(glyf, offsets, glyph_id as u16) {
+                new_glyf.extend_from_slice(glyph);
+                while new_glyf.len() % alignment != 0 {
+                    new_glyf.push(0);
+                }
+            }
+        }
+    }
+    push_offset(&mut new_loca, new_glyf.len());
+
+    (new_glyf, new_loca)
+}
+
+/// Reads the sfnt version of a face, looking inside font collections
+fn sfnt_flavor(data: &[u8], index: u32) -> Result<u32, AppError> {
+    let mut offset = 0;
+    if data.get(0..4) == Some(b"ttcf") {
+        offset = read_u32(data, 12 + index as usize * 4).ok_or_else(invalid_font)? as usize;
+    }
+
+    read_u32(data, offset).ok_or_else(invalid_font)
+}
+
+/// Packs tables into a WOFF2 file with a single brotli-compressed stream
+fn encode_woff2(flavor: u32, mut tables: Vec<(Tag, Vec<u8>)>) -> Result<Vec<u8>, AppError> {
+    // Sorting by tag also guarantees loca follows glyf, as the format requires
+    tables.sort_by_key(|(tag, _)| tag.0);
+
+    let mut directory = Vec::new();
+    let mut stream = Vec::new();
+    let mut total_sfnt_size = 12 + 16 * tables.len();
+
+    for (tag, bytes) in &tables {
+        let is_glyf_or_loca =
+            *tag == Tag::from_bytes(b"glyf") || *tag == Tag::from_bytes(b"loca");
+        let flags = if is_glyf_or_loca {
+            ARBITRARY_TAG | GLYF_LOCA_NULL_TRANSFORM
+        } else {
+            ARBITRARY_TAG
+        };
+
+        directory.push(flags);
+        directory.extend_from_slice(&tag.0.to_be_bytes());
+        write_uint_base128(&mut directory, bytes.len() as u32);
+
+        stream.extend_from_slice(bytes);
+        total_sfnt_size += (bytes.len() + 3) & !3;
+    }
+
+    let mut compressed = Vec::new();
+    let params = brotli::enc::BrotliEncoderParams {
+        quality: 11,
+        mode: brotli::enc::backward_references::BrotliEncoderMode::BROTLI_MODE_FONT,
+        ..Default::default()
+    };
+    brotli::BrotliCompress(&mut stream.as_slice(), &mut compressed, &params).map_err(|e| {
+        AppError::image_generation_failed(format!("Font compression failed: {}", e))
+    })?;
+
+    let unpadded_length = WOFF2_HEADER_SIZE + directory.len() + compressed.len();
+    let total_length = (unpadded_length + 3) & !3;
+
+    let mut woff2 = Vec::with_capacity(total_length);
+    woff2.extend_from_slice(&WOFF2_SIGNATURE.to_be_bytes());
+    woff2.extend_from_slice(&flavor.to_be_bytes());
+    woff2.extend_from_slice(&(total_length as u32).to_be_bytes());
+    woff2.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+    woff2.extend_from_slice(&0u16.to_be_bytes()); // reserved
+    woff2.extend_from_slice(&(total_sfnt_size as u32).to_be_bytes());
+    woff2.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+    woff2.extend_from_slice(&1u16.to_be_bytes()); // major version
+    woff2.extend_from_slice(&0u16.to_be_bytes()); // minor version
+    woff2.extend_from_slice(&[0; 20]); // no metadata or private data
+    woff2.extend_from_slice(&directory);
+    woff2.extend_from_slice(&compressed);
+    woff2.resize(total_length, 0);
+
+    Ok(woff2)
+}
+
+/// Packs tables into an uncompressed sfnt file with valid checksums
+fn encode_sfnt(flavor: u32, mut tables: Vec<(Tag, Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by_key(|(tag, _)| tag.0);
+
+    let num_tables = tables.len() as u16;
+    let entry_selector = 15 - num_tables.max(1).leading_zeros() as u16;
+    let search_range = (1u16 << entry_selector) * 16;
+
+    let mut sfnt = Vec::new();
+    sfnt.extend_from_slice(&flavor.to_be_bytes());
+    sfnt.extend_from_slice(&num_tables.to_be_bytes());
+    sfnt.extend_from_slice(&search_range.to_be_bytes());
+    sfnt.extend_from_slice(&entry_selector.to_be_bytes());
+    sfnt.extend_from_slice(&(num_tables * 16 - search_range).to_be_bytes());
+
+    let directory_end = 12 + 16 * tables.len();
+    let mut body = Vec::new();
+    let mut head_offset = None;
+
+    for (tag, bytes) in &mut tables {
+        if *tag == Tag::from_bytes(b"head") && bytes.len() >= 12 {
+            // The adjustment is computed over the whole file once it is assembled
+            bytes[8..12].fill(0);
+            head_offset = Some(directory_end + body.len());
+        }
+
+        sfnt.extend_from_slice(&tag.0.to_be_bytes());
+        sfnt.extend_from_slice(&table_checksum(bytes).to_be_bytes());
+        sfnt.extend_from_slice(&((directory_end + body.len()) as u32).to_be_bytes());
+        sfnt.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+
+        body.extend_from_slice(bytes);
+        body.resize((body.len() + 3) & !3, 0);
+    }
+
+    sfnt.extend_from_slice(&body);
+
+    if let Some(offset) = head_offset {
+        let adjustment = 0xB1B0_AFBAu32.wrapping_sub(table_checksum(&sfnt));
+        sfnt[offset + 8..offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+
+    sfnt
+}
+
+/// Sums a table as big-endian u32 words, zero-padding the final word
+fn table_checksum(bytes: &[u8]) -> u32 {
+    bytes.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// Writes a WOFF2 variable-length UIntBase128 value
+fn write_uint_base128(out: &mut Vec<u8>, value: u32) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    let mut rest = value >> 7;
+    while rest > 0 {
+        bytes.push((rest & 0x7F) as u8 | 0x80);
+        rest >>= 7;
+    }
+    out.extend(bytes.iter().rev());
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn invalid_font() -> AppError {
+    AppError::image_generation_failed("Font data is malformed")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::font_manager::FontManager;
+
+    /// Minimal WOFF2 reader for fonts written by `encode_woff2`
+    fn decode_woff2(woff2: &[u8]) -> Vec<u8> {
+        assert_eq!(read_u32(woff2, 0), Some(WOFF2_SIGNATURE));
+        let flavor = read_u32(woff2, 4).unwrap();
+        let num_tables = read_u16(woff2, 12).unwrap() as usize;
+        let compressed_length = read_u32(woff2, 20).unwrap() as usize;
+
+        let mut offset = WOFF2_HEADER_SIZE;
+        let mut entries = Vec::new();
+        for _ in 0..num_tables {
+            assert_eq!(woff2[offset] & ARBITRARY_TAG, ARBITRARY_TAG);
+            let tag = read_u32(woff2, offset + 1).unwrap();
+            offset += 5;
+
+            let mut length = 0u32;
+            loop {
+                let byte = woff2[offset];
+                offset += 1;
+                length = (length << 7) | (byte & 0x7F) as u32;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+            entries.push((tag, length as usize));
+        }
+
+        let mut stream = Vec::new();
+        brotli::BrotliDecompress(&mut &woff2[offset..offset + compressed_length], &mut stream)
+            .unwrap();
+
+        let mut tables = Vec::new();
+        let mut stream_offset = 0;
+        for (tag, length) in entries {
+            tables.push((Tag(tag), stream[stream_offset..stream_offset + length].to_vec()));
+            stream_offset += length;
+        }
+
+        encode_sfnt(flavor, tables)
+    }
+
+    #[test]
+    fn test_uint_base128_encoding() {
+        let mut out = Vec::new();
+        write_uint_base128(&mut out, 63);
+        assert_eq!(out, vec![63]);
+
+        out.clear();
+        write_uint_base128(&mut out, 300);
+        assert_eq!(out, vec![0x82, 0x2C]);
+    }
+
+    #[test]
+    fn test_subset_keeps_only_requested_glyphs() {
+        let manager = FontManager::new().unwrap();
+        let loaded = manager.resolve_loaded("monospace");
+        let chars: BTreeSet<char> = "fn main".chars().collect();
+
+        let woff2 = subset_to_woff2(&loaded.data, loaded.index, &chars).unwrap();
+        assert!(woff2.len() < loaded.data.len() / 4);
+        assert_eq!(woff2.len() % 4, 0);
+
+        let sfnt = decode_woff2(&woff2);
+        let face = ttf_parser::Face::parse(&sfnt, 0).unwrap();
+
+        let kept = face.glyph_index('m').unwrap();
+        assert!(face.glyph_bounding_box(kept).is_some());
+
+        let dropped = face.glyph_index('Q').unwrap();
+        assert!(face.glyph_bounding_box(dropped).is_none());
+
+        // Metrics of kept glyphs are unchanged
+        let original = ttf_parser::Face::parse(&loaded.data, loaded.index).unwrap();
+        assert_eq!(face.glyph_hor_advance(kept), original.glyph_hor_advance(kept));
+    }
+
+    #[test]
+    fn test_sfnt_subset_has_valid_checksums() {
+        let manager = FontManager::new().unwrap();
+        let loaded = manager.resolve_loaded("monospace");
+        let original = ttf_parser::Face::parse(&loaded.data, loaded.index).unwrap();
+        let glyph_ids: BTreeSet<u16> = "let x"
+            .chars()
+            .filter_map(|ch| original.glyph_index(ch))
+            .map(|id| id.0)
+            .collect();
+
+        let sfnt = subset_to_sfnt(&loaded.data, loaded.index, &glyph_ids).unwrap();
+        assert!(sfnt.len() < loaded.data.len() / 2);
+
+        // A correctly adjusted font sums to the magic constant
+        assert_eq!(table_checksum(&sfnt), 0xB1B0_AFBA);
+
+        let face = ttf_parser::Face::parse(&sfnt, 0).unwrap();
+        let kept = face.glyph_index('x').unwrap();
+        assert!(face.glyph_bounding_box(kept).is_some());
+    }
+}
@@ -0,0 +1,72 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Splits text into grapheme clusters, the units the character grid is made of,
+/// skipping line endings
+pub fn graphemes(text: &str) -> impl Iterator<Item = &str> {
+    text.graphemes(true)
+        .filter(|grapheme| !matches!(*grapheme, "\n" | "\r" | "\r\n"))
+}
+
+/// Columns a grapheme cluster takes on the grid: two for wide East Asian characters
+/// and emoji, one for everything else, including lone combining marks
+pub fn grapheme_width(grapheme: &str) -> usize {
+    grapheme.width().clamp(1, 2)
+}
+
+/// Columns a string takes on the grid
+pub fn display_width(text: &str) -> usize {
+    graphemes(text).map(grapheme_width).sum()
+}
+
+/// Returns true if a grapheme is drawn as an emoji: pictographs, flags, keycaps
+/// and anything asking for emoji presentation or joining emoji together
+pub fn is_emoji(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .any(|ch| matches!(ch, '\u{200D}' | '\u{FE0F}' | '\u{20E3}' | '\u{1F000}'..='\u{1FAFF}'))
+}
+
+/// Characters that only affect their neighbours and need no glyph of their own:
+/// joiners, variation selectors and emoji tag sequences
+pub fn is_invisible(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{200B}'..='\u{200D}' | '\u{FE00}'..='\u{FE0F}' | '\u{E0020}'..='\u{E007F}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grapheme_widths() {
+        assert_eq!(grapheme_width("a"), 1);
+        assert_eq!(grapheme_width("e\u{301}"), 1);
+        assert_eq!(grapheme_width("\u{301}"), 1);
+        assert_eq!(grapheme_width("漢"), 2);
+        assert_eq!(grapheme_width("Ａ"), 2);
+        assert_eq!(grapheme_width("👍"), 2);
+        assert_eq!(grapheme_width("👨\u{200D}👩\u{200D}👧"), 2);
+        assert_eq!(grapheme_width("☺\u{FE0F}"), 2);
+    }
+
+    #[test]
+    fn test_display_width_counts_clusters() {
+        assert_eq!(display_width("let café = 1;\n"), 13);
+        assert_eq!(display_width("cafe\u{301}"), 4);
+        assert_eq!(display_width("// 漢字 👨\u{200D}👩\u{200D}👧"), 10);
+        assert_eq!(graphemes("🇯🇵x\r\n").collect::<Vec<_>>(), vec!["🇯🇵", "x"]);
+    }
+
+    #[test]
+    fn test_emoji_detection() {
+        assert!(is_emoji("👍"));
+        assert!(is_emoji("☺\u{FE0F}"));
+        assert!(is_emoji("1\u{FE0F}\u{20E3}"));
+        assert!(is_emoji("🇯🇵"));
+        assert!(!is_emoji("漢"));
+        assert!(!is_emoji("☺"));
+    }
+}
@@ -122,10 +122,11 @@ impl LanguageDetector {
         if suggestions.is_empty() {
             for lang in supported {
                 let lang_lower = lang.to_lowercase();
-                if !target_lower.is_empty() && !lang_lower.is_empty() {
-                    if lang_lower.starts_with(&target_lower[..1]) {
-                        suggestions.push(lang.clone());
-                    }
+                if !target_lower.is_empty()
+                    && !lang_lower.is_empty()
+                    && lang_lower.starts_with(&target_lower[..1])
+                {
+                    suggestions.push(lang.clone());
                 }
             }
         }
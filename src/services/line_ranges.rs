@@ -0,0 +1,168 @@
+use crate::models::errors::AppError;
+use crate::models::theme::Theme;
+use serde::{Deserialize, Serialize};
+
+/// Upper bound on ranges per request so one export can't stack up thousands of bands
+pub const MAX_LINE_RANGES: usize = 64;
+
+/// How the lines of a range are emphasized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum LineRangeMode {
+    /// A background band behind the lines
+    #[default]
+    Highlight,
+    /// Every line outside a focus range is dimmed
+    Focus,
+    /// A green band with a `+` marker in the gutter
+    Added,
+    /// A red band with a `-` marker in the gutter
+    Removed,
+}
+
+/// An inclusive range of 1-based line numbers and how to emphasize it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+    #[serde(default)]
+    pub mode: LineRangeMode,
+    /// Overrides the band color of highlight, added and removed ranges
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// The emphasis that applies to a single line once every range is considered
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineEmphasis {
+    /// Mode and optional color of the band; later ranges win where bands overlap
+    pub band: Option<(LineRangeMode, Option<String>)>,
+    pub dimmed: bool,
+}
+
+impl LineRange {
+    /// Creates a range with the default color for its mode
+    pub fn new(start: usize, end: usize, mode: LineRangeMode) -> Self {
+        LineRange {
+            start,
+            end,
+            mode,
+            color: None,
+        }
+    }
+
+    /// Returns true if the 1-based line number falls inside the range
+    pub fn contains(&self, line_number: usize) -> bool {
+        (self.start..=self.end).contains(&line_number)
+    }
+
+    /// Checks that the range is well formed
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.start == 0 || self.end < self.start {
+            return Err(AppError::image_generation_failed(format!(
+                "Invalid line range {}-{}: lines start at 1 and the end can't precede the start",
+                self.start, self.end
+            )));
+        }
+
+        if let Some(ref color) = self.color {
+            if !Theme::is_valid_color(color) {
+                return Err(AppError::image_generation_failed(format!(
+                    "Invalid line range color: {}",
+                    color
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Validates every range and their count
+pub fn validate_line_ranges(ranges: &[LineRange]) -> Result<(), AppError> {
+    if ranges.len() > MAX_LINE_RANGES {
+        return Err(AppError::image_generation_failed(format!(
+            "At most {} line ranges are supported",
+            MAX_LINE_RANGES
+        )));
+    }
+
+    ranges.iter().try_for_each(LineRange::validate)
+}
+
+/// Resolves how a 1-based line number is emphasized by a set of ranges
+pub fn emphasis_for_line(ranges: &[LineRange], line_number: usize) -> LineEmphasis {
+    let has_focus = ranges.iter().any(|range| range.mode == LineRangeMode::Focus);
+    let mut emphasis = LineEmphasis {
+        band: None,
+        dimmed: has_focus,
+    };
+
+    for range in ranges.iter().filter(|range| range.contains(line_number)) {
+        match range.mode {
+            LineRangeMode::Focus => emphasis.dimmed = false,
+            mode => emphasis.band = Some((mode, range.color.clone())),
+        }
+    }
+
+    emphasis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_dims_lines_outside_every_focus_range() {
+        let ranges = vec![
+            LineRange::new(2, 3, LineRangeMode::Focus),
+            LineRange::new(6, 6, LineRangeMode::Focus),
+        ];
+
+        let dimmed: Vec<usize> = (1..=7)
+            .filter(|&line| emphasis_for_line(&ranges, line).dimmed)
+            .collect();
+        assert_eq!(dimmed, vec![1, 4, 5, 7]);
+
+        // Without a focus range nothing is dimmed
+        let highlight = [LineRange::new(2, 3, LineRangeMode::Highlight)];
+        assert!(!emphasis_for_line(&highlight, 1).dimmed);
+    }
+
+    #[test]
+    fn test_later_bands_win_where_ranges_overlap() {
+        let ranges = vec![
+            LineRange::new(1, 5, LineRangeMode::Highlight),
+            LineRange::new(3, 3, LineRangeMode::Removed),
+            LineRange::new(4, 4, LineRangeMode::Focus),
+        ];
+
+        assert_eq!(
+            emphasis_for_line(&ranges, 3).band,
+            Some((LineRangeMode::Removed, None))
+        );
+        // Focus doesn't replace a band
+        let line_four = emphasis_for_line(&ranges, 4);
+        assert_eq!(line_four.band, Some((LineRangeMode::Highlight, None)));
+        assert!(!line_four.dimmed);
+        assert_eq!(emphasis_for_line(&ranges, 6), LineEmphasis {
+            band: None,
+            dimmed: true,
+        });
+    }
+
+    #[test]
+    fn test_range_validation() {
+        assert!(LineRange::new(12, 18, LineRangeMode::Highlight).validate().is_ok());
+        assert!(LineRange::new(0, 3, LineRangeMode::Highlight).validate().is_err());
+        assert!(LineRange::new(5, 4, LineRangeMode::Added).validate().is_err());
+
+        let bad_color = LineRange {
+            color: Some("yellow".to_string()),
+            ..LineRange::new(1, 1, LineRangeMode::Highlight)
+        };
+        assert!(bad_color.validate().is_err());
+
+        let too_many = vec![LineRange::new(1, 1, LineRangeMode::Highlight); MAX_LINE_RANGES + 1];
+        assert!(validate_line_ranges(&too_many).is_err());
+    }
+}
@@ -5,6 +5,27 @@ pub mod download_service;
 pub mod export_service;
 pub mod ocr;
 pub mod image_generator;
+pub mod scene;
+pub mod blur;
+pub mod patterns;
+pub mod background_images;
+pub mod svg_renderer;
+pub mod pdf_renderer;
+pub mod animation;
+pub mod line_ranges;
+pub mod annotations;
+pub mod diff;
+pub mod shell;
+pub mod redaction;
+pub mod ansi;
+pub mod wrap;
+pub mod canvas;
+pub mod file_icons;
+pub mod shaping;
+pub mod graphemes;
+pub mod color_glyphs;
+pub mod font_manager;
+pub mod font_subset;
 pub mod theme_manager;
 pub mod language_detector;
 pub mod file_storage;
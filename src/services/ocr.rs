@@ -1,11 +1,15 @@
 // OCR service for text extraction from images
 use crate::models::errors::AppError;
-use image::{DynamicImage, GenericImageView, ImageBuffer, ImageOutputFormat};
+use image::{DynamicImage, GenericImageView};
+#[cfg(feature = "tesseract")]
+use image::{ImageBuffer, ImageOutputFormat};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "tesseract")]
 use std::fs::File;
 
 use std::time::Duration;
 use tokio::time::timeout;
+#[cfg(feature = "tesseract")]
 use uuid::Uuid;
 
 /// Result of OCR text extraction
@@ -325,15 +329,16 @@ impl OCRService {
             ("vv", "w"), // double v to w
         ];
 
+        // Replace O with 0 when surrounded by digits
+        let digit_o = regex::Regex::new(r"(\d)O(\d)")
+            .unwrap_or_else(|_| regex::Regex::new(r"").unwrap());
+
         // Apply context-aware replacements
         // This is a simplified version - in production, you'd want more sophisticated logic
         for (from, to) in replacements {
             // Only replace in specific contexts to avoid false positives
             if from == "O" && to == "0" {
-                // Replace O with 0 when surrounded by digits
-                let re = regex::Regex::new(r"(\d)O(\d)")
-                    .unwrap_or_else(|_| regex::Regex::new(r"").unwrap());
-                fixed = re.replace_all(&fixed, format!("$1{}$2", to)).to_string();
+                fixed = digit_o.replace_all(&fixed, format!("$1{}$2", to)).to_string();
             }
         }
 
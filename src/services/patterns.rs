@@ -0,0 +1,201 @@
+use crate::models::theme::PatternKind;
+use crate::services::scene::{Tile, TileShape};
+use image::{GrayImage, Luma};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+/// Side of a topographic tile's mask
+const TOPOGRAPHIC_MASK_SIZE: u32 = 256;
+/// Contour lines across the full height of the noise
+const TOPOGRAPHIC_LEVELS: f32 = 10.0;
+/// Side of a grain tile's mask
+const GRAIN_MASK_SIZE: u32 = 128;
+/// Points along each edge of a wave
+const WAVE_SEGMENTS: usize = 32;
+
+/// Side of a pattern's tile at scale 1, in layout units
+pub fn base_size(kind: PatternKind) -> f32 {
+    match kind {
+        PatternKind::Dots => 20.0,
+        PatternKind::Grid => 24.0,
+        PatternKind::DiagonalStripes => 16.0,
+        PatternKind::Checkerboard => 32.0,
+        PatternKind::Waves => 40.0,
+        PatternKind::Topographic => TOPOGRAPHIC_MASK_SIZE as f32,
+        PatternKind::Grain => GRAIN_MASK_SIZE as f32,
+    }
+}
+
+/// Rotation a pattern is drawn at before the theme's own, in degrees
+pub fn base_rotation(kind: PatternKind) -> f32 {
+    match kind {
+        PatternKind::DiagonalStripes => 45.0,
+        _ => 0.0,
+    }
+}
+
+/// The tile a pattern repeats. Tiles wrap seamlessly at their edges.
+pub fn tile(kind: PatternKind) -> Tile {
+    let rect = |x: f32, y: f32, width: f32, height: f32| TileShape::Rect {
+        x,
+        y,
+        width,
+        height,
+    };
+
+    match kind {
+        PatternKind::Dots => Tile::Shapes(vec![TileShape::Circle {
+            cx: 0.5,
+            cy: 0.5,
+            radius: 0.05,
+        }]),
+        // One line along the top and one down the left, meeting without overlap
+        PatternKind::Grid => {
+            let line = 1.0 / base_size(kind);
+            Tile::Shapes(vec![rect(0.0, 0.0, 1.0, line), rect(0.0, line, line, 1.0 - line)])
+        }
+        PatternKind::DiagonalStripes => Tile::Shapes(vec![rect(0.0, 0.0, 1.0, 0.5)]),
+        PatternKind::Checkerboard => {
+            Tile::Shapes(vec![rect(0.0, 0.0, 0.5, 0.5), rect(0.5, 0.5, 0.5, 0.5)])
+        }
+        PatternKind::Waves => Tile::Shapes(vec![TileShape::Polygon(wave(0.12, 0.08))]),
+        PatternKind::Topographic => Tile::Mask(Arc::new(topographic_mask())),
+        PatternKind::Grain => Tile::Mask(Arc::new(grain_mask())),
+    }
+}
+
+/// A band following one period of a sine wave across the tile
+fn wave(amplitude: f32, thickness: f32) -> Vec<(f32, f32)> {
+    let center = |x: f32| 0.5 + amplitude * (x * TAU).sin();
+    let xs = (0..=WAVE_SEGMENTS).map(|i| i as f32 / WAVE_SEGMENTS as f32);
+
+    xs.clone()
+        .map(|x| (x, center(x) - thickness / 2.0))
+        .chain(xs.rev().map(|x| (x, center(x) + thickness / 2.0)))
+        .collect()
+}
+
+/// Anti-aliased contour lines of tileable noise
+fn topographic_mask() -> GrayImage {
+    let size = TOPOGRAPHIC_MASK_SIZE;
+    let step = 1.0 / size as f32;
+    let height = |x: u32, y: u32| fractal_noise(x as f32 * step, y as f32 * step) * TOPOGRAPHIC_LEVELS;
+
+    GrayImage::from_fn(size, size, |x, y| {
+        let value = height(x, y);
+        // How fast the height changes per pixel turns level distances into pixel distances
+        let dx = height((x + 1) % size, y) - value;
+        let dy = height(x, (y + 1) % size) - value;
+        let slope = dx.hypot(dy).max(f32::EPSILON);
+
+        let distance = (value - value.round()).abs() / slope;
+        Luma([((1.0 - distance).clamp(0.0, 1.0) * 255.0) as u8])
+    })
+}
+
+/// Sparse random speckles
+fn grain_mask() -> GrayImage {
+    GrayImage::from_fn(GRAIN_MASK_SIZE, GRAIN_MASK_SIZE, |x, y| {
+        Luma([(hash(x, y, 7).powi(3) * 255.0) as u8])
+    })
+}
+
+/// Smooth noise from 0.0 to 1.0 with detail at a few scales, repeating every unit
+fn fractal_noise(u: f32, v: f32) -> f32 {
+    0.6 * value_noise(u, v, 4, 1) + 0.3 * value_noise(u, v, 8, 2) + 0.1 * value_noise(u, v, 16, 3)
+}
+
+/// Interpolates random values on a `cells` by `cells` lattice that wraps around
+fn value_noise(u: f32, v: f32, cells: u32, seed: u32) -> f32 {
+    let (x, y) = (u * cells as f32, v * cells as f32);
+    let (x0, y0) = (x.floor(), y.floor());
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (fx, fy) = (smooth(x - x0), smooth(y - y0));
+    let corner = |dx: u32, dy: u32| {
+        hash(
+            (x0 as u32 + dx) % cells,
+            (y0 as u32 + dy) % cells,
+            seed,
+        )
+    };
+
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * fx;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * fx;
+    top + (bottom - top) * fy
+}
+
+/// A repeatable pseudo-random value from 0.0 to 1.0 for a lattice point
+fn hash(x: u32, y: u32, seed: u32) -> f32 {
+    let mut h = x
+        .wrapping_mul(0x8da6_b343)
+        ^ y.wrapping_mul(0xd816_3841)
+        ^ seed.wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x2c1b_3c6d);
+    h ^= h >> 12;
+    h = h.wrapping_mul(0x297a_2d39);
+    h ^= h >> 15;
+
+    h as f32 / u32::MAX as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_noise_tiles_wrap_seamlessly() {
+        for (u, v) in [(0.0, 0.3), (0.7, 0.0), (0.0, 0.0)] {
+            let wrapped = fractal_noise(u + 1.0 - f32::EPSILON, v);
+            assert!((fractal_noise(u, v) - wrapped).abs() < 0.01);
+        }
+
+        let mask = topographic_mask();
+        let contour = mask.pixels().filter(|pixel| pixel[0] > 128).count();
+        let total = (TOPOGRAPHIC_MASK_SIZE * TOPOGRAPHIC_MASK_SIZE) as usize;
+        assert!(contour > total / 50 && contour < total / 2, "{} of {}", contour, total);
+    }
+
+    #[test]
+    fn test_shape_tiles_stay_inside_the_tile() {
+        for kind in [
+            PatternKind::Dots,
+            PatternKind::Grid,
+            PatternKind::DiagonalStripes,
+            PatternKind::Checkerboard,
+            PatternKind::Waves,
+        ] {
+            let Tile::Shapes(shapes) = tile(kind) else {
+                panic!("{:?} should be drawn with shapes", kind);
+            };
+            let points: Vec<(f32, f32)> = shapes
+                .iter()
+                .flat_map(|shape| match shape {
+                    TileShape::Rect {
+                        x,
+                        y,
+                        width,
+                        height,
+                    } => vec![(*x, *y), (x + width, y + height)],
+                    TileShape::Circle { cx, cy, radius } => {
+                        vec![(cx - radius, cy - radius), (cx + radius, cy + radius)]
+                    }
+                    TileShape::Polygon(points) => points.clone(),
+                })
+                .collect();
+
+            assert!(
+                points.iter().all(|&(x, y)| (0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y)),
+                "{:?}",
+                kind
+            );
+        }
+
+        // Half of a checkerboard is covered
+        let Tile::Shapes(squares) = tile(PatternKind::Checkerboard) else {
+            unreachable!()
+        };
+        assert!(squares[0].contains(0.25, 0.25) && squares[1].contains(0.75, 0.75));
+        assert!(!squares.iter().any(|shape| shape.contains(0.75, 0.25)));
+    }
+}
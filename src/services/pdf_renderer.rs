@@ -0,0 +1,1402 @@
+use crate::models::errors::AppError;
+use crate::services::font_manager::{
+    FontManager, LoadedFont, SYNTHETIC_BOLD_EMBOLDEN, SYNTHETIC_ITALIC_SKEW,
+};
+use crate::services::font_subset::subset_to_sfnt;
+use crate::services::scene::{
+    CircleNode, ConicGradient, GradientStop, GroupNode, ImageNode, LineNode, LinearGradient,
+    Paint, RadialGradient, RectNode, Scene, SceneNode, ShadowNode, TextRun, Tile, TilePattern,
+    TileShape,
+};
+use image::{Rgba, RgbaImage};
+use pdf_writer::types::{
+    CidFontType, ColorSpaceOperand, FontFlags, FunctionShadingType, LineJoinStyle, PaintType,
+    SystemInfo, TextRenderingMode, TilingType, UnicodeCmap,
+};
+use pdf_writer::{Content, Filter, Name, Pdf, Rect, Ref, Str, TextStr};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+/// Control point distance for approximating a quarter circle with a cubic Bézier curve
+const KAPPA: f32 = 0.552_284_8;
+
+/// Renders scenes to PDF documents with selectable text and embedded font subsets
+pub struct PdfRenderer {
+    font_manager: Arc<FontManager>,
+}
+
+/// State accumulated while writing a single PDF document
+struct PdfWriter<'a> {
+    font_manager: &'a FontManager,
+    pdf: Pdf,
+    next_ref: Ref,
+    fonts: Vec<PdfFont>,
+    font_indices: HashMap<String, FontUse>,
+    alpha_states: BTreeMap<u8, Ref>,
+    shadings: Vec<Ref>,
+    patterns: Vec<Ref>,
+    x_objects: Vec<Ref>,
+}
+
+/// A font used in the document together with the glyphs drawn with it
+/// and the text each glyph stands for
+struct PdfFont {
+    loaded: LoadedFont,
+    font_ref: Ref,
+    glyphs: BTreeMap<u16, String>,
+}
+
+/// The document font a run's face maps to and the styles it has to synthesize
+#[derive(Clone, Copy)]
+struct FontUse {
+    index: usize,
+    synthetic_bold: bool,
+    synthetic_italic: bool,
+}
+
+/// A piece of a positioned text operation
+enum TextItem {
+    Glyphs(Vec<u8>),
+    Adjust(f32),
+}
+
+impl PdfRenderer {
+    /// Creates a new PdfRenderer that resolves fonts through the given manager
+    pub fn new(font_manager: Arc<FontManager>) -> Self {
+        PdfRenderer { font_manager }
+    }
+
+    /// Renders each scene as one page, converting scene pixels with `points_per_pixel`
+    pub fn render(&self, pages: &[Scene], points_per_pixel: f32) -> Result<Vec<u8>, AppError> {
+        if pages.is_empty() {
+            return Err(AppError::image_generation_failed(
+                "A PDF needs at least one page",
+            ));
+        }
+
+        let mut writer = PdfWriter {
+            font_manager: &self.font_manager,
+            pdf: Pdf::new(),
+            next_ref: Ref::new(1),
+            fonts: Vec::new(),
+            font_indices: HashMap::new(),
+            alpha_states: BTreeMap::new(),
+            shadings: Vec::new(),
+            patterns: Vec::new(),
+            x_objects: Vec::new(),
+        };
+
+        let catalog_id = writer.alloc();
+        let page_tree_id = writer.alloc();
+
+        let mut page_ids = Vec::new();
+        for scene in pages {
+            let content = writer.page_content(scene, points_per_pixel)?;
+            let content_id = writer.alloc();
+            writer
+                .pdf
+                .stream(content_id, &deflate(&content))
+                .filter(Filter::FlateDecode);
+
+            let width = scene.width as f32 * points_per_pixel;
+            let height = scene.height as f32 * points_per_pixel;
+            page_ids.push((writer.alloc(), content_id, width, height));
+        }
+
+        writer.write_fonts()?;
+        writer.write_alpha_states();
+
+        for &(page_id, content_id, width, height) in &page_ids {
+            let mut page = writer.pdf.page(page_id);
+            page.media_box(Rect::new(0.0, 0.0, width, height))
+                .parent(page_tree_id)
+                .contents(content_id);
+            write_resources(
+                page.resources(),
+                &writer.fonts,
+                &writer.alpha_states,
+                &writer.shadings,
+                &writer.patterns,
+                &writer.x_objects,
+            );
+        }
+
+        writer
+            .pdf
+            .pages(page_tree_id)
+            .kids(page_ids.iter().map(|(id, ..)| *id))
+            .count(page_ids.len() as i32);
+        writer.pdf.catalog(catalog_id).pages(page_tree_id);
+
+        let info_id = writer.alloc();
+        writer
+            .pdf
+            .document_info(info_id)
+            .producer(TextStr("Code Snippet Designer"));
+
+        Ok(writer.pdf.finish())
+    }
+}
+
+impl PdfWriter<'_> {
+    fn alloc(&mut self) -> Ref {
+        self.next_ref.bump()
+    }
+
+    /// Writes the drawing operations of one page, flipping to the scene's y-down pixel space
+    fn page_content(&mut self, scene: &Scene, points_per_pixel: f32) -> Result<Vec<u8>, AppError> {
+        let mut content = Content::new();
+        content.transform([
+            points_per_pixel,
+            0.0,
+            0.0,
+            -points_per_pixel,
+            0.0,
+            scene.height as f32 * points_per_pixel,
+        ]);
+
+        let page_matrix = PageMatrix {
+            scale: points_per_pixel,
+            height: scene.height as f32 * points_per_pixel,
+        };
+
+        for node in &scene.nodes {
+            self.write_node(&mut content, node, &page_matrix)?;
+        }
+
+        Ok(content.finish())
+    }
+
+    fn write_node(
+        &mut self,
+        content: &mut Content,
+        node: &SceneNode,
+        page_matrix: &PageMatrix,
+    ) -> Result<(), AppError> {
+        match node {
+            SceneNode::Rect(rect) => self.write_rect(content, rect, page_matrix),
+            SceneNode::Circle(circle) => self.write_circle(content, circle),
+            SceneNode::Line(line) => self.write_line(content, line),
+            SceneNode::Text(run) => self.write_text(content, run),
+            SceneNode::Group(group) => return self.write_group(content, group, page_matrix),
+            SceneNode::Shadow(shadow) => self.write_shadow(content, shadow),
+            SceneNode::Image(image) => self.write_image(content, image),
+        }
+
+        Ok(())
+    }
+
+    fn write_rect(&mut self, content: &mut Content, rect: &RectNode, page_matrix: &PageMatrix) {
+        match &rect.fill {
+            Some(Paint::Solid(color)) => {
+                content.save_state();
+                self.set_fill(content, *color);
+                rounded_rect_path(content, rect.x, rect.y, rect.width, rect.height, rect.radius);
+                content.fill_nonzero();
+                content.restore_state();
+            }
+            Some(Paint::LinearGradient(gradient)) => {
+                self.write_gradient_fill(content, rect, gradient);
+            }
+            Some(Paint::RadialGradient(gradient)) => {
+                self.write_radial_fill(content, rect, gradient);
+            }
+            Some(Paint::ConicGradient(gradient)) => {
+                self.write_conic_fill(content, rect, gradient);
+            }
+            Some(Paint::Pattern(pattern)) => {
+                self.write_pattern_fill(content, rect, pattern, page_matrix);
+            }
+            None => {}
+        }
+
+        if let Some(stroke) = &rect.stroke {
+            content.save_state();
+            self.set_stroke(content, stroke.color);
+            content.set_line_width(stroke.width);
+            rounded_rect_path(content, rect.x, rect.y, rect.width, rect.height, rect.radius);
+            content.stroke();
+            content.restore_state();
+        }
+    }
+
+    /// Clips to the shape and paints an axial shading across it
+    fn write_gradient_fill(&mut self, content: &mut Content, rect: &RectNode, gradient: &LinearGradient) {
+        let (Some(first), Some(last)) = (gradient.stops.first(), gradient.stops.last()) else {
+            return;
+        };
+
+        // Move the axis ends to the outer stops so the function domain is always 0-1
+        let ((x1, y1), (x2, y2)) = gradient.line_for_box(rect.x, rect.y, rect.width, rect.height);
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let coords = vec![
+            lerp(x1, x2, first.offset),
+            lerp(y1, y2, first.offset),
+            lerp(x1, x2, last.offset),
+            lerp(y1, y2, last.offset),
+        ];
+
+        self.write_shading(content, rect, FunctionShadingType::Axial, coords, &gradient.stops);
+    }
+
+    /// Clips to the shape and paints a radial shading between circles at the outer stops
+    fn write_radial_fill(&mut self, content: &mut Content, rect: &RectNode, gradient: &RadialGradient) {
+        let (Some(first), Some(last)) = (gradient.stops.first(), gradient.stops.last()) else {
+            return;
+        };
+
+        let (cx, cy, radius) = gradient.circle_for_box(rect.x, rect.y, rect.width, rect.height);
+        let coords = vec![cx, cy, radius * first.offset, cx, cy, radius * last.offset];
+
+        self.write_shading(content, rect, FunctionShadingType::Radial, coords, &gradient.stops);
+    }
+
+    /// Paints a shading clipped to the shape, mapping the outer stops to the ends of `coords`
+    fn write_shading(
+        &mut self,
+        content: &mut Content,
+        rect: &RectNode,
+        shading_type: FunctionShadingType,
+        coords: Vec<f32>,
+        stops: &[GradientStop],
+    ) {
+        let (Some(first), Some(last)) = (stops.first(), stops.last()) else {
+            return;
+        };
+        let span = (last.offset - first.offset).max(f32::EPSILON);
+
+        let segments: Vec<Ref> = stops
+            .windows(2)
+            .map(|pair| {
+                let id = self.alloc();
+                self.pdf
+                    .exponential_function(id)
+                    .domain([0.0, 1.0])
+                    .c0(rgb(pair[0].color))
+                    .c1(rgb(pair[1].color))
+                    .n(1.0);
+                id
+            })
+            .collect();
+
+        let function_id = match segments.as_slice() {
+            [] => {
+                let id = self.alloc();
+                self.pdf
+                    .exponential_function(id)
+                    .domain([0.0, 1.0])
+                    .c0(rgb(first.color))
+                    .c1(rgb(first.color))
+                    .n(1.0);
+                id
+            }
+            [single] => *single,
+            _ => {
+                let id = self.alloc();
+                let bounds = stops[1..stops.len() - 1]
+                    .iter()
+                    .map(|stop| (stop.offset - first.offset) / span);
+                let encode = segments.iter().flat_map(|_| [0.0, 1.0]);
+                self.pdf
+                    .stitching_function(id)
+                    .domain([0.0, 1.0])
+                    .functions(segments.iter().copied())
+                    .bounds(bounds)
+                    .encode(encode);
+                id
+            }
+        };
+
+        let shading_id = self.alloc();
+        self.pdf
+            .function_shading(shading_id)
+            .shading_type(shading_type)
+            .coords(coords)
+            .function(function_id)
+            .extend([true, true])
+            .color_space()
+            .device_rgb();
+        self.shadings.push(shading_id);
+        let name = format!("Sh{}", self.shadings.len());
+
+        content.save_state();
+        rounded_rect_path(content, rect.x, rect.y, rect.width, rect.height, rect.radius);
+        content.clip_nonzero().end_path();
+        // Shadings have no alpha of their own, so the stops' shared alpha applies to all
+        self.set_alpha(content, first.color[3]);
+        content.shading(Name(name.as_bytes()));
+        content.restore_state();
+    }
+
+    /// PDF has no conic shadings, so the sweep is drawn as thin opaque wedges in a
+    /// transparency group that takes the stops' shared alpha as a whole
+    fn write_conic_fill(&mut self, content: &mut Content, rect: &RectNode, gradient: &ConicGradient) {
+        let Some(first) = gradient.stops.first() else {
+            return;
+        };
+
+        let mut wedges = Content::new();
+        for wedge in gradient.wedges(rect.x, rect.y, rect.width, rect.height) {
+            let [r, g, b] = rgb(wedge.color);
+            let [(x1, y1), (x2, y2), (x3, y3)] = wedge.points;
+            wedges.set_fill_rgb(r, g, b);
+            wedges.move_to(x1, y1);
+            wedges.line_to(x2, y2);
+            wedges.line_to(x3, y3);
+            wedges.close_path();
+            wedges.fill_nonzero();
+        }
+        let wedges = deflate(&wedges.finish());
+
+        let form_id = self.alloc();
+        let mut form = self.pdf.form_xobject(form_id, &wedges);
+        form.filter(Filter::FlateDecode);
+        form.bbox(Rect::new(rect.x, rect.y, rect.x + rect.width, rect.y + rect.height));
+        form.group().transparency().isolated(true);
+        drop(form);
+
+        self.x_objects.push(form_id);
+        let name = format!("X{}", self.x_objects.len());
+
+        content.save_state();
+        rounded_rect_path(content, rect.x, rect.y, rect.width, rect.height, rect.radius);
+        content.clip_nonzero().end_path();
+        self.set_alpha(content, first.color[3]);
+        content.x_object(Name(name.as_bytes()));
+        content.restore_state();
+    }
+
+    /// Fills the base color, then the tile as a tiling pattern anchored at the shape's corner
+    fn write_pattern_fill(
+        &mut self,
+        content: &mut Content,
+        rect: &RectNode,
+        pattern: &TilePattern,
+        page_matrix: &PageMatrix,
+    ) {
+        content.save_state();
+        self.set_fill(content, pattern.background);
+        rounded_rect_path(content, rect.x, rect.y, rect.width, rect.height, rect.radius);
+        content.fill_nonzero();
+        content.restore_state();
+
+        let size = pattern.size;
+        let mut tile = Content::new();
+        let mut tile_alpha = None;
+        let mut tile_image = None;
+        match &pattern.tile {
+            Tile::Shapes(shapes) => {
+                tile_alpha = (pattern.color[3] < 255).then(|| self.alpha_state(pattern.color[3]));
+                if tile_alpha.is_some() {
+                    tile.set_parameters(Name(b"GS"));
+                }
+                let [r, g, b] = rgb(pattern.color);
+                tile.set_fill_rgb(r, g, b);
+                for shape in shapes {
+                    match shape {
+                        TileShape::Rect {
+                            x,
+                            y,
+                            width,
+                            height,
+                        } => {
+                            tile.rect(x * size, y * size, width * size, height * size);
+                        }
+                        TileShape::Circle { cx, cy, radius } => {
+                            let (left, top, diameter) = ((cx - radius) * size, (cy - radius) * size, radius * 2.0 * size);
+                            rounded_rect_path(&mut tile, left, top, diameter, diameter, radius * size);
+                        }
+                        TileShape::Polygon(points) => {
+                            for (i, (x, y)) in points.iter().enumerate() {
+                                if i == 0 {
+                                    tile.move_to(x * size, y * size);
+                                } else {
+                                    tile.line_to(x * size, y * size);
+                                }
+                            }
+                            tile.close_path();
+                        }
+                    }
+                }
+                tile.fill_nonzero();
+            }
+            Tile::Mask(mask) => {
+                let color = pattern.color;
+                let colored = image::RgbaImage::from_fn(mask.width(), mask.height(), |x, y| {
+                    let alpha = mask.get_pixel(x, y)[0] as u32 * color[3] as u32 / 255;
+                    Rgba([color[0], color[1], color[2], alpha as u8])
+                });
+                tile_image = Some(self.image_x_object(&colored));
+                tile.transform([size, 0.0, 0.0, -size, 0.0, size]);
+                tile.x_object(Name(b"Im"));
+            }
+        }
+        let tile = tile.finish();
+
+        // Pattern space is relative to the page, not to the current transformation
+        let pattern_id = self.alloc();
+        let mut tiling = self.pdf.tiling_pattern(pattern_id, &tile);
+        tiling
+            .tiling_type(TilingType::ConstantSpacing)
+            .paint_type(PaintType::Colored)
+            .bbox(Rect::new(0.0, 0.0, size, size))
+            .x_step(size)
+            .y_step(size)
+            .matrix(page_matrix.rotated_at(rect.x, rect.y, pattern.rotation));
+        let mut resources = tiling.resources();
+        if let Some(state) = tile_alpha {
+            resources.ext_g_states().pair(Name(b"GS"), state);
+        }
+        if let Some(image) = tile_image {
+            resources.x_objects().pair(Name(b"Im"), image);
+        }
+        drop(resources);
+        drop(tiling);
+
+        self.patterns.push(pattern_id);
+        let name = format!("P{}", self.patterns.len());
+
+        content.save_state();
+        content.set_fill_color_space(ColorSpaceOperand::Pattern);
+        content.set_fill_pattern(std::iter::empty(), Name(name.as_bytes()));
+        rounded_rect_path(content, rect.x, rect.y, rect.width, rect.height, rect.radius);
+        content.fill_nonzero();
+        content.restore_state();
+    }
+
+    /// Draws the blurred shadow as an image of its color with the coverage as a soft mask
+    fn write_shadow(&mut self, content: &mut Content, shadow: &ShadowNode) {
+        let mask = shadow.mask();
+        if mask.width == 0 || mask.height == 0 {
+            return;
+        }
+
+        let alpha = shadow.color[3] as f32;
+        let mask_data: Vec<u8> = mask
+            .coverage
+            .iter()
+            .map(|coverage| (coverage * alpha).round() as u8)
+            .collect();
+        let color_data: Vec<u8> = [shadow.color[0], shadow.color[1], shadow.color[2]]
+            .repeat((mask.width * mask.height) as usize);
+        let (mask_data, color_data) = (deflate(&mask_data), deflate(&color_data));
+
+        let mask_id = self.alloc();
+        let mut soft_mask = self.pdf.image_xobject(mask_id, &mask_data);
+        soft_mask.filter(Filter::FlateDecode);
+        soft_mask
+            .width(mask.width as i32)
+            .height(mask.height as i32)
+            .bits_per_component(8)
+            .color_space()
+            .device_gray();
+        drop(soft_mask);
+
+        let image_id = self.alloc();
+        let mut image = self.pdf.image_xobject(image_id, &color_data);
+        image.filter(Filter::FlateDecode);
+        image
+            .width(mask.width as i32)
+            .height(mask.height as i32)
+            .bits_per_component(8)
+            .s_mask(mask_id)
+            .color_space()
+            .device_rgb();
+        drop(image);
+
+        self.x_objects.push(image_id);
+        let name = format!("X{}", self.x_objects.len());
+
+        // Image space is a unit square whose first row sits at the top in scene space
+        let (width, height) = (mask.width as f32, mask.height as f32);
+        content.save_state();
+        content.transform([width, 0.0, 0.0, -height, mask.x as f32, mask.y as f32 + height]);
+        content.x_object(Name(name.as_bytes()));
+        content.restore_state();
+    }
+
+    /// Draws a bitmap as an RGB image with its alpha channel as a soft mask
+    fn write_image(&mut self, content: &mut Content, node: &ImageNode) {
+        let (width, height) = node.image.dimensions();
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let image_id = self.image_x_object(&node.image);
+        self.x_objects.push(image_id);
+        let name = format!("X{}", self.x_objects.len());
+
+        content.save_state();
+        content.transform([node.width, 0.0, 0.0, -node.height, node.x, node.y + node.height]);
+        content.x_object(Name(name.as_bytes()));
+        content.restore_state();
+    }
+
+    fn write_circle(&mut self, content: &mut Content, circle: &CircleNode) {
+        let diameter = circle.radius * 2.0;
+
+        content.save_state();
+        self.set_fill(content, circle.color);
+        rounded_rect_path(
+            content,
+            circle.cx - circle.radius,
+            circle.cy - circle.radius,
+            diameter,
+            diameter,
+            circle.radius,
+        );
+        content.fill_nonzero();
+        content.restore_state();
+    }
+
+    fn write_line(&mut self, content: &mut Content, line: &LineNode) {
+        content.save_state();
+        self.set_stroke(content, line.color);
+        content.set_line_width(line.width);
+        content.move_to(line.x1, line.y1);
+        content.line_to(line.x2, line.y2);
+        content.stroke();
+        content.restore_state();
+    }
+
+    /// Writes a run as real text, nudging each glyph onto its scene position
+    fn write_text(&mut self, content: &mut Content, run: &TextRun) {
+        let Some(first) = run.glyphs.first() else {
+            return;
+        };
+
+        let font_use = self.font_index(run);
+        let font_index = font_use.index;
+        let font = &mut self.fonts[font_index];
+        let face = ttf_parser::Face::parse(&font.loaded.data, font.loaded.index).ok();
+        let units_per_em = face.as_ref().map_or(1000.0, |face| face.units_per_em() as f32);
+
+        let mut items = Vec::new();
+        let mut glyphs = Vec::new();
+        let mut pen_x = first.x;
+
+        for (i, glyph) in run.glyphs.iter().enumerate() {
+            if glyph.joined {
+                continue;
+            }
+            let glyph_id = glyph
+                .id
+                .or_else(|| face.as_ref()?.glyph_index(glyph.ch).map(|id| id.0))
+                .unwrap_or(0);
+
+            // Control characters without a glyph would show as .notdef boxes
+            if glyph_id == 0 && glyph.ch.is_whitespace() {
+                continue;
+            }
+
+            // Text space adjustments are in thousandths of an em, positive moving left
+            let offset = glyph.x - pen_x;
+            if offset.abs() > 0.001 {
+                if !glyphs.is_empty() {
+                    items.push(TextItem::Glyphs(std::mem::take(&mut glyphs)));
+                }
+                items.push(TextItem::Adjust(-offset * 1000.0 / run.font_size));
+            }
+
+            glyphs.extend_from_slice(&glyph_id.to_be_bytes());
+
+            // A ligature stands for its own character and the joined ones after it
+            font.glyphs.entry(glyph_id).or_insert_with(|| {
+                std::iter::once(glyph.ch)
+                    .chain(run.glyphs[i + 1..].iter().take_while(|next| next.joined).map(|next| next.ch))
+                    .collect()
+            });
+
+            let advance = face
+                .as_ref()
+                .and_then(|face| face.glyph_hor_advance(ttf_parser::GlyphId(glyph_id)))
+                .unwrap_or(0);
+            pen_x = glyph.x + advance as f32 * run.font_size / units_per_em;
+        }
+
+        if !glyphs.is_empty() {
+            items.push(TextItem::Glyphs(glyphs));
+        }
+
+        let name = format!("F{}", font_index + 1);
+        content.save_state();
+        self.set_fill(content, run.color);
+        content.begin_text();
+        content.set_font(Name(name.as_bytes()), run.font_size);
+
+        // Synthetic bold also strokes the outlines; synthetic italics shear the text space
+        if font_use.synthetic_bold {
+            self.set_stroke(content, run.color);
+            content.set_line_width(run.font_size * SYNTHETIC_BOLD_EMBOLDEN);
+            content.set_line_join(LineJoinStyle::RoundJoin);
+            content.set_text_rendering_mode(TextRenderingMode::FillStroke);
+        }
+        let skew = if font_use.synthetic_italic {
+            SYNTHETIC_ITALIC_SKEW
+        } else {
+            0.0
+        };
+
+        // Flip back to y-up so glyphs are not mirrored by the page transformation
+        content.set_text_matrix([1.0, 0.0, skew, -1.0, first.x, run.baseline]);
+
+        let mut shown = content.show_positioned();
+        let mut positioned = shown.items();
+        for item in &items {
+            match item {
+                TextItem::Glyphs(bytes) => positioned.show(Str(bytes)),
+                TextItem::Adjust(amount) => positioned.adjust(*amount),
+            };
+        }
+        drop(positioned);
+        drop(shown);
+
+        content.end_text();
+        content.restore_state();
+    }
+
+    fn write_group(
+        &mut self,
+        content: &mut Content,
+        group: &GroupNode,
+        page_matrix: &PageMatrix,
+    ) -> Result<(), AppError> {
+        content.save_state();
+
+        if let Some(clip) = &group.clip {
+            rounded_rect_path(content, clip.x, clip.y, clip.width, clip.height, clip.radius);
+            content.clip_nonzero().end_path();
+        }
+
+        for child in &group.children {
+            self.write_node(content, child, page_matrix)?;
+        }
+
+        content.restore_state();
+        Ok(())
+    }
+
+    /// Returns the document font for a run's face, loading it on first use.
+    /// Synthesized styles share the font of the face they are derived from.
+    fn font_index(&mut self, run: &TextRun) -> FontUse {
+        let key = run.face_key();
+        if let Some(&font_use) = self.font_indices.get(&key) {
+            return font_use;
+        }
+
+        let styled = self.font_manager.resolve_styled(&run.font_family, run.bold, run.italic);
+        let index = match self.fonts.iter().position(|font| font.loaded.same_face(&styled.loaded)) {
+            Some(index) => index,
+            None => {
+                let font_ref = self.alloc();
+                self.fonts.push(PdfFont {
+                    loaded: styled.loaded,
+                    font_ref,
+                    glyphs: BTreeMap::new(),
+                });
+                self.fonts.len() - 1
+            }
+        };
+
+        let font_use = FontUse {
+            index,
+            synthetic_bold: styled.synthetic_bold,
+            synthetic_italic: styled.synthetic_italic,
+        };
+        self.font_indices.insert(key, font_use);
+        font_use
+    }
+
+    /// Embeds every used font as a CID-keyed font whose character codes are glyph IDs
+    fn write_fonts(&mut self) -> Result<(), AppError> {
+        let fonts = std::mem::take(&mut self.fonts);
+
+        for font in &fonts {
+            let data = &font.loaded.data;
+            let face = ttf_parser::Face::parse(data, font.loaded.index).map_err(|e| {
+                AppError::image_generation_failed(format!("Failed to parse font: {}", e))
+            })?;
+
+            let glyph_ids: BTreeSet<u16> = font.glyphs.keys().copied().collect();
+            let subset = subset_to_sfnt(data, font.loaded.index, &glyph_ids)?;
+            let has_glyf = face.tables().glyf.is_some();
+
+            let units_per_em = face.units_per_em() as f32;
+            let to_pdf_units = |value: f32| value * 1000.0 / units_per_em;
+            let base_font = format!("{}+{}", subset_tag(font), postscript_name(&face));
+
+            let cid_ref = self.alloc();
+            let descriptor_ref = self.alloc();
+            let cmap_ref = self.alloc();
+            let file_ref = self.alloc();
+
+            self.pdf
+                .type0_font(font.font_ref)
+                .base_font(Name(base_font.as_bytes()))
+                .encoding_predefined(Name(b"Identity-H"))
+                .descendant_font(cid_ref)
+                .to_unicode(cmap_ref);
+
+            let system_info = SystemInfo {
+                registry: Str(b"Adobe"),
+                ordering: Str(b"Identity"),
+                supplement: 0,
+            };
+
+            let mut cid = self.pdf.cid_font(cid_ref);
+            cid.subtype(if has_glyf {
+                CidFontType::Type2
+            } else {
+                CidFontType::Type0
+            })
+            .base_font(Name(base_font.as_bytes()))
+            .system_info(system_info)
+            .font_descriptor(descriptor_ref)
+            .default_width(0.0);
+            if has_glyf {
+                cid.cid_to_gid_map_predefined(Name(b"Identity"));
+            }
+            let mut widths = cid.widths();
+            for &glyph_id in font.glyphs.keys() {
+                let advance = face
+                    .glyph_hor_advance(ttf_parser::GlyphId(glyph_id))
+                    .unwrap_or(0);
+                widths.consecutive(glyph_id, [to_pdf_units(advance as f32)]);
+            }
+            drop(widths);
+            drop(cid);
+
+            let bbox = face.global_bounding_box();
+            let mut flags = FontFlags::SYMBOLIC;
+            if face.is_monospaced() {
+                flags |= FontFlags::FIXED_PITCH;
+            }
+            if face.is_italic() {
+                flags |= FontFlags::ITALIC;
+            }
+
+            let mut descriptor = self.pdf.font_descriptor(descriptor_ref);
+            descriptor
+                .name(Name(base_font.as_bytes()))
+                .flags(flags)
+                .bbox(Rect::new(
+                    to_pdf_units(bbox.x_min as f32),
+                    to_pdf_units(bbox.y_min as f32),
+                    to_pdf_units(bbox.x_max as f32),
+                    to_pdf_units(bbox.y_max as f32),
+                ))
+                .italic_angle(face.italic_angle().unwrap_or(0.0))
+                .ascent(to_pdf_units(face.ascender() as f32))
+                .descent(to_pdf_units(face.descender() as f32))
+                .cap_height(to_pdf_units(
+                    face.capital_height().unwrap_or(face.ascender()) as f32,
+                ))
+                .stem_v(80.0);
+            if has_glyf {
+                descriptor.font_file2(file_ref);
+            } else {
+                descriptor.font_file3(file_ref);
+            }
+            drop(descriptor);
+
+            let compressed = deflate(&subset);
+            let mut stream = self.pdf.stream(file_ref, &compressed);
+            stream.filter(Filter::FlateDecode);
+            if has_glyf {
+                stream.pair(Name(b"Length1"), subset.len() as i32);
+            } else {
+                stream.pair(Name(b"Subtype"), Name(b"OpenType"));
+            }
+            drop(stream);
+
+            // Maps glyph IDs back to characters so the text can be copied and searched
+            let mut cmap = UnicodeCmap::new(Name(b"Custom"), system_info);
+            for (&glyph_id, text) in &font.glyphs {
+                if glyph_id != 0 {
+                    cmap.pair_with_multiple(glyph_id, text.chars());
+                }
+            }
+            self.pdf.cmap(cmap_ref, &cmap.finish());
+        }
+
+        self.fonts = fonts;
+        Ok(())
+    }
+
+    /// Writes one graphics state per distinct alpha value used in the document
+    fn write_alpha_states(&mut self) {
+        for (&alpha, &state) in &self.alpha_states {
+            let alpha = alpha as f32 / 255.0;
+            self.pdf
+                .ext_graphics(state)
+                .non_stroking_alpha(alpha)
+                .stroking_alpha(alpha);
+        }
+    }
+
+    /// Embeds a bitmap as an RGB image with its alpha as a soft mask
+    fn image_x_object(&mut self, image: &RgbaImage) -> Ref {
+        let (width, height) = image.dimensions();
+        let pixels = image.pixels();
+        let mask_data = deflate(&pixels.clone().map(|pixel| pixel[3]).collect::<Vec<u8>>());
+        let color_data = deflate(
+            &pixels
+                .flat_map(|pixel| [pixel[0], pixel[1], pixel[2]])
+                .collect::<Vec<u8>>(),
+        );
+
+        let mask_id = self.alloc();
+        let mut soft_mask = self.pdf.image_xobject(mask_id, &mask_data);
+        soft_mask.filter(Filter::FlateDecode);
+        soft_mask
+            .width(width as i32)
+            .height(height as i32)
+            .bits_per_component(8)
+            .color_space()
+            .device_gray();
+        drop(soft_mask);
+
+        let image_id = self.alloc();
+        let mut image = self.pdf.image_xobject(image_id, &color_data);
+        image.filter(Filter::FlateDecode);
+        image
+            .width(width as i32)
+            .height(height as i32)
+            .bits_per_component(8)
+            .s_mask(mask_id)
+            .color_space()
+            .device_rgb();
+        drop(image);
+
+        image_id
+    }
+
+    fn set_fill(&mut self, content: &mut Content, color: Rgba<u8>) {
+        self.set_alpha(content, color[3]);
+        let [r, g, b] = rgb(color);
+        content.set_fill_rgb(r, g, b);
+    }
+
+    fn set_stroke(&mut self, content: &mut Content, color: Rgba<u8>) {
+        self.set_alpha(content, color[3]);
+        let [r, g, b] = rgb(color);
+        content.set_stroke_rgb(r, g, b);
+    }
+
+    /// Applies a constant alpha to the current graphics state; opaque needs no state
+    fn set_alpha(&mut self, content: &mut Content, alpha: u8) {
+        if alpha < 255 {
+            self.alpha_state(alpha);
+            let name = format!("GS{}", alpha);
+            content.set_parameters(Name(name.as_bytes()));
+        }
+    }
+
+    fn alpha_state(&mut self, alpha: u8) -> Ref {
+        if let Some(&state) = self.alpha_states.get(&alpha) {
+            return state;
+        }
+
+        let state = self.alloc();
+        self.alpha_states.insert(alpha, state);
+        state
+    }
+}
+
+/// Maps scene pixels to the page's default coordinate space
+struct PageMatrix {
+    scale: f32,
+    height: f32,
+}
+
+impl PageMatrix {
+    /// Returns the page transformation translated to a scene position and rotated clockwise
+    /// around it
+    fn rotated_at(&self, x: f32, y: f32, degrees: f32) -> [f32; 6] {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let [a, _, _, d, e, f] = self.at(x, y);
+        [a * cos, d * sin, -a * sin, d * cos, e, f]
+    }
+
+    /// Returns the page transformation translated to a scene position
+    fn at(&self, x: f32, y: f32) -> [f32; 6] {
+        [
+            self.scale,
+            0.0,
+            0.0,
+            -self.scale,
+            x * self.scale,
+            self.height - y * self.scale,
+        ]
+    }
+}
+
+/// Lists every document resource on a page's resource dictionary
+fn write_resources(
+    mut resources: pdf_writer::writers::Resources,
+    fonts: &[PdfFont],
+    alpha_states: &BTreeMap<u8, Ref>,
+    shadings: &[Ref],
+    patterns: &[Ref],
+    x_objects: &[Ref],
+) {
+    let mut font_dict = resources.fonts();
+    for (index, font) in fonts.iter().enumerate() {
+        let name = format!("F{}", index + 1);
+        font_dict.pair(Name(name.as_bytes()), font.font_ref);
+    }
+    drop(font_dict);
+
+    let mut state_dict = resources.ext_g_states();
+    for (alpha, state) in alpha_states {
+        let name = format!("GS{}", alpha);
+        state_dict.pair(Name(name.as_bytes()), *state);
+    }
+    drop(state_dict);
+
+    let mut shading_dict = resources.shadings();
+    for (index, shading) in shadings.iter().enumerate() {
+        let name = format!("Sh{}", index + 1);
+        shading_dict.pair(Name(name.as_bytes()), *shading);
+    }
+    drop(shading_dict);
+
+    let mut pattern_dict = resources.patterns();
+    for (index, pattern) in patterns.iter().enumerate() {
+        let name = format!("P{}", index + 1);
+        pattern_dict.pair(Name(name.as_bytes()), *pattern);
+    }
+    drop(pattern_dict);
+
+    let mut x_object_dict = resources.x_objects();
+    for (index, x_object) in x_objects.iter().enumerate() {
+        let name = format!("X{}", index + 1);
+        x_object_dict.pair(Name(name.as_bytes()), *x_object);
+    }
+}
+
+/// Appends a rectangle with circular corners of `radius` to the current path
+fn rounded_rect_path(content: &mut Content, x: f32, y: f32, width: f32, height: f32, radius: f32) {
+    let radius = radius.min(width / 2.0).min(height / 2.0).max(0.0);
+    if radius == 0.0 {
+        content.rect(x, y, width, height);
+        return;
+    }
+
+    let (right, bottom) = (x + width, y + height);
+    let k = radius * (1.0 - KAPPA);
+
+    content.move_to(x + radius, y);
+    content.line_to(right - radius, y);
+    content.cubic_to(right - k, y, right, y + k, right, y + radius);
+    content.line_to(right, bottom - radius);
+    content.cubic_to(right, bottom - k, right - k, bottom, right - radius, bottom);
+    content.line_to(x + radius, bottom);
+    content.cubic_to(x + k, bottom, x, bottom - k, x, bottom - radius);
+    content.line_to(x, y + radius);
+    content.cubic_to(x, y + k, x + k, y, x + radius, y);
+    content.close_path();
+}
+
+/// Six uppercase letters identifying a subset, as required for subset font names
+fn subset_tag(font: &PdfFont) -> String {
+    // FNV-1a over the glyph set keeps the tag stable for identical subsets
+    let hash = font
+        .glyphs
+        .keys()
+        .flat_map(|glyph_id| glyph_id.to_be_bytes())
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+
+    (0..6)
+        .map(|i| (b'A' + ((hash >> (i * 8)) % 26) as u8) as char)
+        .collect()
+}
+
+/// Returns the font's PostScript name, limited to characters allowed in PDF names
+fn postscript_name(face: &ttf_parser::Face) -> String {
+    let name: String = face
+        .names()
+        .into_iter()
+        .filter(|name| name.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+        .find_map(|name| name.to_string())
+        .unwrap_or_default()
+        .chars()
+        .filter(|ch| ch.is_ascii_alphanumeric() || *ch == '-')
+        .collect();
+
+    if name.is_empty() {
+        "SnippetFont".to_string()
+    } else {
+        name
+    }
+}
+
+fn rgb(color: Rgba<u8>) -> [f32; 3] {
+    [
+        color[0] as f32 / 255.0,
+        color[1] as f32 / 255.0,
+        color[2] as f32 / 255.0,
+    ]
+}
+
+fn deflate(data: &[u8]) -> Vec<u8> {
+    miniz_oxide::deflate::compress_to_vec_zlib(data, 6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::scene::{ClipRect, GradientStop, SceneGlyph};
+
+    fn text_scene(text: &str) -> Scene {
+        let glyphs = text
+            .chars()
+            .enumerate()
+            .map(|(i, ch)| SceneGlyph {
+                ch,
+                x: 20.0 + i as f32 * 8.4,
+                id: None,
+                joined: false,
+            })
+            .collect();
+
+        Scene {
+            width: 400,
+            height: 200,
+            nodes: vec![SceneNode::Group(GroupNode {
+                clip: Some(ClipRect {
+                    x: 0.0,
+                    y: 0.0,
+                    width: 400.0,
+                    height: 200.0,
+                    radius: 12.0,
+                }),
+                children: vec![
+                    SceneNode::Rect(RectNode {
+                        fill: Some(Paint::LinearGradient(LinearGradient {
+                            angle: 180.0,
+                            stops: vec![
+                                GradientStop {
+                                    offset: 0.0,
+                                    color: Rgba([30, 30, 30, 255]),
+                                },
+                                GradientStop {
+                                    offset: 1.0,
+                                    color: Rgba([60, 60, 60, 255]),
+                                },
+                            ],
+                        })),
+                        ..RectNode::filled(0.0, 0.0, 400.0, 200.0, Rgba([0, 0, 0, 255]))
+                    }),
+                    SceneNode::Text(TextRun {
+                        font_family: "monospace".to_string(),
+                        font_size: 14.0,
+                        baseline: 40.0,
+                        color: Rgba([255, 255, 255, 255]),
+                        bold: false,
+                        italic: false,
+                        glyphs,
+                    }),
+                ],
+            })],
+        }
+    }
+
+    fn renderer() -> PdfRenderer {
+        PdfRenderer::new(Arc::new(FontManager::new().unwrap()))
+    }
+
+    #[test]
+    fn test_pdf_text_is_searchable() {
+        let pdf = renderer().render(&[text_scene("let total = 42;")], 0.75).unwrap();
+        assert!(pdf.starts_with(b"%PDF-"));
+
+        let text = pdf_extract::extract_text_from_mem(&pdf).unwrap();
+        assert!(text.contains("let total = 42;"), "extracted: {:?}", text);
+    }
+
+    #[test]
+    fn test_pdf_embeds_font_subset() {
+        let pdf = renderer().render(&[text_scene("abc")], 0.75).unwrap();
+        let document = pdf_extract::Document::load_mem(&pdf).unwrap();
+
+        let font_file = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_dict().ok())
+            .find_map(|dict| dict.get(b"FontFile2").ok())
+            .and_then(|object| object.as_reference().ok())
+            .and_then(|id| document.get_object(id).ok())
+            .and_then(|object| object.as_stream().ok())
+            .map(|stream| stream.decompressed_content().unwrap())
+            .expect("font file is embedded");
+
+        // Only the three glyphs (plus .notdef) keep their outlines
+        let face = ttf_parser::Face::parse(&font_file, 0).unwrap();
+        assert!(face.glyph_bounding_box(face.glyph_index('b').unwrap()).is_some());
+        assert!(face.glyph_bounding_box(face.glyph_index('z').unwrap()).is_none());
+
+        let original = FontManager::new().unwrap().resolve_loaded("monospace");
+        assert!(font_file.len() < original.data.len() / 2);
+    }
+
+    #[test]
+    fn test_pages_are_sized_from_scene() {
+        let pages = [text_scene("one"), text_scene("two")];
+        let pdf = renderer().render(&pages, 0.75).unwrap();
+        let document = pdf_extract::Document::load_mem(&pdf).unwrap();
+
+        assert_eq!(document.get_pages().len(), 2);
+        for page_id in document.get_pages().values() {
+            let page = document.get_dictionary(*page_id).unwrap();
+            let media_box: Vec<f32> = page
+                .get(b"MediaBox")
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|value| value.as_float().unwrap())
+                .collect();
+            assert_eq!(media_box, vec![0.0, 0.0, 300.0, 150.0]);
+        }
+    }
+
+    #[test]
+    fn test_shadow_is_drawn_as_soft_masked_image() {
+        let mut scene = text_scene("shadow");
+        scene.nodes.insert(
+            0,
+            SceneNode::Shadow(ShadowNode {
+                x: 20.0,
+                y: 20.0,
+                width: 360.0,
+                height: 160.0,
+                radius: 12.0,
+                blur_radius: 16.0,
+                color: Rgba([0, 0, 0, 128]),
+            }),
+        );
+
+        let pdf = renderer().render(&[scene], 0.75).unwrap();
+        let document = pdf_extract::Document::load_mem(&pdf).unwrap();
+
+        let image = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_stream().ok())
+            .find(|stream| stream.dict.get(b"SMask").is_ok())
+            .expect("shadow image is embedded");
+        let mask = document
+            .get_object(image.dict.get(b"SMask").unwrap().as_reference().unwrap())
+            .and_then(|object| object.as_stream())
+            .unwrap();
+        // lopdf won't decode image streams, so inflate the mask directly
+        let mask = miniz_oxide::inflate::decompress_to_vec_zlib(&mask.content).unwrap();
+
+        // The mask spans the blurred bounds and peaks at the shadow color's alpha
+        let width = image.dict.get(b"Width").unwrap().as_i64().unwrap();
+        assert_eq!(width, 360 + 2 * 24);
+        assert_eq!(mask.iter().copied().max(), Some(128));
+        assert_eq!(mask[0], 0);
+    }
+
+    #[test]
+    fn test_synthetic_styles_stroke_and_shear_text() {
+        let mut scene = text_scene("bold");
+        let SceneNode::Group(ref mut group) = scene.nodes[0] else {
+            unreachable!()
+        };
+        let SceneNode::Text(ref mut run) = group.children[1] else {
+            unreachable!()
+        };
+        run.font_family = "Definitely Not A Real Font".to_string();
+        run.bold = true;
+        run.italic = true;
+
+        let pdf = renderer().render(&[scene], 0.75).unwrap();
+        let document = pdf_extract::Document::load_mem(&pdf).unwrap();
+        let page = *document.get_pages().values().next().unwrap();
+        let content = String::from_utf8_lossy(&document.get_page_content(page).unwrap()).into_owned();
+
+        assert!(content.contains("2 Tr"), "content: {}", content);
+        assert!(content.contains(&format!("1 0 {} -1", SYNTHETIC_ITALIC_SKEW)));
+
+        // The text stays searchable
+        let text = pdf_extract::extract_text_from_mem(&pdf).unwrap();
+        assert!(text.contains("bold"));
+    }
+
+    #[test]
+    fn test_shaped_glyphs_map_back_to_their_text() {
+        let mut scene = text_scene("cafe\u{301}!");
+        let SceneNode::Group(ref mut group) = scene.nodes[0] else {
+            unreachable!()
+        };
+        let SceneNode::Text(ref mut run) = group.children[1] else {
+            unreachable!()
+        };
+
+        // The shaper draws the accented letter as a single precomposed glyph
+        let loaded = FontManager::new().unwrap().resolve_loaded(&run.font_family);
+        let face = ttf_parser::Face::parse(&loaded.data, loaded.index).unwrap();
+        let composed = face.glyph_index('\u{e9}').unwrap().0;
+        run.glyphs[3].id = Some(composed);
+        run.glyphs[4].joined = true;
+        run.glyphs[5].x = run.glyphs[4].x;
+        run.glyphs[4].x -= 4.2;
+
+        let pdf = renderer().render(&[scene], 0.75).unwrap();
+        let text = pdf_extract::extract_text_from_mem(&pdf).unwrap();
+        assert!(text.contains("cafe\u{301}!"), "extracted: {:?}", text);
+    }
+
+    #[test]
+    fn test_images_keep_their_alpha_as_soft_mask() {
+        let mut bitmap = image::RgbaImage::new(2, 1);
+        bitmap.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        bitmap.put_pixel(1, 0, Rgba([0, 0, 255, 128]));
+        let mut scene = text_scene("emoji");
+        scene.nodes.push(SceneNode::Image(ImageNode {
+            x: 10.0,
+            y: 10.0,
+            width: 20.0,
+            height: 10.0,
+            image: Arc::new(bitmap),
+        }));
+
+        let pdf = renderer().render(&[scene], 0.75).unwrap();
+        let document = pdf_extract::Document::load_mem(&pdf).unwrap();
+
+        let image = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_stream().ok())
+            .find(|stream| stream.dict.get(b"SMask").is_ok())
+            .expect("image is embedded");
+        let mask = document
+            .get_object(image.dict.get(b"SMask").unwrap().as_reference().unwrap())
+            .and_then(|object| object.as_stream())
+            .unwrap();
+
+        let inflate = |data: &[u8]| miniz_oxide::inflate::decompress_to_vec_zlib(data).unwrap();
+        assert_eq!(inflate(&image.content), vec![255, 0, 0, 0, 0, 255]);
+        assert_eq!(inflate(&mask.content), vec![255, 128]);
+    }
+
+    #[test]
+    fn test_radial_and_conic_gradients() {
+        let stops = vec![
+            GradientStop {
+                offset: 0.0,
+                color: Rgba([255, 0, 0, 204]),
+            },
+            GradientStop {
+                offset: 1.0,
+                color: Rgba([0, 0, 255, 204]),
+            },
+        ];
+        let mut scene = text_scene("gradients");
+        scene.nodes.push(SceneNode::Rect(RectNode {
+            fill: Some(Paint::RadialGradient(RadialGradient {
+                center_x: 0.5,
+                center_y: 0.5,
+                stops: stops.clone(),
+            })),
+            ..RectNode::filled(0.0, 0.0, 200.0, 100.0, Rgba([0, 0, 0, 255]))
+        }));
+        scene.nodes.push(SceneNode::Rect(RectNode {
+            fill: Some(Paint::ConicGradient(ConicGradient {
+                angle: 0.0,
+                center_x: 0.5,
+                center_y: 0.5,
+                stops,
+            })),
+            ..RectNode::filled(200.0, 100.0, 200.0, 100.0, Rgba([0, 0, 0, 255]))
+        }));
+
+        let pdf = renderer().render(&[scene], 0.75).unwrap();
+        let document = pdf_extract::Document::load_mem(&pdf).unwrap();
+
+        let shading_types: Vec<i64> = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_dict().ok())
+            .filter_map(|dict| dict.get(b"ShadingType").ok()?.as_i64().ok())
+            .collect();
+        assert!(shading_types.contains(&2) && shading_types.contains(&3));
+
+        // The wedges form one transparency group, so their overlaps don't show
+        let form = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_stream().ok())
+            .find(|stream| stream.dict.get(b"Group").is_ok())
+            .expect("conic gradient is drawn as a form");
+        let wedges = miniz_oxide::inflate::decompress_to_vec_zlib(&form.content).unwrap();
+        let fills = wedges.split(|&byte| byte == b'\n').filter(|line| *line == b"f").count();
+        assert_eq!(fills, 360);
+    }
+
+    #[test]
+    fn test_patterns_tile_shapes_and_masks() {
+        let mut scene = text_scene("patterns");
+        let mask = image::GrayImage::from_fn(4, 4, |x, _| image::Luma([x as u8 * 80]));
+        for (tile, rotation) in [
+            (
+                Tile::Shapes(vec![TileShape::Circle {
+                    cx: 0.5,
+                    cy: 0.5,
+                    radius: 0.1,
+                }]),
+                0.0,
+            ),
+            (Tile::Mask(Arc::new(mask)), 90.0),
+        ] {
+            scene.nodes.push(SceneNode::Rect(RectNode {
+                fill: Some(Paint::Pattern(TilePattern {
+                    background: Rgba([20, 20, 20, 255]),
+                    color: Rgba([200, 200, 255, 128]),
+                    size: 16.0,
+                    rotation,
+                    tile,
+                })),
+                ..RectNode::filled(0.0, 0.0, 200.0, 100.0, Rgba([0, 0, 0, 255]))
+            }));
+        }
+
+        let pdf = renderer().render(&[scene], 0.75).unwrap();
+        let document = pdf_extract::Document::load_mem(&pdf).unwrap();
+        let patterns: Vec<&pdf_extract::Dictionary> = document
+            .objects
+            .values()
+            .filter_map(|object| object.as_stream().ok())
+            .map(|stream| &stream.dict)
+            .filter(|dict| dict.get(b"PatternType").is_ok())
+            .collect();
+        assert_eq!(patterns.len(), 2);
+
+        // The mask tile draws an embedded image and is turned a quarter
+        let masked = patterns
+            .iter()
+            .find(|dict| {
+                let resources = dict.get(b"Resources").and_then(|resources| resources.as_dict());
+                resources.is_ok_and(|resources| resources.get(b"XObject").is_ok())
+            })
+            .expect("mask tile references its image");
+        let matrix: Vec<f32> = masked
+            .get(b"Matrix")
+            .and_then(|matrix| matrix.as_array())
+            .unwrap()
+            .iter()
+            .map(|value| value.as_float().unwrap())
+            .collect();
+        assert!(matrix[0].abs() < 0.001 && (matrix[1] + 0.75).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_empty_document_is_rejected() {
+        assert!(renderer().render(&[], 0.75).is_err());
+    }
+}
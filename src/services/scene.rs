@@ -0,0 +1,731 @@
+use crate::services::blur::gaussian_blur;
+use image::{GrayImage, Luma, Rgba, RgbaImage};
+use std::sync::Arc;
+
+/// A renderer-independent description of a code snippet image.
+/// Both the raster and the SVG backends draw from the same scene.
+#[derive(Debug, Clone)]
+pub struct Scene {
+    pub width: u32,
+    pub height: u32,
+    pub nodes: Vec<SceneNode>,
+}
+
+/// A single drawable element of a scene, painted in order
+#[derive(Debug, Clone)]
+pub enum SceneNode {
+    Rect(RectNode),
+    Circle(CircleNode),
+    Line(LineNode),
+    Text(TextRun),
+    Group(GroupNode),
+    Shadow(ShadowNode),
+    Image(ImageNode),
+}
+
+/// An axis-aligned, optionally rounded rectangle
+#[derive(Debug, Clone)]
+pub struct RectNode {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub radius: f32,
+    pub fill: Option<Paint>,
+    pub stroke: Option<Stroke>,
+}
+
+/// How the inside of a shape is filled
+#[derive(Debug, Clone)]
+pub enum Paint {
+    Solid(Rgba<u8>),
+    LinearGradient(LinearGradient),
+    RadialGradient(RadialGradient),
+    ConicGradient(ConicGradient),
+    Pattern(TilePattern),
+}
+
+/// A linear gradient across the filled shape
+#[derive(Debug, Clone)]
+pub struct LinearGradient {
+    /// Direction in degrees using the CSS convention (180 = top to bottom)
+    pub angle: f32,
+    pub stops: Vec<GradientStop>,
+}
+
+/// Circles of color spreading from a center out to the farthest corner of the filled shape
+#[derive(Debug, Clone)]
+pub struct RadialGradient {
+    /// Center as fractions (0.0-1.0) of the shape's width and height
+    pub center_x: f32,
+    pub center_y: f32,
+    pub stops: Vec<GradientStop>,
+}
+
+/// Colors swept clockwise around a center of the filled shape
+#[derive(Debug, Clone)]
+pub struct ConicGradient {
+    /// Where the sweep starts, in degrees clockwise from the top
+    pub angle: f32,
+    /// Center as fractions (0.0-1.0) of the shape's width and height
+    pub center_x: f32,
+    pub center_y: f32,
+    pub stops: Vec<GradientStop>,
+}
+
+/// A triangle from a conic gradient's center, filled with the color of its middle
+#[derive(Debug, Clone, Copy)]
+pub struct ConicWedge {
+    pub points: [(f32, f32); 3],
+    pub color: Rgba<u8>,
+}
+
+/// A color at a position (0.0-1.0) along a gradient
+#[derive(Debug, Clone)]
+pub struct GradientStop {
+    pub offset: f32,
+    pub color: Rgba<u8>,
+}
+
+/// A square tile repeated over a base color, starting at the filled shape's top-left corner
+#[derive(Debug, Clone)]
+pub struct TilePattern {
+    pub background: Rgba<u8>,
+    pub color: Rgba<u8>,
+    /// Side of the tile
+    pub size: f32,
+    /// Degrees clockwise around the shape's top-left corner
+    pub rotation: f32,
+    pub tile: Tile,
+}
+
+/// What a pattern tile draws in its color, in tile units from 0.0 to 1.0
+#[derive(Debug, Clone)]
+pub enum Tile {
+    /// Non-overlapping filled shapes
+    Shapes(Vec<TileShape>),
+    /// Coverage stretched over the tile, for noise that has no simple outline
+    Mask(Arc<GrayImage>),
+}
+
+/// A filled shape inside a pattern tile
+#[derive(Debug, Clone)]
+pub enum TileShape {
+    Rect {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+    Circle {
+        cx: f32,
+        cy: f32,
+        radius: f32,
+    },
+    Polygon(Vec<(f32, f32)>),
+}
+
+/// Outline drawn centered on a shape's edge
+#[derive(Debug, Clone)]
+pub struct Stroke {
+    pub color: Rgba<u8>,
+    pub width: f32,
+}
+
+/// A filled circle
+#[derive(Debug, Clone)]
+pub struct CircleNode {
+    pub cx: f32,
+    pub cy: f32,
+    pub radius: f32,
+    pub color: Rgba<u8>,
+}
+
+/// A straight line segment with butt caps
+#[derive(Debug, Clone)]
+pub struct LineNode {
+    pub x1: f32,
+    pub y1: f32,
+    pub x2: f32,
+    pub y2: f32,
+    pub width: f32,
+    pub color: Rgba<u8>,
+}
+
+/// A run of same-styled text with every glyph already positioned
+#[derive(Debug, Clone)]
+pub struct TextRun {
+    pub font_family: String,
+    pub font_size: f32,
+    pub baseline: f32,
+    pub color: Rgba<u8>,
+    pub bold: bool,
+    pub italic: bool,
+    pub glyphs: Vec<SceneGlyph>,
+}
+
+/// A character placed at an absolute x position on the run's baseline
+#[derive(Debug, Clone, Copy)]
+pub struct SceneGlyph {
+    pub ch: char,
+    pub x: f32,
+    /// Glyph picked by text shaping; without one the font's glyph for `ch` is drawn
+    pub id: Option<u16>,
+    /// True when the preceding glyph already draws this character as part of a ligature
+    pub joined: bool,
+}
+
+/// A set of nodes that can share a clip region
+#[derive(Debug, Clone)]
+pub struct GroupNode {
+    pub clip: Option<ClipRect>,
+    pub children: Vec<SceneNode>,
+}
+
+/// A rounded rectangle blurred with a Gaussian, cast behind another shape
+#[derive(Debug, Clone)]
+pub struct ShadowNode {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub radius: f32,
+    /// Blur radius with CSS semantics: the Gaussian's standard deviation is half of it
+    pub blur_radius: f32,
+    pub color: Rgba<u8>,
+}
+
+/// A bitmap, such as a color emoji, stretched over a rectangle
+#[derive(Debug, Clone)]
+pub struct ImageNode {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub image: Arc<RgbaImage>,
+}
+
+/// Per-pixel shadow coverage (0.0-1.0) for a pixel-aligned region of the scene
+#[derive(Debug, Clone)]
+pub struct ShadowMask {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub coverage: Vec<f32>,
+}
+
+/// A rounded rectangle that clips a group's children
+#[derive(Debug, Clone)]
+pub struct ClipRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub radius: f32,
+}
+
+impl RectNode {
+    /// Creates a sharp-cornered rectangle filled with a solid color
+    pub fn filled(x: f32, y: f32, width: f32, height: f32, color: Rgba<u8>) -> Self {
+        RectNode {
+            x,
+            y,
+            width,
+            height,
+            radius: 0.0,
+            fill: Some(Paint::Solid(color)),
+            stroke: None,
+        }
+    }
+
+    /// Returns true if the point lies inside the (rounded) rectangle
+    pub fn contains(&self, px: f32, py: f32) -> bool {
+        rounded_rect_contains(self.x, self.y, self.width, self.height, self.radius, px, py)
+    }
+
+    /// Fraction (0.0-1.0) of the pixel centered on the point that the rectangle covers
+    pub fn coverage(&self, px: f32, py: f32) -> f32 {
+        rounded_rect_coverage(self.x, self.y, self.width, self.height, self.radius, px, py)
+    }
+}
+
+impl ClipRect {
+    /// Returns true if the point lies inside the clip region
+    pub fn contains(&self, px: f32, py: f32) -> bool {
+        rounded_rect_contains(self.x, self.y, self.width, self.height, self.radius, px, py)
+    }
+
+    /// Fraction (0.0-1.0) of the pixel centered on the point that the clip region covers
+    pub fn coverage(&self, px: f32, py: f32) -> f32 {
+        rounded_rect_coverage(self.x, self.y, self.width, self.height, self.radius, px, py)
+    }
+}
+
+impl ShadowNode {
+    /// Standard deviation of the shadow's Gaussian blur
+    pub fn sigma(&self) -> f32 {
+        self.blur_radius / 2.0
+    }
+
+    /// How far the blurred shadow reaches beyond its rectangle
+    pub fn extent(&self) -> f32 {
+        (self.sigma() * 3.0).ceil()
+    }
+
+    /// Rasterizes the rectangle and blurs it into a coverage mask over the shadow's bounds
+    pub fn mask(&self) -> ShadowMask {
+        let extent = self.extent();
+        let x = (self.x - extent).floor() as i32;
+        let y = (self.y - extent).floor() as i32;
+        let width = ((self.x + self.width + extent).ceil() as i32 - x).max(0) as u32;
+        let height = ((self.y + self.height + extent).ceil() as i32 - y).max(0) as u32;
+
+        let mut coverage = Vec::with_capacity((width * height) as usize);
+        for row in 0..height {
+            for column in 0..width {
+                let px = (x + column as i32) as f32 + 0.5;
+                let py = (y + row as i32) as f32 + 0.5;
+                coverage.push(rounded_rect_coverage(
+                    self.x, self.y, self.width, self.height, self.radius, px, py,
+                ));
+            }
+        }
+
+        gaussian_blur(&mut coverage, width as usize, height as usize, self.sigma());
+
+        ShadowMask {
+            x,
+            y,
+            width,
+            height,
+            coverage,
+        }
+    }
+}
+
+impl SceneNode {
+    /// Moves the node, including any children, by the given offset
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        match self {
+            SceneNode::Rect(rect) => {
+                rect.x += dx;
+                rect.y += dy;
+            }
+            SceneNode::Circle(circle) => {
+                circle.cx += dx;
+                circle.cy += dy;
+            }
+            SceneNode::Line(line) => {
+                line.x1 += dx;
+                line.y1 += dy;
+                line.x2 += dx;
+                line.y2 += dy;
+            }
+            SceneNode::Text(run) => {
+                run.baseline += dy;
+                for glyph in &mut run.glyphs {
+                    glyph.x += dx;
+                }
+            }
+            SceneNode::Group(group) => {
+                if let Some(ref mut clip) = group.clip {
+                    clip.x += dx;
+                    clip.y += dy;
+                }
+                for child in &mut group.children {
+                    child.translate(dx, dy);
+                }
+            }
+            SceneNode::Shadow(shadow) => {
+                shadow.x += dx;
+                shadow.y += dy;
+            }
+            SceneNode::Image(image) => {
+                image.x += dx;
+                image.y += dy;
+            }
+        }
+    }
+}
+
+impl TextRun {
+    /// Returns the characters of the run in order
+    pub fn text(&self) -> String {
+        self.glyphs.iter().map(|glyph| glyph.ch).collect()
+    }
+
+    /// Identifies the face the run is drawn with, for renderers that load each face once
+    pub fn face_key(&self) -> String {
+        format!("{}:{}:{}", self.font_family, self.bold, self.italic)
+    }
+}
+
+impl LinearGradient {
+    /// Returns the start and end points of the gradient line for a box, per the CSS definition
+    pub fn line_for_box(&self, x: f32, y: f32, width: f32, height: f32) -> ((f32, f32), (f32, f32)) {
+        let radians = self.angle.to_radians();
+        let (dir_x, dir_y) = (radians.sin(), -radians.cos());
+        let half_length = (width * dir_x.abs() + height * dir_y.abs()) / 2.0;
+        let (center_x, center_y) = (x + width / 2.0, y + height / 2.0);
+
+        (
+            (center_x - dir_x * half_length, center_y - dir_y * half_length),
+            (center_x + dir_x * half_length, center_y + dir_y * half_length),
+        )
+    }
+}
+
+impl RadialGradient {
+    /// Returns the center and radius of the gradient for a box
+    pub fn circle_for_box(&self, x: f32, y: f32, width: f32, height: f32) -> (f32, f32, f32) {
+        let (center_x, center_y) = (x + width * self.center_x, y + height * self.center_y);
+
+        (center_x, center_y, farthest_corner((center_x, center_y), x, y, width, height))
+    }
+}
+
+impl ConicGradient {
+    /// Wedges enough for a smooth sweep at export sizes
+    const WEDGES: usize = 360;
+
+    /// Returns the center of the gradient for a box
+    pub fn center_for_box(&self, x: f32, y: f32, width: f32, height: f32) -> (f32, f32) {
+        (x + width * self.center_x, y + height * self.center_y)
+    }
+
+    /// Position (0.0-1.0) of a point along the sweep
+    pub fn position(&self, center: (f32, f32), px: f32, py: f32) -> f32 {
+        let degrees = (px - center.0).atan2(center.1 - py).to_degrees();
+        (degrees - self.angle).rem_euclid(360.0) / 360.0
+    }
+
+    /// Approximates the gradient over a box with solid wedges, for formats that have no
+    /// conic gradients. Each wedge slightly overlaps the next to hide seams.
+    pub fn wedges(&self, x: f32, y: f32, width: f32, height: f32) -> Vec<ConicWedge> {
+        let center = self.center_for_box(x, y, width, height);
+        let reach = farthest_corner(center, x, y, width, height) + 1.0;
+        let point = |turn: f32| {
+            let radians = (self.angle + turn * 360.0).to_radians();
+            (center.0 + radians.sin() * reach, center.1 - radians.cos() * reach)
+        };
+
+        let step = 1.0 / Self::WEDGES as f32;
+        (0..Self::WEDGES)
+            .map(|i| {
+                let start = i as f32 * step;
+                let channels = sample_stops(&self.stops, start + step / 2.0);
+                ConicWedge {
+                    points: [center, point(start), point(start + step * 1.5)],
+                    color: Rgba(channels.map(|channel| channel.round() as u8)),
+                }
+            })
+            .collect()
+    }
+}
+
+impl TilePattern {
+    /// Samples per pixel side when rasterizing shape tiles
+    const SUPERSAMPLING: u32 = 4;
+
+    /// Rasterizes the tile into a coverage mask with one pixel per unit of `size`,
+    /// or returns the tile's own mask
+    pub fn mask(&self) -> Arc<GrayImage> {
+        let shapes = match self.tile {
+            Tile::Mask(ref mask) => return mask.clone(),
+            Tile::Shapes(ref shapes) => shapes,
+        };
+
+        let side = self.size.ceil().max(1.0) as u32;
+        let samples = Self::SUPERSAMPLING;
+        let step = 1.0 / (side * samples) as f32;
+        let mask = GrayImage::from_fn(side, side, |x, y| {
+            let mut covered = 0;
+            for sy in 0..samples {
+                for sx in 0..samples {
+                    let u = ((x * samples + sx) as f32 + 0.5) * step;
+                    let v = ((y * samples + sy) as f32 + 0.5) * step;
+                    if shapes.iter().any(|shape| shape.contains(u, v)) {
+                        covered += 1;
+                    }
+                }
+            }
+            Luma([(covered * 255 / (samples * samples)) as u8])
+        });
+
+        Arc::new(mask)
+    }
+
+    /// Position (0.0-1.0) within the tile of a point relative to the shape's top-left corner
+    pub fn tile_position(&self, dx: f32, dy: f32) -> (f32, f32) {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (u, v) = (dx * cos + dy * sin, dy * cos - dx * sin);
+        let size = self.size.max(f32::EPSILON);
+
+        ((u / size).rem_euclid(1.0), (v / size).rem_euclid(1.0))
+    }
+}
+
+/// Bilinearly samples a coverage mask (0.0-1.0) at a tile position, wrapping at the edges
+pub fn sample_mask(mask: &GrayImage, u: f32, v: f32) -> f32 {
+    let (width, height) = mask.dimensions();
+    if width == 0 || height == 0 {
+        return 0.0;
+    }
+
+    let x = u * width as f32 - 0.5;
+    let y = v * height as f32 - 0.5;
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+    let at = |x: f32, y: f32| {
+        let x = (x as i64).rem_euclid(width as i64) as u32;
+        let y = (y as i64).rem_euclid(height as i64) as u32;
+        mask.get_pixel(x, y)[0] as f32 / 255.0
+    };
+
+    let top = at(x0, y0) * (1.0 - fx) + at(x0 + 1.0, y0) * fx;
+    let bottom = at(x0, y0 + 1.0) * (1.0 - fx) + at(x0 + 1.0, y0 + 1.0) * fx;
+    top * (1.0 - fy) + bottom * fy
+}
+
+impl TileShape {
+    /// Returns true if a point in tile units lies inside the shape
+    pub fn contains(&self, u: f32, v: f32) -> bool {
+        match self {
+            TileShape::Rect {
+                x,
+                y,
+                width,
+                height,
+            } => u >= *x && u < x + width && v >= *y && v < y + height,
+            TileShape::Circle { cx, cy, radius } => (u - cx).hypot(v - cy) <= *radius,
+            TileShape::Polygon(points) => {
+                // Even-odd rule: count the edges a ray to the right crosses
+                let mut inside = false;
+                for (i, &(x1, y1)) in points.iter().enumerate() {
+                    let (x2, y2) = points[(i + 1) % points.len()];
+                    if (y1 > v) != (y2 > v) && u < x1 + (v - y1) / (y2 - y1) * (x2 - x1) {
+                        inside = !inside;
+                    }
+                }
+                inside
+            }
+        }
+    }
+}
+
+/// Distance from a point inside a box to the box's farthest corner
+fn farthest_corner(center: (f32, f32), x: f32, y: f32, width: f32, height: f32) -> f32 {
+    let reach_x = (center.0 - x).max(x + width - center.0);
+    let reach_y = (center.1 - y).max(y + height - center.1);
+    reach_x.hypot(reach_y)
+}
+
+/// Samples gradient stops at a position (0.0-1.0), returning unrounded channels so
+/// rasterizers can dither them
+pub fn sample_stops(stops: &[GradientStop], t: f32) -> [f32; 4] {
+    let channels = |color: Rgba<u8>| color.0.map(|channel| channel as f32);
+    let Some(first) = stops.first() else {
+        return [0.0; 4];
+    };
+
+    let t = t.clamp(0.0, 1.0);
+    if t <= first.offset {
+        return channels(first.color);
+    }
+
+    for pair in stops.windows(2) {
+        let (start, end) = (&pair[0], &pair[1]);
+        if t <= end.offset {
+            let span = end.offset - start.offset;
+            let ratio = if span > 0.0 {
+                (t - start.offset) / span
+            } else {
+                1.0
+            };
+            let (from, to) = (channels(start.color), channels(end.color));
+            return std::array::from_fn(|i| from[i] + (to[i] - from[i]) * ratio);
+        }
+    }
+
+    stops.last().map_or([0.0; 4], |stop| channels(stop.color))
+}
+
+/// Tests a point against a rectangle whose corners are rounded by `radius`
+fn rounded_rect_contains(
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    radius: f32,
+    px: f32,
+    py: f32,
+) -> bool {
+    if px < x || py < y || px > x + width || py > y + height {
+        return false;
+    }
+
+    let radius = radius.min(width / 2.0).min(height / 2.0).max(0.0);
+    if radius == 0.0 {
+        return true;
+    }
+
+    // Distance from the point to the nearest corner circle center
+    let corner_x = px.clamp(x + radius, x + width - radius);
+    let corner_y = py.clamp(y + radius, y + height - radius);
+    let (dx, dy) = (px - corner_x, py - corner_y);
+
+    dx * dx + dy * dy <= radius * radius
+}
+
+/// Approximates pixel coverage from the signed distance between the point and the outline
+fn rounded_rect_coverage(
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    radius: f32,
+    px: f32,
+    py: f32,
+) -> f32 {
+    let (half_width, half_height) = (width / 2.0, height / 2.0);
+    let radius = radius.min(half_width).min(half_height).max(0.0);
+
+    // Distance to the rectangle shrunk by the radius, minus the radius
+    let qx = (px - x - half_width).abs() - half_width + radius;
+    let qy = (py - y - half_height).abs() - half_height + radius;
+    let outside = (qx.max(0.0).powi(2) + qy.max(0.0).powi(2)).sqrt();
+    let distance = outside + qx.max(qy).min(0.0) - radius;
+
+    (0.5 - distance).clamp(0.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounded_rect_contains() {
+        let rect = RectNode {
+            radius: 10.0,
+            ..RectNode::filled(0.0, 0.0, 100.0, 50.0, Rgba([0, 0, 0, 255]))
+        };
+
+        assert!(rect.contains(50.0, 25.0));
+        assert!(rect.contains(10.0, 1.0));
+        assert!(!rect.contains(0.5, 0.5)); // Cut off by the corner
+        assert!(!rect.contains(101.0, 25.0));
+    }
+
+    #[test]
+    fn test_rounded_rect_coverage_is_antialiased() {
+        let rect = RectNode {
+            radius: 10.0,
+            ..RectNode::filled(0.0, 0.0, 100.0, 50.0, Rgba([0, 0, 0, 255]))
+        };
+
+        // Pixel-aligned straight edges stay crisp
+        assert_eq!(rect.coverage(50.5, 0.5), 1.0);
+        assert_eq!(rect.coverage(50.5, -0.5), 0.0);
+
+        // Pixels on the corner arc are partially covered
+        let on_arc = 10.0 - 10.0 * std::f32::consts::FRAC_1_SQRT_2;
+        let partial = rect.coverage(on_arc, on_arc);
+        assert!((partial - 0.5).abs() < 0.01, "{}", partial);
+        assert_eq!(rect.coverage(0.5, 0.5), 0.0);
+    }
+
+    #[test]
+    fn test_shadow_mask_covers_blurred_bounds() {
+        let shadow = ShadowNode {
+            x: 10.0,
+            y: 20.0,
+            width: 40.0,
+            height: 30.0,
+            radius: 0.0,
+            blur_radius: 8.0,
+            color: Rgba([0, 0, 0, 128]),
+        };
+
+        let mask = shadow.mask();
+        assert_eq!((mask.x, mask.y), (-2, 8));
+        assert_eq!((mask.width, mask.height), (64, 54));
+
+        let at = |x: i32, y: i32| mask.coverage[((y - mask.y) as u32 * mask.width + (x - mask.x) as u32) as usize];
+        assert!(at(30, 35) > 0.99); // Center stays solid
+        assert!((at(9, 35) + at(10, 35) - 1.0).abs() < 0.01); // Left edge is half covered
+        assert!(at(-2, 35) < 0.01); // Fades out within the extent
+    }
+
+    #[test]
+    fn test_gradient_line_matches_css() {
+        let gradient = LinearGradient {
+            angle: 180.0,
+            stops: vec![],
+        };
+
+        let ((x1, y1), (x2, y2)) = gradient.line_for_box(0.0, 0.0, 200.0, 100.0);
+        assert!((x1 - 100.0).abs() < 0.001 && y1.abs() < 0.001);
+        assert!((x2 - 100.0).abs() < 0.001 && (y2 - 100.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_radial_and_conic_geometry() {
+        let stops = vec![
+            GradientStop {
+                offset: 0.0,
+                color: Rgba([0, 0, 0, 255]),
+            },
+            GradientStop {
+                offset: 1.0,
+                color: Rgba([255, 255, 255, 255]),
+            },
+        ];
+
+        // Off-center radial gradients reach the far corner
+        let radial = RadialGradient {
+            center_x: 0.25,
+            center_y: 0.5,
+            stops: stops.clone(),
+        };
+        let (cx, cy, radius) = radial.circle_for_box(0.0, 0.0, 200.0, 100.0);
+        assert_eq!((cx, cy), (50.0, 50.0));
+        assert!((radius - 150.0f32.hypot(50.0)).abs() < 0.001);
+
+        // Conic gradients sweep clockwise from their angle
+        let conic = ConicGradient {
+            angle: 90.0,
+            center_x: 0.5,
+            center_y: 0.5,
+            stops,
+        };
+        let center = conic.center_for_box(0.0, 0.0, 100.0, 100.0);
+        assert!(conic.position(center, 100.0, 50.0).abs() < 0.001);
+        assert!((conic.position(center, 50.0, 100.0) - 0.25).abs() < 0.001);
+        assert!((conic.position(center, 50.0, 0.0) - 0.75).abs() < 0.001);
+
+        let wedges = conic.wedges(0.0, 0.0, 100.0, 100.0);
+        assert_eq!(wedges.len(), ConicGradient::WEDGES);
+        assert!(wedges[0].color[0] < wedges[wedges.len() - 1].color[0]);
+        assert!(wedges.iter().all(|wedge| wedge.points[0] == center));
+    }
+
+    #[test]
+    fn test_sample_stops_interpolates_channels() {
+        let stops = [
+            GradientStop {
+                offset: 0.2,
+                color: Rgba([0, 0, 0, 255]),
+            },
+            GradientStop {
+                offset: 0.6,
+                color: Rgba([255, 100, 0, 255]),
+            },
+        ];
+
+        assert_eq!(sample_stops(&stops, 0.0), [0.0, 0.0, 0.0, 255.0]);
+        let halfway = sample_stops(&stops, 0.4);
+        assert!((halfway[0] - 127.5).abs() < 0.01 && (halfway[1] - 50.0).abs() < 0.01);
+        assert_eq!(sample_stops(&stops, 1.0), [255.0, 100.0, 0.0, 255.0]);
+    }
+}
@@ -15,6 +15,12 @@ pub struct SessionData {
     pub data: HashMap<String, serde_json::Value>,
 }
 
+impl Default for SessionData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl SessionData {
     /// Creates a new session
     pub fn new() -> Self {
@@ -0,0 +1,142 @@
+use crate::models::theme::TypographyStyle;
+use crate::services::font_manager::LoadedFont;
+use rustybuzz::{Direction, Face, Feature, Tag, UnicodeBuffer};
+
+/// Ligatures shapers apply unless they are switched off
+const DEFAULT_ON_FEATURES: [&str; 2] = ["liga", "calt"];
+
+/// A glyph picked by the shaper, positioned in pixels from the start of the text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShapedGlyph {
+    pub id: u16,
+    /// Byte offset of the first character the glyph belongs to
+    pub cluster: usize,
+    pub x: f32,
+    pub advance: f32,
+}
+
+/// Shapes a line of text left to right with exactly the listed features enabled.
+/// Clusters stay in logical order, so glyphs can be matched back to characters.
+/// Returns None if the font can't be parsed for shaping.
+pub fn shape(
+    font: &LoadedFont,
+    text: &str,
+    font_size: f32,
+    features: &[String],
+) -> Option<Vec<ShapedGlyph>> {
+    let face = Face::from_slice(&font.data, font.index)?;
+    let scale = font_size / face.units_per_em() as f32;
+
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.set_direction(Direction::LeftToRight);
+
+    let output = rustybuzz::shape(&face, &feature_settings(features), buffer);
+
+    let mut pen = 0.0;
+    let glyphs = output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, position)| {
+            let advance = position.x_advance as f32 * scale;
+            let glyph = ShapedGlyph {
+                id: info.glyph_id as u16,
+                cluster: info.cluster as usize,
+                x: pen + position.x_offset as f32 * scale,
+                advance,
+            };
+            pen += advance;
+            glyph
+        })
+        .collect();
+
+    Some(glyphs)
+}
+
+/// Turns the theme's feature list into shaper settings, switching off the
+/// default-on ligatures it leaves out and ignoring tags it may not set
+fn feature_settings(features: &[String]) -> Vec<Feature> {
+    let disabled = DEFAULT_ON_FEATURES
+        .iter()
+        .filter(|tag| !features.iter().any(|feature| feature == *tag))
+        .map(|tag| Feature::new(Tag::from_bytes_lossy(tag.as_bytes()), 0, ..));
+    let enabled = features
+        .iter()
+        .filter(|tag| TypographyStyle::is_supported_feature(tag))
+        .map(|tag| Feature::new(Tag::from_bytes_lossy(tag.as_bytes()), 1, ..));
+
+    disabled.chain(enabled).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::font_manager::{FontManager, LIGATURE_FONT_FAMILY};
+    use ab_glyph::{Font, GlyphId};
+
+    fn features(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn test_unlisted_ligatures_are_switched_off() {
+        let settings = feature_settings(&features(&["calt", "ss02", "bogus"]));
+        let tags: Vec<(Tag, u32)> = settings
+            .iter()
+            .map(|feature| (feature.tag, feature.value))
+            .collect();
+
+        assert_eq!(
+            tags,
+            vec![
+                (Tag::from_bytes(b"liga"), 0),
+                (Tag::from_bytes(b"calt"), 1),
+                (Tag::from_bytes(b"ss02"), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shaped_advances_and_clusters() {
+        let manager = FontManager::new().unwrap();
+        let font = manager.resolve_loaded("monospace");
+        let default_features = TypographyStyle::default_font_features();
+
+        let glyphs = shape(&font, "a => b", 20.0, &default_features).unwrap();
+        assert_eq!(glyphs.len(), 6);
+        assert!(glyphs.iter().all(|glyph| (glyph.advance - 20.0 * 1233.0 / 2048.0).abs() < 0.01));
+        assert_eq!(glyphs[3].cluster, 3);
+        assert!((glyphs[3].x - glyphs[0].advance * 3.0).abs() < 0.01);
+
+        // A combining accent is composed into a single glyph spanning both characters
+        let glyphs = shape(&font, "a\u{301}b", 20.0, &default_features).unwrap();
+        let clusters: Vec<usize> = glyphs.iter().map(|glyph| glyph.cluster).collect();
+        assert_eq!(clusters, vec![0, 3]);
+    }
+
+    #[test]
+    fn test_operators_shape_to_one_ligature_glyph() {
+        let manager = FontManager::new().unwrap();
+        let font = manager.resolve_loaded(LIGATURE_FONT_FAMILY);
+        let default_features = TypographyStyle::default_font_features();
+
+        for operator in ["=>", "!="] {
+            let glyphs = shape(&font, operator, 20.0, &default_features).unwrap();
+            // Fira Code keeps one cell per character: an empty spacer, then the ligature
+            let inked: Vec<u16> = glyphs
+                .iter()
+                .map(|glyph| glyph.id)
+                .filter(|id| font.font.outline(GlyphId(*id)).is_some())
+                .collect();
+            assert_eq!(inked.len(), 1, "{} shaped to {:?}", operator, glyphs);
+            assert!(operator.chars().all(|ch| font.font.glyph_id(ch).0 != inked[0]));
+
+            // Without ligatures each character keeps its own glyph
+            let plain = shape(&font, operator, 20.0, &[]).unwrap();
+            let ids: Vec<u16> = plain.iter().map(|glyph| glyph.id).collect();
+            let expected: Vec<u16> = operator.chars().map(|ch| font.font.glyph_id(ch).0).collect();
+            assert_eq!(ids, expected);
+        }
+    }
+}
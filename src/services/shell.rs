@@ -0,0 +1,219 @@
+use crate::models::errors::AppError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Prompts recognized without a custom pattern: `$ `, `# ` and PowerShell's `PS>` or `PS C:\path>`
+pub const DEFAULT_PROMPT_PATTERN: &str = r"^(?:[$#](?: |$)|PS(?: [^>]*)?> ?)";
+
+/// Longest accepted custom prompt pattern
+pub const MAX_PROMPT_PATTERN_LENGTH: usize = 256;
+
+/// Longest accepted user, host or path in a prompt segment
+pub const MAX_SEGMENT_PART_LENGTH: usize = 64;
+
+/// How shell session input is split into prompts, commands and output
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellOptions {
+    /// Regex for the prompt, matched at the start of each line; defaults to `DEFAULT_PROMPT_PATTERN`
+    pub prompt_pattern: Option<String>,
+    /// Shown before each prompt, like a shell's `user@host:path`
+    pub prompt_segment: Option<PromptSegment>,
+    /// Draws a terminal block cursor after the last line
+    pub cursor: bool,
+}
+
+/// The `user@host:path` part of a prompt; empty parts are left out
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptSegment {
+    pub user: String,
+    pub host: String,
+    pub path: String,
+}
+
+/// One line of a shell session
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionLine {
+    /// A command typed after a prompt, or with no prompt when it continues the previous
+    /// command after a trailing `\`
+    Command { prompt: Option<String>, command: String },
+    /// Anything the commands printed
+    Output(String),
+}
+
+impl ShellOptions {
+    /// Validates the prompt pattern and segment
+    pub fn validate(&self) -> Result<(), AppError> {
+        self.prompt_regex()?;
+
+        if let Some(ref segment) = self.prompt_segment {
+            for part in [&segment.user, &segment.host, &segment.path] {
+                if part.chars().count() > MAX_SEGMENT_PART_LENGTH {
+                    return Err(AppError::image_generation_failed(format!(
+                        "Prompt segment parts must be at most {} characters",
+                        MAX_SEGMENT_PART_LENGTH
+                    )));
+                }
+                if part.chars().any(char::is_control) {
+                    return Err(AppError::image_generation_failed(
+                        "Prompt segment parts cannot contain control characters",
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compiles the prompt pattern
+    fn prompt_regex(&self) -> Result<Regex, AppError> {
+        let pattern = self.prompt_pattern.as_deref().unwrap_or(DEFAULT_PROMPT_PATTERN);
+        if pattern.len() > MAX_PROMPT_PATTERN_LENGTH {
+            return Err(AppError::image_generation_failed(format!(
+                "Prompt pattern must be at most {} characters",
+                MAX_PROMPT_PATTERN_LENGTH
+            )));
+        }
+
+        Regex::new(pattern).map_err(|e| {
+            AppError::image_generation_failed(format!("Invalid prompt pattern: {}", e))
+        })
+    }
+}
+
+impl PromptSegment {
+    /// The segment's user and host, and its path, as they're shown
+    pub fn parts(&self) -> (String, String) {
+        let identity = match (self.user.is_empty(), self.host.is_empty()) {
+            (false, false) => format!("{}@{}", self.user, self.host),
+            (false, true) => self.user.clone(),
+            (true, false) => self.host.clone(),
+            (true, true) => String::new(),
+        };
+        let path = match (identity.is_empty(), self.path.is_empty()) {
+            (_, true) => String::new(),
+            (true, false) => self.path.clone(),
+            (false, false) => format!(":{}", self.path),
+        };
+
+        (identity, path)
+    }
+}
+
+/// Splits a pasted terminal session into commands and output
+pub fn parse_session(input: &str, options: &ShellOptions) -> Result<Vec<SessionLine>, AppError> {
+    let prompt = options.prompt_regex()?;
+    let mut continuing = false;
+
+    let lines = input
+        .lines()
+        .map(|raw| {
+            let raw = raw.strip_suffix('\r').unwrap_or(raw);
+            let line = if continuing {
+                SessionLine::Command {
+                    prompt: None,
+                    command: raw.to_string(),
+                }
+            } else {
+                match prompt.find(raw).filter(|m| m.start() == 0 && !m.is_empty()) {
+                    Some(m) => SessionLine::Command {
+                        prompt: Some(m.as_str().to_string()),
+                        command: raw[m.end()..].to_string(),
+                    },
+                    None => SessionLine::Output(raw.to_string()),
+                }
+            };
+
+            continuing = matches!(&line, SessionLine::Command { command, .. } if command.ends_with('\\'));
+            line
+        })
+        .collect();
+
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(prompt: Option<&str>, command: &str) -> SessionLine {
+        SessionLine::Command {
+            prompt: prompt.map(str::to_string),
+            command: command.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_default_prompts() {
+        let input = "$ ls -la\ntotal 0\n# whoami\nroot\nPS C:\\Users\\dev> Get-Date\nPS> exit\n$\n$HOME";
+        let lines = parse_session(input, &ShellOptions::default()).unwrap();
+
+        assert_eq!(
+            lines,
+            vec![
+                command(Some("$ "), "ls -la"),
+                SessionLine::Output("total 0".to_string()),
+                command(Some("# "), "whoami"),
+                SessionLine::Output("root".to_string()),
+                command(Some("PS C:\\Users\\dev> "), "Get-Date"),
+                command(Some("PS> "), "exit"),
+                command(Some("$"), ""),
+                SessionLine::Output("$HOME".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_prompts_and_continuations() {
+        let options = ShellOptions {
+            prompt_pattern: Some(r"❯ |>>> ".to_string()),
+            ..Default::default()
+        };
+        let input = "❯ cargo build \\\n    --release\n   Compiling demo\nnot ❯ a prompt\n>>> 1 + 1";
+        let lines = parse_session(input, &options).unwrap();
+
+        assert_eq!(
+            lines,
+            vec![
+                command(Some("❯ "), "cargo build \\"),
+                command(None, "    --release"),
+                SessionLine::Output("   Compiling demo".to_string()),
+                SessionLine::Output("not ❯ a prompt".to_string()),
+                command(Some(">>> "), "1 + 1"),
+            ]
+        );
+
+        let invalid = ShellOptions {
+            prompt_pattern: Some("(unclosed".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+        assert!(parse_session("$ ls", &invalid).is_err());
+    }
+
+    #[test]
+    fn test_prompt_segment_parts() {
+        let segment = PromptSegment {
+            user: "dev".to_string(),
+            host: "box".to_string(),
+            path: "~/src".to_string(),
+        };
+        assert_eq!(segment.parts(), ("dev@box".to_string(), ":~/src".to_string()));
+
+        let path_only = PromptSegment {
+            path: "~".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(path_only.parts(), (String::new(), "~".to_string()));
+
+        let options = ShellOptions {
+            prompt_segment: Some(PromptSegment {
+                user: "a\nb".to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+    }
+}
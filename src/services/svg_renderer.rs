@@ -0,0 +1,829 @@
+use crate::models::errors::AppError;
+use crate::services::font_manager::{
+    FontManager, LoadedFont, SYNTHETIC_BOLD_EMBOLDEN, SYNTHETIC_ITALIC_SKEW,
+};
+use crate::services::font_subset::subset_to_woff2;
+use crate::services::image_generator::SvgTextMode;
+use crate::services::scene::{
+    ClipRect, CircleNode, ConicGradient, GradientStop, GroupNode, ImageNode, LineNode, Paint,
+    RectNode, Scene, SceneNode, ShadowNode, TextRun, Tile, TilePattern, TileShape,
+};
+use ab_glyph::{Font, GlyphId, OutlineCurve, Point};
+use base64::Engine;
+use image::Rgba;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+/// Renders scenes to standalone SVG documents
+pub struct SvgRenderer {
+    font_manager: Arc<FontManager>,
+}
+
+/// State accumulated while writing a single SVG document
+struct SvgWriter<'a> {
+    font_manager: &'a FontManager,
+    text_mode: SvgTextMode,
+    defs: String,
+    body: String,
+    next_id: usize,
+    fonts: Vec<LoadedFont>,
+    font_indices: HashMap<String, FontUse>,
+    used_chars: Vec<BTreeSet<char>>,
+    glyph_defs: HashSet<(usize, u16)>,
+}
+
+/// The document font a run's face maps to and the styles it has to synthesize
+#[derive(Clone, Copy)]
+struct FontUse {
+    index: usize,
+    synthetic_bold: bool,
+    synthetic_italic: bool,
+}
+
+impl SvgRenderer {
+    /// Creates a new SvgRenderer that resolves fonts through the given manager
+    pub fn new(font_manager: Arc<FontManager>) -> Self {
+        SvgRenderer { font_manager }
+    }
+
+    /// Renders a scene to an SVG document
+    pub fn render(&self, scene: &Scene, text_mode: &SvgTextMode) -> Result<String, AppError> {
+        let mut writer = SvgWriter {
+            font_manager: &self.font_manager,
+            text_mode: text_mode.clone(),
+            defs: String::new(),
+            body: String::new(),
+            next_id: 0,
+            fonts: Vec::new(),
+            font_indices: HashMap::new(),
+            used_chars: Vec::new(),
+            glyph_defs: HashSet::new(),
+        };
+
+        for node in &scene.nodes {
+            writer.write_node(node)?;
+        }
+
+        writer.finish(scene.width, scene.height)
+    }
+}
+
+impl SvgWriter<'_> {
+    /// Assembles the document from the collected definitions and body
+    fn finish(self, width: u32, height: u32) -> Result<String, AppError> {
+        let mut svg = String::new();
+
+        svg.push_str(&format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" xmlns:xlink=\"http://www.w3.org/1999/xlink\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            width, height, width, height
+        ));
+
+        svg.push_str("<defs>\n");
+        if self.text_mode == SvgTextMode::EmbeddedFont && !self.fonts.is_empty() {
+            svg.push_str(&self.font_faces()?);
+        }
+        svg.push_str(&self.defs);
+        svg.push_str("</defs>\n");
+
+        svg.push_str(&self.body);
+        svg.push_str("</svg>\n");
+
+        Ok(svg)
+    }
+
+    /// Builds @font-face rules with a WOFF2 subset of every font used in the document
+    fn font_faces(&self) -> Result<String, AppError> {
+        let mut style = String::from("<style type=\"text/css\"><![CDATA[\n");
+
+        for (index, (font, chars)) in self.fonts.iter().zip(&self.used_chars).enumerate() {
+            let woff2 = subset_to_woff2(&font.data, font.index, chars)?;
+            let encoded = base64::engine::general_purpose::STANDARD.encode(woff2);
+
+            style.push_str(&format!(
+                "@font-face {{ font-family: '{}'; src: url(data:font/woff2;base64,{}) format('woff2'); }}\n",
+                Self::embedded_family(index),
+                encoded
+            ));
+        }
+
+        style.push_str("]]></style>\n");
+        Ok(style)
+    }
+
+    fn write_node(&mut self, node: &SceneNode) -> Result<(), AppError> {
+        match node {
+            SceneNode::Rect(rect) => self.write_rect(rect),
+            SceneNode::Circle(circle) => {
+                self.write_circle(circle);
+                Ok(())
+            }
+            SceneNode::Line(line) => {
+                self.write_line(line);
+                Ok(())
+            }
+            SceneNode::Text(run) => self.write_text(run),
+            SceneNode::Group(group) => self.write_group(group),
+            SceneNode::Shadow(shadow) => {
+                self.write_shadow(shadow);
+                Ok(())
+            }
+            SceneNode::Image(image) => self.write_image(image),
+        }
+    }
+
+    /// Writes the shadow rectangle through a Gaussian blur filter sized to its blurred bounds
+    fn write_shadow(&mut self, shadow: &ShadowNode) {
+        let mut filter = String::new();
+        if shadow.sigma() > 0.0 {
+            let id = self.next_id("shadow");
+            let extent = shadow.extent();
+            self.defs.push_str(&format!(
+                "<filter id=\"{}\" filterUnits=\"userSpaceOnUse\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"><feGaussianBlur stdDeviation=\"{}\"/></filter>\n",
+                id,
+                num(shadow.x - extent),
+                num(shadow.y - extent),
+                num(shadow.width + extent * 2.0),
+                num(shadow.height + extent * 2.0),
+                num_precise(shadow.sigma())
+            ));
+            filter = format!(" filter=\"url(#{})\"", id);
+        }
+
+        let rect = RectNode {
+            radius: shadow.radius,
+            ..RectNode::filled(shadow.x, shadow.y, shadow.width, shadow.height, shadow.color)
+        };
+        self.body.push_str(&format!(
+            "<rect {} {}{}/>\n",
+            Self::rect_geometry(&rect),
+            Self::paint_attrs("fill", shadow.color),
+            filter
+        ));
+    }
+
+    fn write_rect(&mut self, rect: &RectNode) -> Result<(), AppError> {
+        let fill = match &rect.fill {
+            Some(Paint::Solid(color)) => Self::paint_attrs("fill", *color),
+            Some(Paint::LinearGradient(gradient)) => {
+                let id = self.next_id("paint");
+                let ((x1, y1), (x2, y2)) =
+                    gradient.line_for_box(rect.x, rect.y, rect.width, rect.height);
+
+                self.defs.push_str(&format!(
+                    "<linearGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">\n",
+                    id,
+                    num(x1),
+                    num(y1),
+                    num(x2),
+                    num(y2)
+                ));
+                self.write_stops(&gradient.stops);
+                self.defs.push_str("</linearGradient>\n");
+
+                format!("fill=\"url(#{})\"", id)
+            }
+            Some(Paint::RadialGradient(gradient)) => {
+                let id = self.next_id("paint");
+                let (cx, cy, radius) =
+                    gradient.circle_for_box(rect.x, rect.y, rect.width, rect.height);
+
+                self.defs.push_str(&format!(
+                    "<radialGradient id=\"{}\" gradientUnits=\"userSpaceOnUse\" cx=\"{}\" cy=\"{}\" r=\"{}\">\n",
+                    id,
+                    num(cx),
+                    num(cy),
+                    num(radius)
+                ));
+                self.write_stops(&gradient.stops);
+                self.defs.push_str("</radialGradient>\n");
+
+                format!("fill=\"url(#{})\"", id)
+            }
+            Some(Paint::ConicGradient(gradient)) => {
+                self.write_conic_wedges(rect, gradient);
+                "fill=\"none\"".to_string()
+            }
+            Some(Paint::Pattern(pattern)) => {
+                // Base color first, then the repeating tile on top
+                self.body.push_str(&format!(
+                    "<rect {} {}/>\n",
+                    Self::rect_geometry(rect),
+                    Self::paint_attrs("fill", pattern.background)
+                ));
+
+                let id = self.next_id("paint");
+                let transform = if pattern.rotation != 0.0 {
+                    format!(
+                        " patternTransform=\"rotate({} {} {})\"",
+                        num_precise(pattern.rotation),
+                        num(rect.x),
+                        num(rect.y)
+                    )
+                } else {
+                    String::new()
+                };
+                self.defs.push_str(&format!(
+                    "<pattern id=\"{}\" patternUnits=\"userSpaceOnUse\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}>",
+                    id,
+                    num(rect.x),
+                    num(rect.y),
+                    num_precise(pattern.size),
+                    num_precise(pattern.size),
+                    transform
+                ));
+                let tile = Self::pattern_tile(pattern)?;
+                self.defs.push_str(&tile);
+                self.defs.push_str("</pattern>\n");
+
+                format!("fill=\"url(#{})\"", id)
+            }
+            None => "fill=\"none\"".to_string(),
+        };
+
+        let stroke = match &rect.stroke {
+            Some(stroke) => format!(
+                " {} stroke-width=\"{}\"",
+                Self::paint_attrs("stroke", stroke.color),
+                num(stroke.width)
+            ),
+            None => String::new(),
+        };
+
+        self.body.push_str(&format!(
+            "<rect {} {}{}/>\n",
+            Self::rect_geometry(rect),
+            fill,
+            stroke
+        ));
+
+        Ok(())
+    }
+
+    /// Draws a pattern's tile at its size: shapes in the pattern color, or the mask as an image
+    fn pattern_tile(pattern: &TilePattern) -> Result<String, AppError> {
+        let size = pattern.size;
+        let shapes = match &pattern.tile {
+            Tile::Shapes(shapes) => shapes,
+            Tile::Mask(mask) => {
+                let color = pattern.color;
+                let tile = image::RgbaImage::from_fn(mask.width(), mask.height(), |x, y| {
+                    let alpha = mask.get_pixel(x, y)[0] as u32 * color[3] as u32 / 255;
+                    Rgba([color[0], color[1], color[2], alpha as u8])
+                });
+                let mut png = Vec::new();
+                tile.write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+                    .map_err(|e| AppError::image_generation_failed(format!("PNG encoding failed: {}", e)))?;
+
+                return Ok(format!(
+                    "<image width=\"{}\" height=\"{}\" preserveAspectRatio=\"none\" href=\"data:image/png;base64,{}\"/>",
+                    num_precise(size),
+                    num_precise(size),
+                    base64::engine::general_purpose::STANDARD.encode(png)
+                ));
+            }
+        };
+
+        let mut tile = format!("<g {}>", Self::paint_attrs("fill", pattern.color));
+        for shape in shapes {
+            match shape {
+                TileShape::Rect {
+                    x,
+                    y,
+                    width,
+                    height,
+                } => tile.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                    num_precise(x * size),
+                    num_precise(y * size),
+                    num_precise(width * size),
+                    num_precise(height * size)
+                )),
+                TileShape::Circle { cx, cy, radius } => tile.push_str(&format!(
+                    "<circle cx=\"{}\" cy=\"{}\" r=\"{}\"/>",
+                    num_precise(cx * size),
+                    num_precise(cy * size),
+                    num_precise(radius * size)
+                )),
+                TileShape::Polygon(points) => {
+                    let points: Vec<String> = points
+                        .iter()
+                        .map(|(x, y)| format!("{},{}", num_precise(x * size), num_precise(y * size)))
+                        .collect();
+                    tile.push_str(&format!("<polygon points=\"{}\"/>", points.join(" ")));
+                }
+            }
+        }
+        tile.push_str("</g>");
+
+        Ok(tile)
+    }
+
+    fn write_stops(&mut self, stops: &[GradientStop]) {
+        for stop in stops {
+            self.defs.push_str(&format!(
+                "<stop offset=\"{}\" {}/>\n",
+                num(stop.offset),
+                Self::paint_attrs("stop-color", stop.color)
+            ));
+        }
+    }
+
+    /// SVG has no conic gradients, so the sweep is drawn as thin wedges clipped to the shape.
+    /// The wedges overlap, so they are drawn opaque under the stops' shared opacity.
+    fn write_conic_wedges(&mut self, rect: &RectNode, gradient: &ConicGradient) {
+        let id = self.next_id("clip");
+        self.defs.push_str(&format!(
+            "<clipPath id=\"{}\"><rect {}/></clipPath>\n",
+            id,
+            Self::rect_geometry(rect)
+        ));
+
+        let opacity = gradient.stops.first().map_or(255, |stop| stop.color[3]);
+        let group_opacity = if opacity < 255 {
+            format!(" opacity=\"{}\"", num_precise(opacity as f32 / 255.0))
+        } else {
+            String::new()
+        };
+        self.body.push_str(&format!("<g clip-path=\"url(#{})\"{}>\n", id, group_opacity));
+
+        for wedge in gradient.wedges(rect.x, rect.y, rect.width, rect.height) {
+            let [(x1, y1), (x2, y2), (x3, y3)] = wedge.points;
+            let mut color = wedge.color;
+            color[3] = 255;
+            self.body.push_str(&format!(
+                "<path d=\"M{} {}L{} {}L{} {}Z\" {}/>\n",
+                num(x1),
+                num(y1),
+                num(x2),
+                num(y2),
+                num(x3),
+                num(y3),
+                Self::paint_attrs("fill", color)
+            ));
+        }
+        self.body.push_str("</g>\n");
+    }
+
+    fn write_circle(&mut self, circle: &CircleNode) {
+        self.body.push_str(&format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" {}/>\n",
+            num(circle.cx),
+            num(circle.cy),
+            num(circle.radius),
+            Self::paint_attrs("fill", circle.color)
+        ));
+    }
+
+    fn write_line(&mut self, line: &LineNode) {
+        self.body.push_str(&format!(
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" {} stroke-width=\"{}\"/>\n",
+            num(line.x1),
+            num(line.y1),
+            num(line.x2),
+            num(line.y2),
+            Self::paint_attrs("stroke", line.color),
+            num(line.width)
+        ));
+    }
+
+    /// Embeds the bitmap as a PNG data URL
+    fn write_image(&mut self, image: &ImageNode) -> Result<(), AppError> {
+        let mut png = Vec::new();
+        image
+            .image
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+            .map_err(|e| AppError::image_generation_failed(format!("PNG encoding failed: {}", e)))?;
+
+        self.body.push_str(&format!(
+            "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"none\" href=\"data:image/png;base64,{}\"/>\n",
+            num(image.x),
+            num(image.y),
+            num(image.width),
+            num(image.height),
+            base64::engine::general_purpose::STANDARD.encode(png)
+        ));
+
+        Ok(())
+    }
+
+    fn write_text(&mut self, run: &TextRun) -> Result<(), AppError> {
+        if run.glyphs.is_empty() {
+            return Ok(());
+        }
+
+        let font = self.font_index(run);
+
+        match self.text_mode {
+            SvgTextMode::Outlines => self.write_text_outlines(run, font),
+            SvgTextMode::EmbeddedFont => {
+                self.used_chars[font.index].extend(run.glyphs.iter().map(|glyph| glyph.ch));
+
+                // Viewers synthesize the styles the embedded face lacks
+                let mut style = String::new();
+                if font.synthetic_bold {
+                    style.push_str(" font-weight=\"bold\"");
+                }
+                if font.synthetic_italic {
+                    style.push_str(" font-style=\"italic\"");
+                }
+
+                let positions: Vec<String> = run.glyphs.iter().map(|glyph| num(glyph.x)).collect();
+                self.body.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-family=\"'{}', monospace\" font-size=\"{}\"{} {} xml:space=\"preserve\">{}</text>\n",
+                    positions.join(" "),
+                    num(run.baseline),
+                    Self::embedded_family(font.index),
+                    num(run.font_size),
+                    style,
+                    Self::paint_attrs("fill", run.color),
+                    escape_xml(&run.text())
+                ));
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes each glyph as a reference to its outline path, defined once per document.
+    /// Synthetic italics shear the reference and synthetic bold strokes the outline.
+    fn write_text_outlines(&mut self, run: &TextRun, font_use: FontUse) -> Result<(), AppError> {
+        let font_index = font_use.index;
+        let font = self.fonts[font_index].font.clone();
+        let units_per_em = font.units_per_em().unwrap_or(1000.0);
+        let scale = run.font_size / units_per_em;
+        let skew = if font_use.synthetic_italic {
+            SYNTHETIC_ITALIC_SKEW * scale
+        } else {
+            0.0
+        };
+
+        let mut uses = String::new();
+        for glyph in run.glyphs.iter().filter(|glyph| !glyph.ch.is_whitespace() && !glyph.joined) {
+            let glyph_id = glyph.id.map_or_else(|| font.glyph_id(glyph.ch), GlyphId);
+            if !self.define_glyph(&font, font_index, glyph_id) {
+                continue;
+            }
+
+            uses.push_str(&format!(
+                "<use xlink:href=\"#glyph-{}-{}\" transform=\"matrix({} 0 {} {} {} {})\"/>\n",
+                font_index,
+                glyph_id.0,
+                num_precise(scale),
+                num_precise(skew),
+                num_precise(-scale),
+                num(glyph.x),
+                num(run.baseline)
+            ));
+        }
+
+        if !uses.is_empty() {
+            // The stroke is in font units, inside each glyph's transform
+            let stroke = if font_use.synthetic_bold {
+                format!(
+                    " {} stroke-width=\"{}\" stroke-linejoin=\"round\"",
+                    Self::paint_attrs("stroke", run.color),
+                    num(run.font_size * SYNTHETIC_BOLD_EMBOLDEN / scale)
+                )
+            } else {
+                String::new()
+            };
+            self.body.push_str(&format!(
+                "<g {}{}>\n",
+                Self::paint_attrs("fill", run.color),
+                stroke
+            ));
+            self.body.push_str(&uses);
+            self.body.push_str("</g>\n");
+        }
+
+        Ok(())
+    }
+
+    /// Adds a glyph outline to the definitions; returns false if the glyph has no outline
+    fn define_glyph(&mut self, font: &impl Font, font_index: usize, glyph_id: GlyphId) -> bool {
+        if self.glyph_defs.contains(&(font_index, glyph_id.0)) {
+            return true;
+        }
+
+        let Some(outline) = font.outline(glyph_id) else {
+            return false;
+        };
+
+        self.defs.push_str(&format!(
+            "<path id=\"glyph-{}-{}\" d=\"{}\"/>\n",
+            font_index,
+            glyph_id.0,
+            outline_path(&outline.curves)
+        ));
+        self.glyph_defs.insert((font_index, glyph_id.0));
+        true
+    }
+
+    fn write_group(&mut self, group: &GroupNode) -> Result<(), AppError> {
+        match &group.clip {
+            Some(clip) => {
+                let id = self.next_id("clip");
+                self.defs.push_str(&format!(
+                    "<clipPath id=\"{}\"><rect {}/></clipPath>\n",
+                    id,
+                    Self::clip_geometry(clip)
+                ));
+                self.body.push_str(&format!("<g clip-path=\"url(#{})\">\n", id));
+            }
+            None => self.body.push_str("<g>\n"),
+        }
+
+        for child in &group.children {
+            self.write_node(child)?;
+        }
+
+        self.body.push_str("</g>\n");
+        Ok(())
+    }
+
+    /// Returns the document font for a run's face, loading it on first use.
+    /// Synthesized styles share the font of the face they are derived from.
+    fn font_index(&mut self, run: &TextRun) -> FontUse {
+        let key = run.face_key();
+        if let Some(&font_use) = self.font_indices.get(&key) {
+            return font_use;
+        }
+
+        let styled = self.font_manager.resolve_styled(&run.font_family, run.bold, run.italic);
+        let index = match self.fonts.iter().position(|font| font.same_face(&styled.loaded)) {
+            Some(index) => index,
+            None => {
+                self.fonts.push(styled.loaded);
+                self.used_chars.push(BTreeSet::new());
+                self.fonts.len() - 1
+            }
+        };
+
+        let font_use = FontUse {
+            index,
+            synthetic_bold: styled.synthetic_bold,
+            synthetic_italic: styled.synthetic_italic,
+        };
+        self.font_indices.insert(key, font_use);
+        font_use
+    }
+
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{}-{}", prefix, self.next_id)
+    }
+
+    fn embedded_family(index: usize) -> String {
+        format!("snippet-font-{}", index)
+    }
+
+    fn rect_geometry(rect: &RectNode) -> String {
+        let mut geometry = format!(
+            "x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"",
+            num(rect.x),
+            num(rect.y),
+            num(rect.width),
+            num(rect.height)
+        );
+        if rect.radius > 0.0 {
+            geometry.push_str(&format!(" rx=\"{}\"", num(rect.radius)));
+        }
+        geometry
+    }
+
+    fn clip_geometry(clip: &ClipRect) -> String {
+        let mut geometry = format!(
+            "x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"",
+            num(clip.x),
+            num(clip.y),
+            num(clip.width),
+            num(clip.height)
+        );
+        if clip.radius > 0.0 {
+            geometry.push_str(&format!(" rx=\"{}\"", num(clip.radius)));
+        }
+        geometry
+    }
+
+    /// Formats a color attribute, adding a matching opacity attribute for translucent colors
+    fn paint_attrs(attribute: &str, color: Rgba<u8>) -> String {
+        let mut attrs = format!(
+            "{}=\"#{:02x}{:02x}{:02x}\"",
+            attribute, color[0], color[1], color[2]
+        );
+
+        if color[3] < 255 {
+            let opacity_attribute = match attribute {
+                "stop-color" => "stop-opacity".to_string(),
+                _ => format!("{}-opacity", attribute),
+            };
+            attrs.push_str(&format!(
+                " {}=\"{}\"",
+                opacity_attribute,
+                num_precise(color[3] as f32 / 255.0)
+            ));
+        }
+
+        attrs
+    }
+}
+
+/// Converts glyph outline curves (font units, y up) to SVG path data
+fn outline_path(curves: &[OutlineCurve]) -> String {
+    let mut path = String::new();
+    let mut last_point: Option<Point> = None;
+
+    for curve in curves {
+        let (start, end, segment) = match curve {
+            OutlineCurve::Line(p0, p1) => (*p0, *p1, format!("L{} {}", num(p1.x), num(p1.y))),
+            OutlineCurve::Quad(p0, p1, p2) => (
+                *p0,
+                *p2,
+                format!("Q{} {} {} {}", num(p1.x), num(p1.y), num(p2.x), num(p2.y)),
+            ),
+            OutlineCurve::Cubic(p0, p1, p2, p3) => (
+                *p0,
+                *p3,
+                format!(
+                    "C{} {} {} {} {} {}",
+                    num(p1.x),
+                    num(p1.y),
+                    num(p2.x),
+                    num(p2.y),
+                    num(p3.x),
+                    num(p3.y)
+                ),
+            ),
+        };
+
+        // A curve that doesn't continue from the previous one starts a new contour
+        if last_point != Some(start) {
+            if last_point.is_some() {
+                path.push('Z');
+            }
+            path.push_str(&format!("M{} {}", num(start.x), num(start.y)));
+        }
+
+        path.push_str(&segment);
+        last_point = Some(end);
+    }
+
+    if last_point.is_some() {
+        path.push('Z');
+    }
+
+    path
+}
+
+/// Formats a coordinate with at most two decimals
+fn num(value: f32) -> String {
+    trim_number(format!("{:.2}", value))
+}
+
+/// Formats a scale or opacity factor with enough precision to stay visually exact
+fn num_precise(value: f32) -> String {
+    trim_number(format!("{:.6}", value))
+}
+
+fn trim_number(formatted: String) -> String {
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "" | "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+/// Escapes text for use in XML content
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::scene::{SceneGlyph, Stroke};
+    use resvg::usvg::TreeParsing;
+
+    fn text_scene() -> Scene {
+        Scene {
+            width: 200,
+            height: 60,
+            nodes: vec![
+                SceneNode::Rect(RectNode {
+                    radius: 8.0,
+                    stroke: Some(Stroke {
+                        color: Rgba([255, 255, 255, 128]),
+                        width: 1.0,
+                    }),
+                    ..RectNode::filled(0.0, 0.0, 200.0, 60.0, Rgba([30, 30, 30, 255]))
+                }),
+                SceneNode::Text(TextRun {
+                    font_family: "monospace".to_string(),
+                    font_size: 20.0,
+                    baseline: 40.0,
+                    color: Rgba([255, 0, 0, 255]),
+                    bold: false,
+                    italic: false,
+                    glyphs: "a<b a"
+                        .chars()
+                        .enumerate()
+                        .map(|(i, ch)| SceneGlyph {
+                            ch,
+                            x: 10.0 + i as f32 * 12.0,
+                            id: None,
+                            joined: false,
+                        })
+                        .collect(),
+                }),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_number_formatting() {
+        assert_eq!(num(10.0), "10");
+        assert_eq!(num(1.5), "1.5");
+        assert_eq!(num(-0.001), "0");
+        assert_eq!(num_precise(0.0048828125), "0.004883");
+    }
+
+    #[test]
+    fn test_outlined_text_uses_shared_glyph_paths() {
+        let renderer = SvgRenderer::new(Arc::new(FontManager::new().unwrap()));
+        let svg = renderer.render(&text_scene(), &SvgTextMode::Outlines).unwrap();
+
+        // "a" appears twice but is only defined once; whitespace has no outline
+        assert_eq!(svg.matches("<path id=\"glyph-").count(), 3);
+        assert_eq!(svg.matches("<use ").count(), 4);
+        assert!(!svg.contains("<text"));
+        assert!(svg.contains("rx=\"8\""));
+        assert!(svg.contains("stroke-opacity=\"0.501961\""));
+    }
+
+    #[test]
+    fn test_embedded_font_text_is_selectable() {
+        let renderer = SvgRenderer::new(Arc::new(FontManager::new().unwrap()));
+        let svg = renderer.render(&text_scene(), &SvgTextMode::EmbeddedFont).unwrap();
+
+        assert!(svg.contains("@font-face"));
+        assert!(svg.contains("data:font/woff2;base64,"));
+        assert!(svg.contains(">a&lt;b a</text>"));
+        assert!(svg.contains("x=\"10 22 34 46 58\""));
+    }
+
+    #[test]
+    fn test_missing_styles_are_synthesized() {
+        let renderer = SvgRenderer::new(Arc::new(FontManager::new().unwrap()));
+        let mut scene = text_scene();
+        let SceneNode::Text(ref mut run) = scene.nodes[1] else {
+            unreachable!()
+        };
+        run.font_family = "Definitely Not A Real Font".to_string();
+        run.bold = true;
+        run.italic = true;
+
+        // Outlines are sheared and stroked in the text color
+        let svg = renderer.render(&scene, &SvgTextMode::Outlines).unwrap();
+        assert!(svg.contains("stroke=\"#ff0000\" stroke-width=\"") && svg.contains("stroke-linejoin=\"round\""));
+        let skew = num_precise(SYNTHETIC_ITALIC_SKEW * 20.0 / 2048.0);
+        assert!(svg.contains(&format!("matrix(0.009766 0 {} -0.009766 10 40)", skew)));
+
+        // Embedded text asks the viewer to synthesize them
+        let svg = renderer.render(&scene, &SvgTextMode::EmbeddedFont).unwrap();
+        assert!(svg.contains("font-weight=\"bold\" font-style=\"italic\""));
+    }
+
+    #[test]
+    fn test_images_are_embedded_as_png() {
+        let renderer = SvgRenderer::new(Arc::new(FontManager::new().unwrap()));
+        let scene = Scene {
+            width: 40,
+            height: 40,
+            nodes: vec![SceneNode::Image(ImageNode {
+                x: 4.0,
+                y: 8.0,
+                width: 24.0,
+                height: 24.0,
+                image: Arc::new(image::RgbaImage::from_pixel(2, 2, Rgba([255, 200, 0, 255]))),
+            })],
+        };
+
+        let svg = renderer.render(&scene, &SvgTextMode::Outlines).unwrap();
+        assert!(svg.contains("<image x=\"4\" y=\"8\" width=\"24\" height=\"24\""));
+        assert!(svg.contains("href=\"data:image/png;base64,iVBORw0KGgo"));
+    }
+
+    #[test]
+    fn test_svg_output_parses() {
+        let renderer = SvgRenderer::new(Arc::new(FontManager::new().unwrap()));
+
+        for mode in [SvgTextMode::Outlines, SvgTextMode::EmbeddedFont] {
+            let svg = renderer.render(&text_scene(), &mode).unwrap();
+            let tree = resvg::usvg::Tree::from_str(&svg, &resvg::usvg::Options::default());
+            assert!(tree.is_ok(), "{:?} output failed to parse", mode);
+        }
+    }
+}
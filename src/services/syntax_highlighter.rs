@@ -1,11 +1,19 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
 use syntect::easy::HighlightLines;
-use syntect::highlighting::{Color, Style, Theme as SyntectTheme, ThemeSet};
+use syntect::highlighting::{
+    Color, FontStyle, ScopeSelectors, Style, StyleModifier, Theme as SyntectTheme, ThemeItem,
+    ThemeSet,
+};
 use syntect::parsing::{SyntaxSet, SyntaxReference};
 use syntect::util::LinesWithEndings;
-use crate::models::theme::{SyntaxColors, Theme};
+use crate::models::theme::{SyntaxColors, SyntaxFontStyles, Theme, TokenStyle};
 use crate::models::errors::AppError;
+use crate::services::diff::{DiffLineKind, UnifiedDiff};
+use crate::services::shell::{PromptSegment, SessionLine};
+use crate::services::graphemes::{self, grapheme_width};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Result of syntax highlighting operation
 #[derive(Debug, Clone)]
@@ -20,6 +28,10 @@ pub struct HighlightResult {
 pub struct HighlightedLine {
     pub segments: Vec<HighlightedSegment>,
     pub line_number: usize,
+    /// Text shown in the line number gutter instead of `line_number`
+    pub gutter: Option<String>,
+    /// True for the extra rows a wrapped line continues on
+    pub continuation: bool,
 }
 
 /// A segment of text with styling information
@@ -30,11 +42,138 @@ pub struct HighlightedSegment {
 }
 
 /// Style information for a text segment
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SegmentStyle {
     pub color: String,
     pub bold: bool,
     pub italic: bool,
+    pub underline: bool,
+    /// Fill drawn behind the text
+    pub background: Option<String>,
+    /// Blanked out by the redaction pass and drawn as a bar
+    pub redacted: bool,
+}
+
+/// Picks one color out of a theme's syntax colors
+type SyntaxColorFn = fn(&SyntaxColors) -> &str;
+
+/// Picks the matching font style out of a theme's syntax font styles
+type SyntaxStyleFn = fn(&SyntaxFontStyles) -> TokenStyle;
+
+/// TextMate scope selectors mapped to the SyntaxColors and SyntaxFontStyles fields that style them.
+/// More specific selectors (e.g. `keyword.operator`) win over broader ones (`keyword`).
+const SCOPE_MAPPINGS: &[(&str, SyntaxColorFn, SyntaxStyleFn)] = &[
+    ("comment, punctuation.definition.comment", |c| &c.comment, |s| s.comment),
+    (
+        "string, punctuation.definition.string, constant.character.escape",
+        |c| &c.string,
+        |s| s.string,
+    ),
+    ("constant.numeric, constant.language, constant.character", |c| &c.number, |s| s.number),
+    ("keyword, storage, variable.language", |c| &c.keyword, |s| s.keyword),
+    (
+        "keyword.operator, punctuation.separator, punctuation.accessor",
+        |c| &c.operator,
+        |s| s.operator,
+    ),
+    (
+        "entity.name.function, support.function, variable.function, meta.function-call entity.name",
+        |c| &c.function,
+        |s| s.function,
+    ),
+    (
+        "variable, variable.parameter, variable.other, entity.name.variable",
+        |c| &c.variable,
+        |s| s.variable,
+    ),
+    (
+        "entity.name.type, entity.name.class, entity.name.struct, entity.name.enum, \
+         entity.name.trait, entity.other.inherited-class, support.type, support.class",
+        |c| &c.type_name,
+        |s| s.type_name,
+    ),
+];
+
+impl HighlightedLine {
+    /// Sets the background of the characters in `columns`, splitting segments at its edges
+    pub fn set_background(&mut self, columns: std::ops::Range<usize>, color: &str) {
+        self.restyle(columns, |segment| segment.style.background = Some(color.to_string()));
+    }
+
+    /// Blanks out the characters in `columns` and marks them to be drawn as a redaction bar.
+    /// Each grapheme becomes an `x` per column it took, so the text never reaches the image
+    /// but the line keeps its layout.
+    pub fn redact(&mut self, columns: std::ops::Range<usize>) {
+        self.restyle(columns, |segment| {
+            segment.text = "x".repeat(graphemes::display_width(&segment.text));
+            segment.style.redacted = true;
+        });
+    }
+
+    /// Splits segments at the edges of the characters in `columns` and applies `change` to
+    /// the pieces inside them
+    fn restyle(&mut self, columns: std::ops::Range<usize>, change: impl Fn(&mut HighlightedSegment)) {
+        let mut segments = Vec::with_capacity(self.segments.len() + 2);
+        let mut column = 0;
+
+        for segment in self.segments.drain(..) {
+            let length = segment.text.chars().count();
+            let start = columns.start.clamp(column, column + length) - column;
+            let end = columns.end.clamp(column, column + length) - column;
+            column += length;
+
+            if start == end {
+                segments.push(segment);
+                continue;
+            }
+
+            let chars: Vec<char> = segment.text.chars().collect();
+            for (range, inside) in [(0..start, false), (start..end, true), (end..length, false)] {
+                if !range.is_empty() {
+                    let mut piece = HighlightedSegment {
+                        text: chars[range].iter().collect(),
+                        style: segment.style.clone(),
+                    };
+                    if inside {
+                        change(&mut piece);
+                    }
+                    segments.push(piece);
+                }
+            }
+        }
+
+        self.segments = segments;
+    }
+
+    /// Pads each tab with spaces up to the next tab stop, every `tab_width` columns.
+    /// The tab itself keeps the first column so whitespace rendering can tell it from spaces.
+    pub fn expand_tabs(&mut self, tab_width: usize) {
+        let tab_width = tab_width.max(1);
+        let mut column = 0;
+
+        for segment in &mut self.segments {
+            if !segment.text.contains('\t') {
+                column += graphemes::display_width(&segment.text);
+                continue;
+            }
+
+            let mut expanded = String::with_capacity(segment.text.len() + tab_width);
+            for grapheme in segment.text.graphemes(true) {
+                if grapheme == "\t" {
+                    let width = tab_width - column % tab_width;
+                    expanded.push('\t');
+                    expanded.push_str(&" ".repeat(width - 1));
+                    column += width;
+                } else {
+                    expanded.push_str(grapheme);
+                    if !matches!(grapheme, "\n" | "\r" | "\r\n") {
+                        column += grapheme_width(grapheme);
+                    }
+                }
+            }
+            segment.text = expanded;
+        }
+    }
 }
 
 /// Service for syntax highlighting with caching and theme integration
@@ -95,6 +234,86 @@ impl SyntaxHighlighter {
             highlighted_lines.push(HighlightedLine {
                 segments,
                 line_number: line_number + 1,
+                gutter: None,
+                continuation: false,
+            });
+        }
+
+        let total_lines = highlighted_lines.len();
+
+        Ok(HighlightResult {
+            highlighted_lines,
+            language: language.to_string(),
+            total_lines,
+        })
+    }
+
+    /// Highlights a parsed diff. The language comes from the file extension when it is known.
+    /// Headers are drawn in the comment color and the gutter shows each line's number in its file.
+    pub fn highlight_diff(
+        &self,
+        diff: &UnifiedDiff,
+        language: &str,
+        theme: &Theme,
+    ) -> Result<HighlightResult, AppError> {
+        let language = diff
+            .file_extension()
+            .filter(|extension| self.syntax_set.find_syntax_by_extension(extension).is_some())
+            .unwrap_or(language);
+
+        // Each side is highlighted as its own file so multi-line state like open strings carries over
+        let side = |excluded: DiffLineKind| -> Result<Vec<HighlightedLine>, AppError> {
+            let code: String = diff
+                .lines
+                .iter()
+                .filter(|line| {
+                    matches!(
+                        line.kind,
+                        DiffLineKind::Context | DiffLineKind::Added | DiffLineKind::Removed
+                    ) && line.kind != excluded
+                })
+                .map(|line| format!("{}\n", line.text))
+                .collect();
+            Ok(self.highlight_code(&code, language, theme)?.highlighted_lines)
+        };
+        let mut old_side = side(DiffLineKind::Added)?.into_iter();
+        let mut new_side = side(DiffLineKind::Removed)?.into_iter();
+
+        let header_style = SegmentStyle {
+            color: theme.syntax.comment.clone(),
+            bold: false,
+            italic: false,
+            underline: false,
+            background: None,
+            redacted: false,
+        };
+
+        let mut highlighted_lines = Vec::with_capacity(diff.lines.len());
+        for (index, line) in diff.lines.iter().enumerate() {
+            let segments = match line.kind {
+                DiffLineKind::Removed => old_side.next().map(|line| line.segments),
+                DiffLineKind::Added => new_side.next().map(|line| line.segments),
+                DiffLineKind::Context => {
+                    old_side.next();
+                    new_side.next().map(|line| line.segments)
+                }
+                DiffLineKind::HunkHeader | DiffLineKind::FileHeader => Some(vec![HighlightedSegment {
+                    text: line.text.clone(),
+                    style: header_style.clone(),
+                }]),
+            };
+
+            let gutter = line
+                .new_number
+                .or(line.old_number)
+                .map(|number| number.to_string())
+                .unwrap_or_default();
+
+            highlighted_lines.push(HighlightedLine {
+                segments: segments.unwrap_or_default(),
+                line_number: index + 1,
+                gutter: Some(gutter),
+                continuation: false,
             });
         }
 
@@ -107,6 +326,81 @@ impl SyntaxHighlighter {
         })
     }
 
+    /// Highlights a shell session: prompts are bold, commands are highlighted as shell script
+    /// and output keeps the plain text color. A prompt segment is shown before every prompt.
+    pub fn highlight_shell_session(
+        &self,
+        session: &[SessionLine],
+        segment: Option<&PromptSegment>,
+        theme: &Theme,
+    ) -> Result<HighlightResult, AppError> {
+        // Commands are highlighted together so continued lines and open quotes carry over
+        let commands: String = session
+            .iter()
+            .filter_map(|line| match line {
+                SessionLine::Command { command, .. } => Some(format!("{}\n", command)),
+                SessionLine::Output(_) => None,
+            })
+            .collect();
+        let mut commands = self.highlight_code(&commands, "sh", theme)?.highlighted_lines.into_iter();
+
+        let style = |color: &str, bold: bool| SegmentStyle {
+            color: color.to_string(),
+            bold,
+            italic: false,
+            underline: false,
+            background: None,
+            redacted: false,
+        };
+        let segment_parts = segment.map(PromptSegment::parts);
+
+        let mut highlighted_lines = Vec::with_capacity(session.len());
+        for (index, line) in session.iter().enumerate() {
+            let mut segments = Vec::new();
+            match line {
+                SessionLine::Command { prompt, .. } => {
+                    if let Some(prompt) = prompt {
+                        if let Some((ref identity, ref path)) = segment_parts {
+                            segments.push(HighlightedSegment {
+                                text: identity.clone(),
+                                style: style(&theme.syntax.string, true),
+                            });
+                            segments.push(HighlightedSegment {
+                                text: path.clone(),
+                                style: style(&theme.syntax.function, true),
+                            });
+                        }
+                        segments.push(HighlightedSegment {
+                            text: prompt.clone(),
+                            style: style(&theme.syntax.keyword, true),
+                        });
+                    }
+                    segments.extend(commands.next().map(|line| line.segments).unwrap_or_default());
+                }
+                SessionLine::Output(text) => segments.push(HighlightedSegment {
+                    text: text.clone(),
+                    style: style(&theme.text_color(), false),
+                }),
+            }
+            segments.retain(|segment| !segment.text.is_empty());
+
+            highlighted_lines.push(HighlightedLine {
+                segments,
+                line_number: index + 1,
+                gutter: None,
+                continuation: false,
+            });
+        }
+
+        let total_lines = highlighted_lines.len();
+
+        Ok(HighlightResult {
+            highlighted_lines,
+            language: "sh".to_string(),
+            total_lines,
+        })
+    }
+
     /// Gets syntax reference for a language with caching
     fn get_syntax_for_language(&self, language: &str) -> Arc<SyntaxReference> {
         // Check cache first
@@ -137,26 +431,58 @@ impl SyntaxHighlighter {
     }
 
     /// Creates a syntect theme from our custom theme structure
-    fn create_syntect_theme_from_custom(&self, _theme: &Theme) -> Result<SyntectTheme, AppError> {
-        // For now, use a default theme - we can enhance this later
-        // The complex theme customization can be implemented in the image generation phase
-        let theme = self
+    fn create_syntect_theme_from_custom(&self, theme: &Theme) -> Result<SyntectTheme, AppError> {
+        // Start from a base theme so non-syntax settings (selection, gutter, ...) have sane values
+        let mut syntect_theme = self
             .theme_set
             .themes
             .get("base16-ocean.dark")
             .or_else(|| self.theme_set.themes.values().next())
-            .ok_or_else(|| AppError::SyntaxHighlightingError { message: "No base theme available".to_string() })?;
+            .ok_or_else(|| AppError::SyntaxHighlightingError { message: "No base theme available".to_string() })?
+            .clone();
+
+        syntect_theme.name = Some(theme.name.clone());
+        // Unscoped text (identifiers, punctuation) uses the theme's plain text color
+        syntect_theme.settings.foreground = Some(self.parse_color(&theme.text_color())?);
+        syntect_theme.settings.background = self.parse_color(&theme.background.primary).ok();
 
-        Ok(theme.clone())
+        self.update_theme_scopes(&mut syntect_theme, theme)?;
+
+        Ok(syntect_theme)
     }
 
-    /// Updates theme scopes with custom syntax colors (simplified version)
-    fn update_theme_scopes(&self, _theme: &mut SyntectTheme, _syntax_colors: &SyntaxColors) -> Result<(), AppError> {
-        // For now, we'll handle color customization in the image generation phase
-        // This allows us to focus on getting the basic syntax highlighting working first
+    /// Replaces the theme's scope rules with ones derived from our syntax colors and font styles
+    fn update_theme_scopes(&self, theme: &mut SyntectTheme, custom: &Theme) -> Result<(), AppError> {
+        let mut scopes = Vec::with_capacity(SCOPE_MAPPINGS.len());
+
+        for (selector, color_of, style_of) in SCOPE_MAPPINGS {
+            let scope = ScopeSelectors::from_str(selector).map_err(|e| AppError::SyntaxHighlightingError {
+                message: format!("Invalid scope selector '{}': {:?}", selector, e),
+            })?;
+
+            scopes.push(ThemeItem {
+                scope,
+                style: StyleModifier {
+                    foreground: Some(self.parse_color(color_of(&custom.syntax))?),
+                    background: None,
+                    font_style: Some(Self::font_style(style_of(&custom.font_styles))),
+                },
+            });
+        }
+
+        theme.scopes = scopes;
         Ok(())
     }
 
+    /// Converts a token style to syntect's font style flags
+    fn font_style(style: TokenStyle) -> FontStyle {
+        let mut font_style = FontStyle::empty();
+        font_style.set(FontStyle::BOLD, style.bold);
+        font_style.set(FontStyle::ITALIC, style.italic);
+        font_style.set(FontStyle::UNDERLINE, style.underline);
+        font_style
+    }
+
     /// Parses a hex color string to syntect Color
     fn parse_color(&self, color_str: &str) -> Result<Color, AppError> {
         if !color_str.starts_with('#') {
@@ -208,8 +534,11 @@ impl SyntaxHighlighter {
         
         SegmentStyle {
             color,
-            bold: style.font_style.contains(syntect::highlighting::FontStyle::BOLD),
-            italic: style.font_style.contains(syntect::highlighting::FontStyle::ITALIC),
+            bold: style.font_style.contains(FontStyle::BOLD),
+            italic: style.font_style.contains(FontStyle::ITALIC),
+            underline: style.font_style.contains(FontStyle::UNDERLINE),
+            background: None,
+            redacted: false,
         }
     }
 
@@ -270,6 +599,32 @@ impl Default for SyntaxHighlighter {
     }
 }
 
+/// Splits text into words, runs of whitespace and single punctuation characters.
+/// Used to find word boundaries when wrapping lines and comparing changed lines.
+pub fn split_tokens(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, ch)) = chars.next() {
+        let same_class = |next: char| {
+            (ch.is_alphanumeric() || ch == '_') && (next.is_alphanumeric() || next == '_')
+                || ch.is_whitespace() && next.is_whitespace()
+        };
+
+        match chars.peek() {
+            Some(&(_, next)) if same_class(next) => continue,
+            Some(&(next_index, _)) => {
+                tokens.push(&text[start..next_index]);
+                start = next_index;
+            }
+            None => tokens.push(&text[start..index + ch.len_utf8()]),
+        }
+    }
+
+    tokens
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -330,6 +685,101 @@ mod tests {
         assert_eq!(highlight_result.language, "unknown-language");
     }
 
+    #[test]
+    fn test_highlight_diff_uses_file_language_and_numbers() {
+        let highlighter = SyntaxHighlighter::new().unwrap();
+        let theme = Theme::default_dark();
+
+        let diff = crate::services::diff::parse_unified_diff(
+            "--- a/app.py\n+++ b/app.py\n@@ -4,2 +4,2 @@\n-x = 1\n+x = \"one\"\n print(x)\n",
+        )
+        .unwrap();
+        let result = highlighter.highlight_diff(&diff, "Rust", &theme).unwrap();
+
+        assert_eq!(result.language, "py");
+        let gutters: Vec<&str> = result
+            .highlighted_lines
+            .iter()
+            .map(|line| line.gutter.as_deref().unwrap())
+            .collect();
+        assert_eq!(gutters, vec!["", "4", "4", "5"]);
+
+        // The hunk header is one comment-colored segment and the added line is highlighted as Python
+        let header = &result.highlighted_lines[0].segments;
+        assert_eq!(header.len(), 1);
+        assert_eq!(header[0].style.color, theme.syntax.comment);
+        assert!(result.highlighted_lines[2]
+            .segments
+            .iter()
+            .any(|segment| segment.text.contains("one") && segment.style.color == theme.syntax.string));
+    }
+
+    #[test]
+    fn test_highlight_shell_session_styles_prompts_commands_and_output() {
+        let highlighter = SyntaxHighlighter::new().unwrap();
+        let theme = Theme::default_dark();
+        let options = crate::services::shell::ShellOptions::default();
+        let session = crate::services::shell::parse_session("$ echo \"hi\"\nhi", &options).unwrap();
+        let segment = PromptSegment {
+            user: "dev".to_string(),
+            host: "box".to_string(),
+            path: "~".to_string(),
+        };
+
+        let result = highlighter.highlight_shell_session(&session, Some(&segment), &theme).unwrap();
+        let command = &result.highlighted_lines[0].segments;
+        let texts: Vec<&str> = command.iter().take(3).map(|segment| segment.text.as_str()).collect();
+        assert_eq!(texts, vec!["dev@box", ":~", "$ "]);
+        assert!(command[2].style.bold && command[2].style.color == theme.syntax.keyword);
+        assert!(command
+            .iter()
+            .any(|segment| segment.text.contains("hi") && segment.style.color == theme.syntax.string));
+
+        let output = &result.highlighted_lines[1].segments;
+        assert_eq!(output.len(), 1);
+        assert_eq!((output[0].text.as_str(), output[0].style.color.as_str()), ("hi", theme.text_color().as_str()));
+    }
+
+    #[test]
+    fn test_set_background_splits_segments() {
+        let highlighter = SyntaxHighlighter::new().unwrap();
+        let theme = Theme::default_dark();
+
+        let mut line = highlighter
+            .highlight_code("let total = 40 + 2;", "Rust", &theme)
+            .unwrap()
+            .highlighted_lines
+            .remove(0);
+        line.set_background(4..9, "#ff000080");
+
+        let text: String = line.segments.iter().map(|segment| segment.text.as_str()).collect();
+        assert_eq!(text, "let total = 40 + 2;");
+
+        let highlighted: String = line
+            .segments
+            .iter()
+            .filter(|segment| segment.style.background.as_deref() == Some("#ff000080"))
+            .map(|segment| segment.text.as_str())
+            .collect();
+        assert_eq!(highlighted, "total");
+    }
+
+    #[test]
+    fn test_tabs_expand_to_tab_stops() {
+        let highlighter = SyntaxHighlighter::new().unwrap();
+        let theme = Theme::default_dark();
+
+        let mut lines = highlighter
+            .highlight_code("\tx := 1\t// 漢\tok\n", "Go", &theme)
+            .unwrap()
+            .highlighted_lines;
+        lines[0].expand_tabs(4);
+
+        let text: String = lines[0].segments.iter().map(|segment| segment.text.as_str()).collect();
+        assert_eq!(text, "\t   x := 1\t // 漢\t  ok\n");
+        assert_eq!(graphemes::display_width(&text), 22);
+    }
+
     #[test]
     fn test_color_parsing() {
         let highlighter = SyntaxHighlighter::new().unwrap();
@@ -349,4 +799,71 @@ mod tests {
         // Test invalid color
         assert!(highlighter.parse_color("invalid").is_err());
     }
+
+    fn segment_color(result: &HighlightResult, text: &str) -> Option<String> {
+        result
+            .highlighted_lines
+            .iter()
+            .flat_map(|line| line.segments.iter())
+            .find(|segment| segment.text.contains(text))
+            .map(|segment| segment.style.color.clone())
+    }
+
+    #[test]
+    fn test_theme_syntax_colors_applied() {
+        let highlighter = SyntaxHighlighter::new().unwrap();
+        let theme = Theme::default_dark();
+
+        let code = "// note\nfn main() {\n    let x = 42;\n    let s = \"hi\";\n}";
+        let result = highlighter.highlight_code(code, "Rust", &theme).unwrap();
+
+        assert_eq!(segment_color(&result, "note").as_deref(), Some(theme.syntax.comment.as_str()));
+        assert_eq!(segment_color(&result, "42").as_deref(), Some(theme.syntax.number.as_str()));
+        assert_eq!(segment_color(&result, "hi").as_deref(), Some(theme.syntax.string.as_str()));
+        assert_eq!(segment_color(&result, "main").as_deref(), Some(theme.syntax.function.as_str()));
+    }
+
+    #[test]
+    fn test_different_themes_produce_different_colors() {
+        let highlighter = SyntaxHighlighter::new().unwrap();
+        let dark = Theme::default_dark();
+        let mut custom = Theme::default_dark();
+        custom.syntax.keyword = "#ff0000".to_string();
+
+        let code = "fn main() {}";
+        let dark_result = highlighter.highlight_code(code, "Rust", &dark).unwrap();
+        let custom_result = highlighter.highlight_code(code, "Rust", &custom).unwrap();
+
+        assert_eq!(segment_color(&dark_result, "fn").as_deref(), Some(dark.syntax.keyword.as_str()));
+        assert_eq!(segment_color(&custom_result, "fn").as_deref(), Some("#ff0000"));
+    }
+
+    #[test]
+    fn test_plain_text_uses_text_color_not_operator() {
+        let highlighter = SyntaxHighlighter::new().unwrap();
+        let mut theme = Theme::default_dark();
+        theme.syntax.operator = "#ff0000".to_string();
+        theme.syntax.text = None;
+
+        let code = "plain words";
+        let result = highlighter.highlight_code(code, "unknown-language", &theme).unwrap();
+        assert_eq!(segment_color(&result, "plain").as_deref(), Some("#d4d4d4"));
+
+        theme.background.primary = "#fafafa".to_string();
+        let result = highlighter.highlight_code(code, "unknown-language", &theme).unwrap();
+        assert_eq!(segment_color(&result, "plain").as_deref(), Some("#24292e"));
+
+        theme.syntax.text = Some("#abcdef".to_string());
+        let result = highlighter.highlight_code(code, "unknown-language", &theme).unwrap();
+        assert_eq!(segment_color(&result, "plain").as_deref(), Some("#abcdef"));
+    }
+
+    #[test]
+    fn test_invalid_syntax_color_is_rejected() {
+        let highlighter = SyntaxHighlighter::new().unwrap();
+        let mut theme = Theme::default_dark();
+        theme.syntax.string = "not-a-color".to_string();
+
+        assert!(highlighter.highlight_code("let a = 1;", "Rust", &theme).is_err());
+    }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::models::theme::{Theme, BackgroundStyle, BackgroundType, SyntaxColors, WindowStyle, WindowStyleType, TypographyStyle};
+use crate::models::theme::{deserialize_shadow_change, Theme, AnsiPalette, BackgroundImage, BackgroundStyle, BackgroundType, GlassStyle, GradientKind, GradientStyle, ImageFit, PatternKind, PatternStyle, SyntaxColors, SyntaxFontStyles, TokenStyle, WindowStyle, WindowStyleType, WindowTab, TypographyStyle, ShadowStyle, BorderStyle};
 use crate::models::errors::AppError;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -60,7 +60,11 @@ impl ThemeManager {
                 primary: "#000000".to_string(),
                 secondary: None,
                 opacity: 1.0,
+                gradient: None,
+                pattern: None,
+                image: None,
             },
+            backdrop: None,
             syntax: SyntaxColors {
                 keyword: "#00ffff".to_string(),
                 string: "#ffff00".to_string(),
@@ -70,14 +74,20 @@ impl ThemeManager {
                 function: "#ffaa00".to_string(),
                 variable: "#00aaff".to_string(),
                 type_name: "#ff6600".to_string(),
+                text: Some("#ffffff".to_string()),
             },
+            font_styles: SyntaxFontStyles::default(),
+            ansi_colors: AnsiPalette::default(),
             window: WindowStyle {
                 style_type: WindowStyleType::Clean,
                 show_title_bar: false,
                 title: None,
                 show_controls: false,
                 border_radius: 0.0,
-                shadow: false,
+                shadow: None,
+                border: None,
+                glass: None,
+                tabs: Vec::new(),
             },
             typography: TypographyStyle {
                 font_family: "Monaco".to_string(),
@@ -85,6 +95,10 @@ impl ThemeManager {
                 line_height: 1.6,
                 letter_spacing: 0.5,
                 show_line_numbers: true,
+                font_features: TypographyStyle::default_font_features(),
+                tab_width: TypographyStyle::default_tab_width(),
+                render_whitespace: false,
+                indent_guides: false,
             },
         }
     }
@@ -99,7 +113,11 @@ impl ThemeManager {
                 primary: "#1e1e1e".to_string(),
                 secondary: None,
                 opacity: 1.0,
+                gradient: None,
+                pattern: None,
+                image: None,
             },
+            backdrop: None,
             syntax: SyntaxColors {
                 keyword: "#569cd6".to_string(),
                 string: "#ce9178".to_string(),
@@ -109,14 +127,20 @@ impl ThemeManager {
                 function: "#dcdcaa".to_string(),
                 variable: "#9cdcfe".to_string(),
                 type_name: "#4ec9b0".to_string(),
+                text: Some("#d4d4d4".to_string()),
             },
+            font_styles: SyntaxFontStyles::default(),
+            ansi_colors: AnsiPalette::default(),
             window: WindowStyle {
                 style_type: WindowStyleType::MacOS,
                 show_title_bar: true,
                 title: Some("Visual Studio Code".to_string()),
                 show_controls: true,
                 border_radius: 6.0,
-                shadow: true,
+                shadow: Some(ShadowStyle::default()),
+                border: None,
+                glass: None,
+                tabs: Vec::new(),
             },
             typography: TypographyStyle {
                 font_family: "Consolas".to_string(),
@@ -124,6 +148,10 @@ impl ThemeManager {
                 line_height: 1.5,
                 letter_spacing: 0.0,
                 show_line_numbers: true,
+                font_features: TypographyStyle::default_font_features(),
+                tab_width: TypographyStyle::default_tab_width(),
+                render_whitespace: false,
+                indent_guides: false,
             },
         }
     }
@@ -138,7 +166,11 @@ impl ThemeManager {
                 primary: "#272822".to_string(),
                 secondary: None,
                 opacity: 1.0,
+                gradient: None,
+                pattern: None,
+                image: None,
             },
+            backdrop: None,
             syntax: SyntaxColors {
                 keyword: "#f92672".to_string(),
                 string: "#e6db74".to_string(),
@@ -148,14 +180,24 @@ impl ThemeManager {
                 function: "#a6e22e".to_string(),
                 variable: "#f8f8f2".to_string(),
                 type_name: "#66d9ef".to_string(),
+                text: Some("#f8f8f2".to_string()),
             },
+            font_styles: SyntaxFontStyles {
+                keyword: TokenStyle::italic(),
+                comment: TokenStyle::italic(),
+                ..Default::default()
+            },
+            ansi_colors: AnsiPalette::default(),
             window: WindowStyle {
                 style_type: WindowStyleType::Terminal,
                 show_title_bar: true,
                 title: Some("Monokai".to_string()),
                 show_controls: true,
                 border_radius: 4.0,
-                shadow: true,
+                shadow: Some(ShadowStyle::default()),
+                border: None,
+                glass: None,
+                tabs: Vec::new(),
             },
             typography: TypographyStyle {
                 font_family: "Monaco".to_string(),
@@ -163,6 +205,10 @@ impl ThemeManager {
                 line_height: 1.4,
                 letter_spacing: 0.0,
                 show_line_numbers: false,
+                font_features: TypographyStyle::default_font_features(),
+                tab_width: TypographyStyle::default_tab_width(),
+                render_whitespace: false,
+                indent_guides: false,
             },
         }
     }
@@ -177,7 +223,11 @@ impl ThemeManager {
                 primary: "#ffffff".to_string(),
                 secondary: None,
                 opacity: 1.0,
+                gradient: None,
+                pattern: None,
+                image: None,
             },
+            backdrop: None,
             syntax: SyntaxColors {
                 keyword: "#d73a49".to_string(),
                 string: "#032f62".to_string(),
@@ -187,14 +237,23 @@ impl ThemeManager {
                 function: "#6f42c1".to_string(),
                 variable: "#e36209".to_string(),
                 type_name: "#005cc5".to_string(),
+                text: Some("#24292e".to_string()),
             },
+            font_styles: SyntaxFontStyles::default(),
+            ansi_colors: AnsiPalette::light(),
             window: WindowStyle {
                 style_type: WindowStyleType::Clean,
                 show_title_bar: true,
                 title: Some("GitHub".to_string()),
                 show_controls: false,
                 border_radius: 6.0,
-                shadow: false,
+                shadow: None,
+                border: Some(BorderStyle {
+                    width: 1.0,
+                    color: "#d0d7de".to_string(),
+                }),
+                glass: None,
+                tabs: Vec::new(),
             },
             typography: TypographyStyle {
                 font_family: "SFMono-Regular".to_string(),
@@ -202,6 +261,10 @@ impl ThemeManager {
                 line_height: 1.45,
                 letter_spacing: 0.0,
                 show_line_numbers: true,
+                font_features: TypographyStyle::default_font_features(),
+                tab_width: TypographyStyle::default_tab_width(),
+                render_whitespace: false,
+                indent_guides: false,
             },
         }
     }
@@ -216,7 +279,11 @@ impl ThemeManager {
                 primary: "#282a36".to_string(),
                 secondary: Some("#44475a".to_string()),
                 opacity: 1.0,
+                gradient: None,
+                pattern: None,
+                image: None,
             },
+            backdrop: None,
             syntax: SyntaxColors {
                 keyword: "#ff79c6".to_string(),
                 string: "#f1fa8c".to_string(),
@@ -226,14 +293,20 @@ impl ThemeManager {
                 function: "#50fa7b".to_string(),
                 variable: "#f8f8f2".to_string(),
                 type_name: "#8be9fd".to_string(),
+                text: Some("#f8f8f2".to_string()),
             },
+            font_styles: SyntaxFontStyles::default(),
+            ansi_colors: AnsiPalette::dracula(),
             window: WindowStyle {
                 style_type: WindowStyleType::MacOS,
                 show_title_bar: true,
                 title: Some("Dracula".to_string()),
                 show_controls: true,
                 border_radius: 8.0,
-                shadow: true,
+                shadow: Some(ShadowStyle::default()),
+                border: None,
+                glass: None,
+                tabs: Vec::new(),
             },
             typography: TypographyStyle {
                 font_family: "Fira Code".to_string(),
@@ -241,6 +314,10 @@ impl ThemeManager {
                 line_height: 1.5,
                 letter_spacing: 0.0,
                 show_line_numbers: false,
+                font_features: TypographyStyle::default_font_features(),
+                tab_width: TypographyStyle::default_tab_width(),
+                render_whitespace: false,
+                indent_guides: false,
             },
         }
     }
@@ -255,7 +332,11 @@ impl ThemeManager {
                 primary: "#002b36".to_string(),
                 secondary: None,
                 opacity: 1.0,
+                gradient: None,
+                pattern: None,
+                image: None,
             },
+            backdrop: None,
             syntax: SyntaxColors {
                 keyword: "#859900".to_string(),
                 string: "#2aa198".to_string(),
@@ -265,14 +346,20 @@ impl ThemeManager {
                 function: "#268bd2".to_string(),
                 variable: "#b58900".to_string(),
                 type_name: "#cb4b16".to_string(),
+                text: Some("#839496".to_string()),
             },
+            font_styles: SyntaxFontStyles::default(),
+            ansi_colors: AnsiPalette::solarized(),
             window: WindowStyle {
                 style_type: WindowStyleType::Terminal,
                 show_title_bar: true,
                 title: Some("Solarized".to_string()),
                 show_controls: true,
                 border_radius: 4.0,
-                shadow: false,
+                shadow: None,
+                border: None,
+                glass: None,
+                tabs: Vec::new(),
             },
             typography: TypographyStyle {
                 font_family: "Source Code Pro".to_string(),
@@ -280,6 +367,10 @@ impl ThemeManager {
                 line_height: 1.4,
                 letter_spacing: 0.0,
                 show_line_numbers: true,
+                font_features: TypographyStyle::default_font_features(),
+                tab_width: TypographyStyle::default_tab_width(),
+                render_whitespace: false,
+                indent_guides: false,
             },
         }
     }
@@ -294,7 +385,11 @@ impl ThemeManager {
                 primary: "#fdf6e3".to_string(),
                 secondary: None,
                 opacity: 1.0,
+                gradient: None,
+                pattern: None,
+                image: None,
             },
+            backdrop: None,
             syntax: SyntaxColors {
                 keyword: "#859900".to_string(),
                 string: "#2aa198".to_string(),
@@ -304,14 +399,20 @@ impl ThemeManager {
                 function: "#268bd2".to_string(),
                 variable: "#b58900".to_string(),
                 type_name: "#cb4b16".to_string(),
+                text: Some("#657b83".to_string()),
             },
+            font_styles: SyntaxFontStyles::default(),
+            ansi_colors: AnsiPalette::solarized(),
             window: WindowStyle {
                 style_type: WindowStyleType::Clean,
                 show_title_bar: false,
                 title: None,
                 show_controls: false,
                 border_radius: 6.0,
-                shadow: true,
+                shadow: Some(ShadowStyle::default()),
+                border: None,
+                glass: None,
+                tabs: Vec::new(),
             },
             typography: TypographyStyle {
                 font_family: "Source Code Pro".to_string(),
@@ -319,6 +420,10 @@ impl ThemeManager {
                 line_height: 1.4,
                 letter_spacing: 0.0,
                 show_line_numbers: false,
+                font_features: TypographyStyle::default_font_features(),
+                tab_width: TypographyStyle::default_tab_width(),
+                render_whitespace: false,
+                indent_guides: false,
             },
         }
     }    
@@ -346,7 +451,7 @@ impl ThemeManager {
     /// Adds a custom theme to the manager
     pub async fn add_theme(&self, theme: Theme) -> Result<(), AppError> {
         // Validate the theme before adding
-        theme.validate().map_err(|e| AppError::theme_error(e))?;
+        theme.validate().map_err(AppError::theme_error)?;
         
         let mut themes = self.themes.write().await;
         themes.insert(theme.id.clone(), theme);
@@ -356,7 +461,7 @@ impl ThemeManager {
     /// Updates an existing theme
     pub async fn update_theme(&self, theme: Theme) -> Result<(), AppError> {
         // Validate the theme before updating
-        theme.validate().map_err(|e| AppError::theme_error(e))?;
+        theme.validate().map_err(AppError::theme_error)?;
         
         let mut themes = self.themes.write().await;
         if themes.contains_key(&theme.id) {
@@ -389,13 +494,13 @@ impl ThemeManager {
     
     /// Validates a theme configuration
     pub fn validate_theme(&self, theme: &Theme) -> Result<(), AppError> {
-        theme.validate().map_err(|e| AppError::theme_error(e))
+        theme.validate().map_err(AppError::theme_error)
     }
     
     /// Gets the default theme (dark theme)
     pub async fn get_default_theme(&self) -> Theme {
         self.get_theme("default-dark").await
-            .unwrap_or_else(|| Theme::default_dark())
+            .unwrap_or_else(Theme::default_dark)
     }
     
     /// Checks if a theme exists
@@ -482,9 +587,31 @@ impl ThemeManager {
             self.apply_background_customization(&mut customized_theme.background, background)?;
         }
         
+        if let Some(backdrop) = customizations.backdrop {
+            // Themes without a backdrop start from a neutral solid one
+            let base = customized_theme.backdrop.get_or_insert_with(|| BackgroundStyle {
+                bg_type: BackgroundType::Solid,
+                primary: "#abb8c3".to_string(),
+                secondary: None,
+                opacity: 1.0,
+                gradient: None,
+                pattern: None,
+                image: None,
+            });
+            self.apply_background_customization(base, backdrop)?;
+        }
+        
         if let Some(syntax) = customizations.syntax {
             self.apply_syntax_customization(&mut customized_theme.syntax, syntax)?;
         }
+
+        if let Some(font_styles) = customizations.font_styles {
+            customized_theme.font_styles = font_styles;
+        }
+
+        if let Some(ansi_colors) = customizations.ansi_colors {
+            customized_theme.ansi_colors = ansi_colors;
+        }
         
         if let Some(window) = customizations.window {
             self.apply_window_customization(&mut customized_theme.window, window)?;
@@ -495,7 +622,7 @@ impl ThemeManager {
         }
         
         // Validate the customized theme
-        customized_theme.validate().map_err(|e| AppError::theme_error(e))?;
+        customized_theme.validate().map_err(AppError::theme_error)?;
         
         Ok(customized_theme)
     }
@@ -525,11 +652,28 @@ impl ThemeManager {
         }
         
         if let Some(opacity) = customization.opacity {
-            if opacity < 0.0 || opacity > 1.0 {
+            if !(0.0..=1.0).contains(&opacity) {
                 return Err(AppError::theme_error("Opacity must be between 0.0 and 1.0".to_string()));
             }
             background.opacity = opacity;
         }
+
+        if let Some(gradient) = customization.gradient {
+            gradient.validate().map_err(AppError::theme_error)?;
+            background.gradient = Some(gradient);
+        }
+
+        if let Some(pattern) = customization.pattern {
+            pattern.validate().map_err(AppError::theme_error)?;
+            background.pattern = Some(pattern);
+        }
+
+        if let Some(image) = customization.image {
+            image.validate().map_err(AppError::theme_error)?;
+            background.image = Some(image);
+        }
+
+        background.validate_image().map_err(AppError::theme_error)?;
         
         Ok(())
     }
@@ -595,6 +739,13 @@ impl ThemeManager {
             }
             syntax.type_name = type_name;
         }
+
+        if let Some(text) = customization.text {
+            if !Theme::is_valid_color(&text) {
+                return Err(AppError::theme_error(format!("Invalid text color: {}", text)));
+            }
+            syntax.text = Some(text);
+        }
         
         Ok(())
     }
@@ -621,6 +772,11 @@ impl ThemeManager {
             window.show_controls = show_controls;
         }
         
+        if let Some(tabs) = customization.tabs {
+            WindowTab::validate_tabs(&tabs).map_err(AppError::theme_error)?;
+            window.tabs = tabs;
+        }
+        
         if let Some(border_radius) = customization.border_radius {
             if border_radius < 0.0 {
                 return Err(AppError::theme_error("Border radius cannot be negative".to_string()));
@@ -628,9 +784,22 @@ impl ThemeManager {
             window.border_radius = border_radius;
         }
         
+        if let Some(border) = customization.border {
+            border.validate().map_err(AppError::theme_error)?;
+            window.border = Some(border);
+        }
+        
         if let Some(shadow) = customization.shadow {
+            if let Some(ref shadow) = shadow {
+                shadow.validate().map_err(AppError::theme_error)?;
+            }
             window.shadow = shadow;
         }
+
+        if let Some(glass) = customization.glass {
+            glass.validate().map_err(AppError::theme_error)?;
+            window.glass = Some(glass);
+        }
         
         Ok(())
     }
@@ -666,6 +835,34 @@ impl ThemeManager {
         if let Some(show_line_numbers) = customization.show_line_numbers {
             typography.show_line_numbers = show_line_numbers;
         }
+
+        if let Some(font_features) = customization.font_features {
+            if let Some(feature) = font_features
+                .iter()
+                .find(|feature| !TypographyStyle::is_supported_feature(feature))
+            {
+                return Err(AppError::theme_error(format!("Unsupported font feature: {}", feature)));
+            }
+            typography.font_features = font_features;
+        }
+
+        if let Some(tab_width) = customization.tab_width {
+            if !(1..=TypographyStyle::MAX_TAB_WIDTH).contains(&tab_width) {
+                return Err(AppError::theme_error(format!(
+                    "Tab width must be between 1 and {}",
+                    TypographyStyle::MAX_TAB_WIDTH
+                )));
+            }
+            typography.tab_width = tab_width;
+        }
+
+        if let Some(render_whitespace) = customization.render_whitespace {
+            typography.render_whitespace = render_whitespace;
+        }
+
+        if let Some(indent_guides) = customization.indent_guides {
+            typography.indent_guides = indent_guides;
+        }
         
         Ok(())
     }
@@ -682,7 +879,11 @@ impl ThemeManager {
                         primary: Some("#000000".to_string()),
                         secondary: None,
                         opacity: Some(1.0),
+                        gradient: None,
+                        pattern: None,
+                        image: None,
                     }),
+                    backdrop: None,
                     syntax: Some(SyntaxCustomization {
                         keyword: Some("#00ffff".to_string()),
                         string: Some("#ffff00".to_string()),
@@ -692,14 +893,20 @@ impl ThemeManager {
                         function: Some("#ffaa00".to_string()),
                         variable: Some("#00aaff".to_string()),
                         type_name: Some("#ff6600".to_string()),
+                        text: None,
                     }),
+                    font_styles: None,
+                    ansi_colors: None,
                     window: Some(WindowCustomization {
                         style_type: Some(WindowStyleType::Clean),
                         show_title_bar: Some(false),
                         title: None,
                         show_controls: Some(false),
                         border_radius: Some(0.0),
-                        shadow: Some(false),
+                        shadow: Some(None),
+                        border: None,
+                        glass: None,
+                        tabs: None,
                     }),
                     typography: Some(TypographyCustomization {
                         font_family: Some("Monaco".to_string()),
@@ -707,6 +914,10 @@ impl ThemeManager {
                         line_height: Some(1.6),
                         letter_spacing: Some(0.5),
                         show_line_numbers: Some(true),
+                        font_features: None,
+                        tab_width: None,
+                        render_whitespace: None,
+                        indent_guides: None,
                     }),
                 };
                 self.customize_theme("default-dark", customization).await
@@ -720,7 +931,11 @@ impl ThemeManager {
                         primary: Some("#fafafa".to_string()),
                         secondary: None,
                         opacity: Some(1.0),
+                        gradient: None,
+                        pattern: None,
+                        image: None,
                     }),
+                    backdrop: None,
                     syntax: Some(SyntaxCustomization {
                         keyword: Some("#0066cc".to_string()),
                         string: Some("#008000".to_string()),
@@ -730,14 +945,20 @@ impl ThemeManager {
                         function: Some("#6600cc".to_string()),
                         variable: Some("#000080".to_string()),
                         type_name: Some("#cc0066".to_string()),
+                        text: None,
                     }),
+                    font_styles: None,
+                    ansi_colors: None,
                     window: Some(WindowCustomization {
                         style_type: Some(WindowStyleType::Clean),
                         show_title_bar: Some(false),
                         title: None,
                         show_controls: Some(false),
                         border_radius: Some(4.0),
-                        shadow: Some(false),
+                        shadow: Some(None),
+                        border: None,
+                        glass: None,
+                        tabs: None,
                     }),
                     typography: Some(TypographyCustomization {
                         font_family: Some("SF Mono".to_string()),
@@ -745,6 +966,10 @@ impl ThemeManager {
                         line_height: Some(1.4),
                         letter_spacing: Some(0.0),
                         show_line_numbers: Some(false),
+                        font_features: None,
+                        tab_width: None,
+                        render_whitespace: None,
+                        indent_guides: None,
                     }),
                 };
                 self.customize_theme("default-light", customization).await
@@ -758,7 +983,11 @@ impl ThemeManager {
                         primary: Some("#0a0a0a".to_string()),
                         secondary: Some("#1a1a2e".to_string()),
                         opacity: Some(1.0),
+                        gradient: None,
+                        pattern: None,
+                        image: None,
                     }),
+                    backdrop: None,
                     syntax: Some(SyntaxCustomization {
                         keyword: Some("#ff0080".to_string()),
                         string: Some("#00ff80".to_string()),
@@ -768,14 +997,26 @@ impl ThemeManager {
                         function: Some("#80ff00".to_string()),
                         variable: Some("#00ffff".to_string()),
                         type_name: Some("#ff4080".to_string()),
+                        text: None,
                     }),
+                    font_styles: None,
+                    ansi_colors: None,
                     window: Some(WindowCustomization {
                         style_type: Some(WindowStyleType::Terminal),
                         show_title_bar: Some(true),
                         title: Some("Neon Terminal".to_string()),
                         show_controls: Some(true),
                         border_radius: Some(6.0),
-                        shadow: Some(true),
+                        shadow: Some(Some(ShadowStyle {
+                            offset_x: 0.0,
+                            offset_y: 0.0,
+                            blur_radius: 32.0,
+                            spread: 2.0,
+                            color: "#ff008066".to_string(),
+                        })),
+                        border: None,
+                        glass: None,
+                        tabs: None,
                     }),
                     typography: Some(TypographyCustomization {
                         font_family: Some("JetBrains Mono".to_string()),
@@ -783,6 +1024,10 @@ impl ThemeManager {
                         line_height: Some(1.5),
                         letter_spacing: Some(0.2),
                         show_line_numbers: Some(true),
+                        font_features: None,
+                        tab_width: None,
+                        render_whitespace: None,
+                        indent_guides: None,
                     }),
                 };
                 self.customize_theme("default-dark", customization).await
@@ -806,6 +1051,13 @@ impl ThemeManager {
         ]
     }
     
+    /// Gets the OpenType features themes can enable
+    pub fn get_available_font_features(&self) -> Vec<String> {
+        let mut features: Vec<String> = ["liga", "calt", "zero"].iter().map(|tag| tag.to_string()).collect();
+        features.extend((1..=20).map(|set| format!("ss{:02}", set)));
+        features
+    }
+    
     /// Gets available window style types
     pub fn get_available_window_styles(&self) -> Vec<WindowStyleType> {
         vec![
@@ -813,6 +1065,8 @@ impl ThemeManager {
             WindowStyleType::Windows,
             WindowStyleType::Terminal,
             WindowStyleType::Clean,
+            WindowStyleType::VSCode,
+            WindowStyleType::JetBrains,
         ]
     }
     
@@ -822,9 +1076,33 @@ impl ThemeManager {
             BackgroundType::Solid,
             BackgroundType::Gradient,
             BackgroundType::Pattern,
+            BackgroundType::Image,
         ]
     }
     
+    /// Gets the shapes gradient backgrounds can take
+    pub fn get_available_gradient_kinds(&self) -> Vec<GradientKind> {
+        vec![GradientKind::Linear, GradientKind::Radial, GradientKind::Conic]
+    }
+    
+    /// Gets the patterns backgrounds can be covered with
+    pub fn get_available_pattern_kinds(&self) -> Vec<PatternKind> {
+        vec![
+            PatternKind::Dots,
+            PatternKind::Grid,
+            PatternKind::DiagonalStripes,
+            PatternKind::Checkerboard,
+            PatternKind::Waves,
+            PatternKind::Topographic,
+            PatternKind::Grain,
+        ]
+    }
+    
+    /// Gets the ways background images can fill their area
+    pub fn get_available_image_fits(&self) -> Vec<ImageFit> {
+        vec![ImageFit::Cover, ImageFit::Contain, ImageFit::Tile]
+    }
+    
     /// Validates a color string and suggests corrections if invalid
     pub fn validate_and_suggest_color(&self, color: &str) -> ColorValidationResult {
         if Theme::is_valid_color(color) {
@@ -888,7 +1166,10 @@ pub struct ThemeCustomization {
     pub id: Option<String>,
     pub name: Option<String>,
     pub background: Option<BackgroundCustomization>,
+    pub backdrop: Option<BackgroundCustomization>,
     pub syntax: Option<SyntaxCustomization>,
+    pub font_styles: Option<SyntaxFontStyles>, // Replaces the theme's bold/italic/underline styles
+    pub ansi_colors: Option<AnsiPalette>, // Replaces the theme's terminal colors
     pub window: Option<WindowCustomization>,
     pub typography: Option<TypographyCustomization>,
 }
@@ -899,6 +1180,9 @@ pub struct BackgroundCustomization {
     pub primary: Option<String>,
     pub secondary: Option<String>,
     pub opacity: Option<f32>,
+    pub gradient: Option<GradientStyle>,
+    pub pattern: Option<PatternStyle>,
+    pub image: Option<BackgroundImage>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -911,6 +1195,8 @@ pub struct SyntaxCustomization {
     pub function: Option<String>,
     pub variable: Option<String>,
     pub type_name: Option<String>,
+    #[serde(default)]
+    pub text: Option<String>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -920,7 +1206,14 @@ pub struct WindowCustomization {
     pub title: Option<String>,
     pub show_controls: Option<bool>,
     pub border_radius: Option<f32>,
-    pub shadow: Option<bool>,
+    /// Replaces the shadow; `false` or `null` removes it, and `true` sets the default one
+    #[serde(default, deserialize_with = "deserialize_shadow_change")]
+    pub shadow: Option<Option<ShadowStyle>>,
+    /// Replaces the border
+    pub border: Option<BorderStyle>,
+    pub glass: Option<GlassStyle>,
+    /// Replaces the editor tabs
+    pub tabs: Option<Vec<WindowTab>>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -930,6 +1223,11 @@ pub struct TypographyCustomization {
     pub line_height: Option<f32>,
     pub letter_spacing: Option<f32>,
     pub show_line_numbers: Option<bool>,
+    /// Replaces the theme's OpenType features
+    pub font_features: Option<Vec<String>>,
+    pub tab_width: Option<u32>,
+    pub render_whitespace: Option<bool>,
+    pub indent_guides: Option<bool>,
 }
 
 /// Predefined theme presets for quick customization
@@ -951,6 +1249,7 @@ pub struct ColorValidationResult {
 #[cfg(test)]
 mod customization_tests {
     use super::*;
+    use crate::models::theme::ColorStop;
     
     #[tokio::test]
     async fn test_theme_customization() {
@@ -964,7 +1263,11 @@ mod customization_tests {
                 primary: Some("#1a1a1a".to_string()),
                 secondary: Some("#2a2a2a".to_string()),
                 opacity: Some(0.9),
+                gradient: None,
+                pattern: None,
+                image: None,
             }),
+            backdrop: None,
             syntax: Some(SyntaxCustomization {
                 keyword: Some("#ff6b6b".to_string()),
                 string: Some("#4ecdc4".to_string()),
@@ -974,7 +1277,10 @@ mod customization_tests {
                 function: Some("#9b59b6".to_string()),
                 variable: Some("#3498db".to_string()),
                 type_name: Some("#e74c3c".to_string()),
+                text: None,
             }),
+            font_styles: None,
+            ansi_colors: None,
             window: None,
             typography: Some(TypographyCustomization {
                 font_family: Some("JetBrains Mono".to_string()),
@@ -982,6 +1288,10 @@ mod customization_tests {
                 line_height: Some(1.6),
                 letter_spacing: Some(0.1),
                 show_line_numbers: Some(true),
+                font_features: Some(vec!["calt".to_string(), "ss01".to_string(), "zero".to_string()]),
+                tab_width: Some(8),
+                render_whitespace: Some(true),
+                indent_guides: Some(true),
             }),
         };
         
@@ -995,6 +1305,9 @@ mod customization_tests {
         assert_eq!(custom_theme.background.primary, "#1a1a1a");
         assert_eq!(custom_theme.syntax.keyword, "#ff6b6b");
         assert_eq!(custom_theme.typography.font_family, "JetBrains Mono");
+        assert_eq!(custom_theme.typography.font_features, vec!["calt", "ss01", "zero"]);
+        assert_eq!(custom_theme.typography.tab_width, 8);
+        assert!(custom_theme.typography.render_whitespace && custom_theme.typography.indent_guides);
     }
     
     #[tokio::test]
@@ -1015,6 +1328,179 @@ mod customization_tests {
         assert_eq!(theme.background.primary, "#000000");
     }
     
+    #[tokio::test]
+    async fn test_font_feature_validation() {
+        let manager = ThemeManager::new();
+        let mut theme = Theme::default_dark();
+        assert_eq!(theme.typography.font_features, vec!["liga", "calt"]);
+
+        theme.typography.font_features = vec!["ss03".to_string(), "zero".to_string()];
+        assert!(manager.validate_theme(&theme).is_ok());
+
+        theme.typography.font_features.push("kern".to_string());
+        assert!(manager.validate_theme(&theme).is_err());
+
+        let customization = ThemeCustomization {
+            id: None,
+            name: None,
+            background: None,
+            backdrop: None,
+            syntax: None,
+            font_styles: None,
+            ansi_colors: None,
+            window: None,
+            typography: Some(TypographyCustomization {
+                font_family: None,
+                font_size: None,
+                line_height: None,
+                letter_spacing: None,
+                show_line_numbers: None,
+                font_features: Some(vec!["ss21".to_string()]),
+                tab_width: None,
+                render_whitespace: None,
+                indent_guides: None,
+            }),
+        };
+        assert!(manager.customize_theme("default-dark", customization).await.is_err());
+    }
+
+    #[test]
+    fn test_tab_width_validation() {
+        let manager = ThemeManager::new();
+        let mut theme = Theme::default_dark();
+        assert_eq!(theme.typography.tab_width, 4);
+
+        theme.typography.tab_width = 0;
+        assert!(manager.validate_theme(&theme).is_err());
+
+        theme.typography.tab_width = TypographyStyle::MAX_TAB_WIDTH;
+        assert!(manager.validate_theme(&theme).is_ok());
+    }
+
+    #[test]
+    fn test_gradient_validation_and_stop_offsets() {
+        let manager = ThemeManager::new();
+        let mut theme = Theme::default_dark();
+        let stop = |color: &str, offset: Option<f32>| ColorStop {
+            color: color.to_string(),
+            offset,
+        };
+        let mut gradient = GradientStyle {
+            kind: GradientKind::Conic,
+            angle: 45.0,
+            center_x: 0.5,
+            center_y: 0.5,
+            stops: vec![
+                stop("#ff0000", None),
+                stop("#00ff00", None),
+                stop("#0000ff", Some(0.8)),
+                stop("#ffffff", None),
+            ],
+        };
+        assert_eq!(gradient.resolved_offsets(), vec![0.0, 0.4, 0.8, 1.0]);
+
+        theme.background.bg_type = BackgroundType::Gradient;
+        theme.background.gradient = Some(gradient.clone());
+        assert!(manager.validate_theme(&theme).is_ok());
+
+        gradient.stops[1].offset = Some(0.9);
+        theme.background.gradient = Some(gradient.clone());
+        assert!(manager.validate_theme(&theme).is_err());
+
+        gradient.stops.truncate(1);
+        theme.background.gradient = Some(gradient);
+        assert!(manager.validate_theme(&theme).is_err());
+    }
+
+    #[test]
+    fn test_pattern_validation() {
+        let manager = ThemeManager::new();
+        let mut theme = Theme::default_dark();
+        theme.background.bg_type = BackgroundType::Pattern;
+        theme.background.pattern = Some(PatternStyle {
+            kind: PatternKind::Waves,
+            scale: 2.0,
+            rotation: 30.0,
+            color: Some("#ffffff".to_string()),
+            opacity: 0.2,
+        });
+        assert!(manager.validate_theme(&theme).is_ok());
+
+        let invalid = [
+            PatternStyle { scale: 0.0, ..PatternStyle::default() },
+            PatternStyle { opacity: 1.5, ..PatternStyle::default() },
+            PatternStyle { color: Some("white".to_string()), ..PatternStyle::default() },
+        ];
+        for pattern in invalid {
+            theme.background.pattern = Some(pattern);
+            assert!(manager.validate_theme(&theme).is_err());
+        }
+    }
+
+    #[test]
+    fn test_image_background_validation() {
+        let manager = ThemeManager::new();
+        let mut theme = Theme::default_dark();
+        theme.background.bg_type = BackgroundType::Image;
+        assert!(manager.validate_theme(&theme).is_err());
+
+        let image = BackgroundImage {
+            file_id: "0f8fad5b-d9cb-469f-a165-70867728950e".to_string(),
+            fit: ImageFit::Tile,
+            blur: 12.0,
+            overlay: None,
+        };
+        theme.background.image = Some(image.clone());
+        assert!(manager.validate_theme(&theme).is_ok());
+
+        let invalid = [
+            BackgroundImage { file_id: "../../etc/passwd".to_string(), ..image.clone() },
+            BackgroundImage { blur: BackgroundImage::MAX_BLUR + 1.0, ..image.clone() },
+        ];
+        for image in invalid {
+            theme.background.image = Some(image);
+            assert!(manager.validate_theme(&theme).is_err());
+        }
+    }
+
+    #[test]
+    fn test_ansi_palette_validation() {
+        let manager = ThemeManager::new();
+        let mut theme = Theme::default_dark();
+        assert!(manager.validate_theme(&theme).is_ok());
+
+        theme.ansi_colors.bright_cyan = "cyan".to_string();
+        assert!(manager.validate_theme(&theme).is_err());
+
+        // Themes saved before palettes existed get the default one
+        let mut json = serde_json::to_value(Theme::default_dark()).unwrap();
+        json.as_object_mut().unwrap().remove("ansi_colors");
+        let theme: Theme = serde_json::from_value(json).unwrap();
+        assert_eq!(theme.ansi_colors, AnsiPalette::default());
+    }
+
+    #[test]
+    fn test_tab_validation() {
+        let manager = ThemeManager::new();
+        let mut theme = Theme::default_dark();
+        let tab = |name: &str, active: bool| WindowTab { name: name.to_string(), active };
+
+        theme.window.style_type = WindowStyleType::VSCode;
+        theme.window.tabs = vec![tab("main.rs", true), tab("lib.rs", false)];
+        assert!(manager.validate_theme(&theme).is_ok());
+
+        let invalid = [
+            vec![tab("main.rs", true), tab("lib.rs", true)],
+            vec![tab(" ", false)],
+            vec![tab(&"a".repeat(WindowTab::MAX_NAME_LENGTH + 1), false)],
+            vec![tab("mod.rs", false); WindowTab::MAX_TABS + 1],
+        ];
+        for tabs in invalid {
+            theme.window.tabs = tabs;
+            assert!(manager.validate_theme(&theme).is_err());
+        }
+    }
+
     #[tokio::test]
     async fn test_invalid_customization() {
         let manager = ThemeManager::new();
@@ -1027,8 +1513,14 @@ mod customization_tests {
                 primary: Some("invalid-color".to_string()), // Invalid color
                 secondary: None,
                 opacity: Some(1.0),
+                gradient: None,
+                pattern: None,
+                image: None,
             }),
+            backdrop: None,
             syntax: None,
+            font_styles: None,
+            ansi_colors: None,
             window: None,
             typography: None,
         };
@@ -1036,7 +1528,110 @@ mod customization_tests {
         let result = manager.customize_theme("default-dark", invalid_customization).await;
         assert!(result.is_err());
     }
-    
+
+    #[tokio::test]
+    async fn test_shadow_customization() {
+        let manager = ThemeManager::new();
+
+        let window_customization = |shadow: Option<Option<ShadowStyle>>, border: Option<BorderStyle>| ThemeCustomization {
+            id: Some("shadowed".to_string()),
+            name: None,
+            background: None,
+            backdrop: None,
+            syntax: None,
+            font_styles: None,
+            ansi_colors: None,
+            window: Some(WindowCustomization {
+                style_type: None,
+                show_title_bar: None,
+                title: None,
+                show_controls: None,
+                border_radius: None,
+                shadow,
+                border,
+                glass: None,
+                tabs: None,
+            }),
+            typography: None,
+        };
+        let shadow = |blur_radius: f32| ShadowStyle {
+            offset_x: -4.0,
+            blur_radius,
+            color: "#3366ff80".to_string(),
+            ..ShadowStyle::default()
+        };
+        let border = BorderStyle { width: 2.0, color: "#ffffff33".to_string() };
+
+        // High contrast has no shadow or border; both are set as given
+        let theme = manager
+            .customize_theme("high-contrast", window_customization(Some(Some(shadow(12.0))), Some(border.clone())))
+            .await
+            .unwrap();
+        assert_eq!(theme.window.shadow, Some(shadow(12.0)));
+        assert_eq!(theme.window.border, Some(border));
+
+        // Leaving the shadow out keeps the theme's; turning it off removes it
+        let theme = manager.customize_theme("vscode-dark", window_customization(None, None)).await.unwrap();
+        assert_eq!(theme.window.shadow, Some(ShadowStyle::default()));
+        let theme = manager.customize_theme("vscode-dark", window_customization(Some(None), None)).await.unwrap();
+        assert_eq!(theme.window.shadow, None);
+
+        let result = manager.customize_theme("high-contrast", window_customization(Some(Some(shadow(-1.0))), None)).await;
+        assert!(result.is_err());
+        let invalid_border = BorderStyle { width: 1.0, color: "white".to_string() };
+        let result = manager.customize_theme("high-contrast", window_customization(None, Some(invalid_border))).await;
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_window_customization_reads_legacy_boolean_shadow() {
+        let read = |json: serde_json::Value| serde_json::from_value::<WindowCustomization>(json).unwrap().shadow;
+
+        assert_eq!(read(serde_json::json!({})), None);
+        assert_eq!(read(serde_json::json!({ "shadow": false })), Some(None));
+        assert_eq!(read(serde_json::json!({ "shadow": null })), Some(None));
+        assert_eq!(read(serde_json::json!({ "shadow": true })), Some(Some(ShadowStyle::default())));
+        assert_eq!(
+            read(serde_json::json!({
+                "shadow": { "offset_x": 2.0, "offset_y": 4.0, "blur_radius": 8.0, "spread": 0.0, "color": "#00000040" }
+            })),
+            Some(Some(ShadowStyle {
+                offset_x: 2.0,
+                offset_y: 4.0,
+                blur_radius: 8.0,
+                spread: 0.0,
+                color: "#00000040".to_string(),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_legacy_boolean_shadow() {
+        let mut json = serde_json::to_value(Theme::default_dark()).unwrap();
+
+        json["window"]["shadow"] = serde_json::json!(true);
+        let theme: Theme = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(theme.window.shadow, Some(ShadowStyle::default()));
+
+        json["window"]["shadow"] = serde_json::json!(false);
+        let theme: Theme = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(theme.window.shadow, None);
+
+        json["window"].as_object_mut().unwrap().remove("shadow");
+        let theme: Theme = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(theme.window.shadow, None);
+
+        json["window"]["shadow"] = serde_json::json!({
+            "offset_x": 2.0,
+            "offset_y": 4.0,
+            "blur_radius": 8.0,
+            "spread": 0.0,
+            "color": "#00000040"
+        });
+        let theme: Theme = serde_json::from_value(json).unwrap();
+        assert_eq!(theme.window.shadow.unwrap().blur_radius, 8.0);
+    }
+
     #[test]
     fn test_color_validation() {
         let manager = ThemeManager::new();
@@ -1065,11 +1660,24 @@ mod customization_tests {
         assert!(fonts.contains(&"Fira Code".to_string()));
         
         let window_styles = manager.get_available_window_styles();
-        assert_eq!(window_styles.len(), 4);
+        assert_eq!(window_styles.len(), 6);
         assert!(window_styles.contains(&WindowStyleType::MacOS));
         
+        let features = manager.get_available_font_features();
+        assert_eq!(features.len(), 23);
+        assert!(features.iter().all(|feature| TypographyStyle::is_supported_feature(feature)));
+        assert!(features.contains(&"ss20".to_string()));
+        
         let bg_types = manager.get_available_background_types();
-        assert_eq!(bg_types.len(), 3);
+        assert_eq!(bg_types.len(), 4);
         assert!(bg_types.contains(&BackgroundType::Solid));
+
+        let gradient_kinds = manager.get_available_gradient_kinds();
+        assert_eq!(gradient_kinds.len(), 3);
+        assert!(gradient_kinds.contains(&GradientKind::Radial));
+
+        let pattern_kinds = manager.get_available_pattern_kinds();
+        assert_eq!(pattern_kinds.len(), 7);
+        assert!(pattern_kinds.contains(&PatternKind::Topographic));
     }
 }
\ No newline at end of file
@@ -0,0 +1,249 @@
+use crate::services::graphemes::{self, display_width, grapheme_width};
+use crate::services::syntax_highlighter::{self, HighlightedLine, HighlightedSegment};
+use serde::{Deserialize, Serialize};
+
+/// Gutter marker drawn in front of continuation rows
+pub const CONTINUATION_MARKER: char = '↪';
+
+/// What happens to lines wider than the code area
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum WrapMode {
+    /// Long lines continue on extra rows, indented like the line they belong to
+    #[default]
+    Wrap,
+    /// The canvas grows to fit the longest line
+    Widen,
+}
+
+/// Splits lines wider than `columns` grid columns into rows at token boundaries.
+/// Continuation rows keep the line's number and repeat its leading whitespace.
+pub fn wrap_lines(lines: &[HighlightedLine], columns: usize) -> Vec<HighlightedLine> {
+    let columns = columns.max(1);
+    let mut rows = Vec::with_capacity(lines.len());
+
+    for line in lines {
+        let pieces: Vec<HighlightedSegment> = line
+            .segments
+            .iter()
+            .flat_map(|segment| {
+                let text: String = segment
+                    .text
+                    .chars()
+                    .filter(|ch| *ch != '\n' && *ch != '\r')
+                    .collect();
+                syntax_highlighter::split_tokens(&text)
+                    .into_iter()
+                    .map(|token| HighlightedSegment {
+                        text: token.to_string(),
+                        style: segment.style.clone(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let length: usize = pieces.iter().map(|piece| display_width(&piece.text)).sum();
+        if length <= columns {
+            rows.push(line.clone());
+            continue;
+        }
+
+        // Deep indentation is capped so continuation rows always have room for text
+        let indent = pieces
+            .iter()
+            .flat_map(|piece| piece.text.chars())
+            .take_while(|ch| ch.is_whitespace())
+            .count()
+            .min(columns / 2);
+
+        let mut row = RowBuilder::new(line, false);
+        for piece in pieces {
+            let piece_length = display_width(&piece.text);
+
+            if row.width + piece_length > columns && !row.is_blank(indent) {
+                // Whitespace at a break is dropped rather than carried onto the next row
+                row.trim_end();
+                rows.push(row.finish());
+                row = RowBuilder::new(line, true);
+                row.push_indent(indent, &piece);
+                if piece.text.trim().is_empty() {
+                    continue;
+                }
+            }
+
+            // Tokens wider than a whole row are split between graphemes wherever the row ends
+            let mut rest: Vec<&str> = graphemes::graphemes(&piece.text).collect();
+            while row.width + rest.iter().map(|g| grapheme_width(g)).sum::<usize>() > columns {
+                let mut fits = 0;
+                let mut width = row.width;
+                while fits < rest.len() && width + grapheme_width(rest[fits]) <= columns {
+                    width += grapheme_width(rest[fits]);
+                    fits += 1;
+                }
+                // A wide grapheme left without room moves on whole, unless the row has nothing else
+                let take = if fits == 0 && row.is_blank(indent) { 1 } else { fits };
+                if take > 0 {
+                    row.push(&piece, rest.drain(..take).collect());
+                }
+                rows.push(row.finish());
+                row = RowBuilder::new(line, true);
+                row.push_indent(indent, &piece);
+            }
+            if !rest.is_empty() {
+                row.push(&piece, rest.concat());
+            }
+        }
+
+        if !row.is_blank(indent) {
+            rows.push(row.finish());
+        }
+    }
+
+    rows
+}
+
+/// Accumulates the segments of one row, merging neighbours that share a style
+struct RowBuilder<'a> {
+    line: &'a HighlightedLine,
+    continuation: bool,
+    segments: Vec<HighlightedSegment>,
+    width: usize,
+}
+
+impl<'a> RowBuilder<'a> {
+    fn new(line: &'a HighlightedLine, continuation: bool) -> Self {
+        RowBuilder {
+            line,
+            continuation,
+            segments: Vec::new(),
+            width: 0,
+        }
+    }
+
+    /// Repeats the line's indentation at the start of a continuation row
+    fn push_indent(&mut self, indent: usize, like: &HighlightedSegment) {
+        if indent > 0 {
+            self.push(like, " ".repeat(indent));
+        }
+    }
+
+    fn push(&mut self, like: &HighlightedSegment, text: String) {
+        self.width += display_width(&text);
+
+        match self.segments.last_mut() {
+            Some(last) if last.style == like.style => last.text.push_str(&text),
+            _ => self.segments.push(HighlightedSegment {
+                text,
+                style: like.style.clone(),
+            }),
+        }
+    }
+
+    /// Drops whitespace from the end of the row
+    fn trim_end(&mut self) {
+        while let Some(last) = self.segments.last_mut() {
+            let trimmed = last.text.trim_end().len();
+            self.width -= display_width(&last.text[trimmed..]);
+            last.text.truncate(trimmed);
+
+            if !last.text.is_empty() {
+                break;
+            }
+            self.segments.pop();
+        }
+    }
+
+    /// True while the row holds nothing but its indentation
+    fn is_blank(&self, indent: usize) -> bool {
+        self.width <= indent && self.continuation || self.width == 0
+    }
+
+    fn finish(self) -> HighlightedLine {
+        HighlightedLine {
+            segments: self.segments,
+            line_number: self.line.line_number,
+            gutter: if self.continuation {
+                None
+            } else {
+                self.line.gutter.clone()
+            },
+            continuation: self.continuation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::syntax_highlighter::SegmentStyle;
+
+    fn line(number: usize, text: &str) -> HighlightedLine {
+        HighlightedLine {
+            segments: vec![HighlightedSegment {
+                text: text.to_string(),
+                style: SegmentStyle {
+                    color: "#ffffff".to_string(),
+                    bold: false,
+                    italic: false,
+                    underline: false,
+                    background: None,
+                    redacted: false,
+                },
+            }],
+            line_number: number,
+            gutter: None,
+            continuation: false,
+        }
+    }
+
+    fn texts(rows: &[HighlightedLine]) -> Vec<String> {
+        rows.iter()
+            .map(|row| row.segments.iter().map(|segment| segment.text.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_short_lines_are_untouched() {
+        let lines = vec![line(1, "let a = 1;\n"), line(2, "")];
+        let rows = wrap_lines(&lines, 20);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].segments[0].text, "let a = 1;\n");
+        assert!(!rows[0].continuation);
+    }
+
+    #[test]
+    fn test_wraps_at_token_boundaries_keeping_indentation() {
+        let lines = vec![line(7, "    let total = first + second + third;\n")];
+        let rows = wrap_lines(&lines, 24);
+
+        assert_eq!(
+            texts(&rows),
+            vec!["    let total = first +", "    second + third;"]
+        );
+        assert!(rows.iter().all(|row| row.line_number == 7));
+        assert!(!rows[0].continuation && rows[1].continuation);
+    }
+
+    #[test]
+    fn test_tokens_wider_than_a_row_are_split() {
+        let lines = vec![line(1, "x = \"abcdefghijklmnop\"")];
+        let rows = wrap_lines(&lines, 8);
+
+        assert!(rows.iter().all(|row| {
+            row.segments.iter().map(|segment| segment.text.chars().count()).sum::<usize>() <= 8
+        }));
+        let joined: String = texts(&rows).concat();
+        assert_eq!(joined.replace(' ', ""), "x=\"abcdefghijklmnop\"");
+    }
+
+    #[test]
+    fn test_wide_characters_wrap_by_display_width() {
+        let lines = vec![line(1, "// 漢字漢字漢字漢字")];
+        let rows = wrap_lines(&lines, 7);
+
+        assert_eq!(texts(&rows), vec!["//", "漢字漢", "字漢字", "漢字"]);
+        assert!(rows.iter().all(|row| {
+            row.segments.iter().map(|segment| display_width(&segment.text)).sum::<usize>() <= 7
+        }));
+    }
+}
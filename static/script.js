@@ -759,10 +759,17 @@ class CodeSnippetDesigner {
           this.currentLanguage === "auto" ? "text" : this.currentLanguage,
         theme: this.currentTheme,
         options: {
-          format: document.getElementById("export-format")?.value || "png",
+          format:
+            { png: "PNG", jpg: "JPEG", svg: "SVG" }[
+              document.getElementById("export-format")?.value
+            ] || "PNG",
           resolution:
-            document.getElementById("export-resolution")?.value || "1x",
-          quality: document.getElementById("export-quality")?.value || 90,
+            { "1x": "Standard", "2x": "High", "3x": "Ultra" }[
+              document.getElementById("export-resolution")?.value
+            ] || "Standard",
+          quality: parseInt(
+            document.getElementById("export-quality")?.value || 90,
+          ),
           background_type:
             document.getElementById("background-type")?.value || "solid",
           background_color_1:
@@ -776,7 +783,17 @@ class CodeSnippetDesigner {
           font_size: parseInt(
             document.getElementById("font-size")?.value || 14,
           ),
-          padding: parseInt(document.getElementById("padding")?.value || 32),
+          padding: (() => {
+            const padding = parseInt(
+              document.getElementById("padding")?.value || 32,
+            );
+            return {
+              top: padding,
+              right: padding,
+              bottom: padding,
+              left: padding,
+            };
+          })(),
         },
       };
 
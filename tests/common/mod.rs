@@ -1,6 +1,9 @@
-use axum::Router;
+#![allow(dead_code)]
+
+use axum::{Extension, Router};
 use std::sync::Arc;
 use tempfile::TempDir;
+use tower_http::services::{ServeDir, ServeFile};
 
 // Re-export the main app modules for testing
 use code_snippet_designer::{AppState, handlers, services, utils};
@@ -41,6 +44,14 @@ pub async fn setup_test_app() -> Router {
         }
     );
     
+    let export_service = Arc::new(
+        services::export_service::ExportService::new().expect("Failed to create export service"),
+    );
+    let download_service = services::download_service::DownloadService::new(
+        export_service,
+        Arc::new(storage_service.clone()),
+    );
+
     // Create app state
     let app_state = AppState {
         config: Arc::new(config),
@@ -48,6 +59,7 @@ pub async fn setup_test_app() -> Router {
         session_manager: Arc::new(session_manager),
         cache_manager: Arc::new(cache_manager),
         rate_limiter: Arc::new(rate_limiter),
+        download_service: Arc::new(download_service),
     };
     
     // Build router (simplified version without middleware for testing)
@@ -71,7 +83,11 @@ pub async fn setup_test_app() -> Router {
         .route("/api/generate/download/:download_id", axum::routing::get(handlers::generate::download_file))
         .route("/api/generate/options", axum::routing::get(handlers::generate::get_export_options))
         .route("/api/generate/stats", axum::routing::get(handlers::generate::get_download_stats))
+        .nest_service("/static", ServeDir::new("static"))
+        .route_service("/", ServeFile::new("static/index.html"))
         .with_state(app_state)
+        // Keep the storage directory alive until the last clone of the app is dropped
+        .layer(Extension(Arc::new(temp_dir)))
 }
 
 /// Create a test PNG image (1x1 pixel)
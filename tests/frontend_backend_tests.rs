@@ -58,7 +58,7 @@ async fn test_complete_user_workflow() {
     let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
     let languages_json: Value = serde_json::from_slice(&body).unwrap();
     let languages = languages_json["languages"].as_array().unwrap();
-    assert!(languages.len() > 0);
+    assert!(!languages.is_empty());
     
     // Step 3: User loads available themes
     let request = Request::builder()
@@ -73,7 +73,7 @@ async fn test_complete_user_workflow() {
     let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
     let themes_json: Value = serde_json::from_slice(&body).unwrap();
     let themes = themes_json["themes"].as_array().unwrap();
-    assert!(themes.len() > 0);
+    assert!(!themes.is_empty());
     let selected_theme = &themes[0];
     
     // Step 4: User pastes code and processes it
@@ -108,7 +108,7 @@ async fn test_complete_user_workflow() {
             serde_json::to_string(&json!({
                 "code": code,
                 "language": detected_language,
-                "theme": selected_theme,
+                "theme": selected_theme["id"],
                 "export_options": {
                     "format": "PNG",
                     "quality": 90
@@ -143,7 +143,7 @@ async fn test_theme_customization_workflow() {
     assert_eq!(response.status(), StatusCode::OK);
     
     let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
-    let mut theme: Value = serde_json::from_slice(&body).unwrap();
+    let theme: Value = serde_json::from_slice(&body).unwrap();
     
     // Step 2: Get customization options
     let request = Request::builder()
@@ -157,21 +157,26 @@ async fn test_theme_customization_workflow() {
     
     let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
     let options: Value = serde_json::from_slice(&body).unwrap();
-    assert!(options["background_types"].is_array());
-    assert!(options["window_styles"].is_array());
+    assert!(options["options"]["background_types"].is_array());
+    assert!(options["options"]["window_styles"].is_array());
+    assert_eq!(options["options"]["gradient"]["kinds"].as_array().unwrap().len(), 3);
+    assert_eq!(options["options"]["pattern"]["kinds"].as_array().unwrap().len(), 7);
     
     // Step 3: Customize theme
-    if let Some(background) = theme.get_mut("background") {
-        if let Some(primary) = background.get_mut("primary") {
-            *primary = json!("#1a1a1a");
+    let customize_request = json!({
+        "base_theme_id": theme["theme"]["id"],
+        "customization": {
+            "background": {
+                "primary": "#1a1a1a"
+            }
         }
-    }
+    });
     
     let request = Request::builder()
         .uri("/api/themes/customize")
         .method("POST")
         .header(header::CONTENT_TYPE, "application/json")
-        .body(Body::from(serde_json::to_string(&theme).unwrap()))
+        .body(Body::from(serde_json::to_string(&customize_request).unwrap()))
         .unwrap();
     
     let response = app.clone().oneshot(request).await.unwrap();
@@ -180,10 +185,31 @@ async fn test_theme_customization_workflow() {
     let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
     let customized_theme: Value = serde_json::from_slice(&body).unwrap();
     assert_eq!(
-        customized_theme["background"]["primary"].as_str().unwrap(),
+        customized_theme["theme"]["background"]["primary"].as_str().unwrap(),
         "#1a1a1a"
     );
     
+    // Step 4: Generate an image with the customized theme
+    let request = Request::builder()
+        .uri("/api/generate")
+        .method("POST")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            serde_json::to_string(&json!({
+                "code": "fn main() {}",
+                "language": "rust",
+                "theme": customized_theme["theme"],
+                "export_options": {
+                    "format": "PNG",
+                    "quality": 90
+                }
+            })).unwrap()
+        ))
+        .unwrap();
+    
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    
     println!("Theme customization workflow test passed");
 }
 
@@ -297,7 +323,19 @@ async fn test_export_format_options() {
     assert!(options["quality_range"].is_object());
     
     let formats = options["formats"].as_array().unwrap();
-    assert!(formats.len() > 0);
+    assert!(!formats.is_empty());
+    assert!(formats.contains(&json!("WEBP")));
+    assert!(formats.contains(&json!("AVIF")));
+    assert!(options["lossless_formats"].as_array().unwrap().contains(&json!("WEBP")));
+    
+    let presets = options["canvas_presets"].as_array().unwrap();
+    assert_eq!(presets.len(), 6);
+    assert!(presets.contains(&json!({
+        "preset": "OpenGraph",
+        "label": "Open Graph image (1200×630)",
+        "width": 1200,
+        "height": 630
+    })));
     
     println!("Export format options: {:?}", formats);
     println!("Export format options test passed");
@@ -359,7 +397,7 @@ async fn test_session_persistence() {
             serde_json::to_string(&json!({
                 "code": "fn test() {}",
                 "language": "rust",
-                "theme": theme,
+                "theme": theme["theme"]["id"],
                 "export_options": {
                     "format": "PNG",
                     "quality": 90
@@ -261,7 +261,7 @@ async fn test_api_throughput() {
     // Send 50 requests as fast as possible
     let mut handles = vec![];
     
-    for i in 0..request_count {
+    for _i in 0..request_count {
         let app_clone = app.clone();
         let handle = tokio::spawn(async move {
             let request = Request::builder()